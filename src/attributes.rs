@@ -0,0 +1,94 @@
+//! Typed attribute values shared by the attribute tools.
+//!
+//! Primitive values (boolean, number, string) are plain JSON. Roblox datatypes use
+//! `{"type": "Vector3", "value": [x, y, z]}` so they survive the trip through
+//! `HttpService:JSONDecode` on the plugin side.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+// Roblox limits attribute names to 100 characters
+const MAX_ATTRIBUTE_NAME_LENGTH: usize = 100;
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Typed(TypedAttributeValue),
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+#[serde(tag = "type", content = "value")]
+pub enum TypedAttributeValue {
+    /// [x, y]
+    Vector2([f64; 2]),
+    /// [x, y, z]
+    Vector3([f64; 3]),
+    /// [r, g, b] with components from 0 to 1
+    Color3([f64; 3]),
+    /// [scale, offset]
+    UDim([f64; 2]),
+    /// [x scale, x offset, y scale, y offset]
+    UDim2([f64; 4]),
+    /// [min, max]
+    NumberRange([f64; 2]),
+    /// BrickColor name (e.g., "Bright red")
+    BrickColor(String),
+}
+
+impl AttributeValue {
+    pub fn validate(&self) -> Result<(), String> {
+        let components: &[f64] = match self {
+            Self::Number(n) => std::slice::from_ref(n),
+            Self::Typed(TypedAttributeValue::Vector2(v)) => v,
+            Self::Typed(TypedAttributeValue::Vector3(v)) => v,
+            Self::Typed(TypedAttributeValue::Color3(v)) => {
+                if v.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                    return Err("Color3 components must be between 0 and 1".to_string());
+                }
+                v
+            }
+            Self::Typed(TypedAttributeValue::UDim(v)) => v,
+            Self::Typed(TypedAttributeValue::UDim2(v)) => v,
+            Self::Typed(TypedAttributeValue::NumberRange(v)) => {
+                if v[0] > v[1] {
+                    return Err("NumberRange min must not be greater than max".to_string());
+                }
+                v
+            }
+            Self::Bool(_) | Self::String(_) | Self::Typed(TypedAttributeValue::BrickColor(_)) => {
+                &[]
+            }
+        };
+
+        if components.iter().any(|c| !c.is_finite()) {
+            return Err("Attribute values must be finite numbers".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Apply the same naming rules Instance:SetAttribute enforces, so bad names fail before the round trip
+pub fn validate_attribute_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Attribute name cannot be empty".to_string());
+    }
+    if name.len() > MAX_ATTRIBUTE_NAME_LENGTH {
+        return Err(format!(
+            "Attribute name '{name}' is longer than {MAX_ATTRIBUTE_NAME_LENGTH} characters"
+        ));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!(
+            "Attribute name '{name}' may only contain letters, digits and underscores"
+        ));
+    }
+    if name.starts_with("RBX") {
+        return Err(format!(
+            "Attribute name '{name}' uses the reserved RBX prefix"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,521 @@
+//! Line coverage for the coverage_start and coverage_report tools.
+//!
+//! coverage_start rewrites each selected script with a counter in front of the first
+//! statement on every line. Counters are inserted on the line they count, so errors and the
+//! report keep the original line numbers. The counts live in the MCPCoverage ModuleScript,
+//! which each Luau VM requires once: the playtest server, and every client, where it hands
+//! its counts to the server through a RemoteFunction. coverage_report gathers them with
+//! server code through MCPServerCodeRunner and puts the original sources back. The run is
+//! kept in a coverage.json file in the place's history directory until then, so the
+//! originals are restored even if the server restarts in between.
+
+use crate::playtest_helper::{self, Helper, StatementVisitor};
+use crate::script_export::{self, CollectedScripts};
+use full_moon::visitors::Visitor;
+use full_moon::LuaVersion;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+pub const HELPER_PATH: &str = "ReplicatedStorage.MCPCoverage";
+
+const SESSION_FILE: &str = "coverage.json";
+
+/// Local holding a script's counts; also marks a source as instrumented
+const COUNTS_LOCAL: &str = "__mcp_coverage";
+
+const HELPER: Helper = Helper {
+    name: "MCPCoverage",
+    table: "Coverage",
+    collect: "snapshot",
+    installer: "coverage_start",
+    client_trigger: "an instrumented script",
+};
+
+const HELPER_BODY: &str = r#"-- Auto-installed by MCP Server for coverage_start/coverage_report
+-- Instrumented scripts count the lines they run here. Each Luau VM (the server and every
+-- client) has its own copy; clients hand theirs to the server through the Collect
+-- RemoteFunction when coverage_report asks.
+local RunService = game:GetService("RunService")
+
+local Coverage = {}
+local counts = {}
+
+-- The line counts of one script, where unrun lines read as 0
+function Coverage.register(path)
+	local lines = counts[path]
+	if not lines then
+		lines = setmetatable({}, {
+			__index = function()
+				return 0
+			end,
+		})
+		counts[path] = lines
+	end
+	return lines
+end
+
+-- Counts as { [path] = { { line, count } } }, a shape remotes and JSON keep intact
+function Coverage.snapshot()
+	local snapshot = {}
+	for path, lines in counts do
+		local entries = {}
+		for line, count in lines do
+			table.insert(entries, { line, count })
+		end
+		snapshot[path] = entries
+	end
+	return snapshot
+end
+"#;
+
+pub fn helper_source() -> String {
+    HELPER.source(HELPER_BODY)
+}
+
+/// An instrumented script and what's needed to report on it and restore it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoveredScript {
+    pub path: String,
+    pub class_name: String,
+    /// Whether it can run on a client: LocalScripts, ModuleScripts and client Scripts
+    pub client: bool,
+    /// Where export_scripts writes the script, used as the LCOV source file
+    pub file: Option<PathBuf>,
+    pub original: String,
+    pub instrumented: String,
+    /// Lines with a counter
+    pub lines: Vec<usize>,
+}
+
+/// The scripts coverage_start instrumented, kept until coverage_report restores them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageSession {
+    pub place_id: u64,
+    pub place_name: String,
+    pub scripts: Vec<CoveredScript>,
+}
+
+/// Scripts to instrument, and the ones left alone with the reason
+pub struct Plan {
+    pub scripts: Vec<CoveredScript>,
+    pub skipped: Vec<String>,
+}
+
+/// Counts from the server and clients, summed per script
+pub struct Hits {
+    pub counts: HashMap<String, BTreeMap<usize, u64>>,
+    pub clients: usize,
+    pub errors: Vec<String>,
+}
+
+pub struct FileCoverage {
+    pub path: String,
+    pub file: Option<PathBuf>,
+    /// Every line with a counter and how often it ran
+    pub lines: Vec<(usize, u64)>,
+}
+
+pub struct CoverageReport {
+    pub files: Vec<FileCoverage>,
+    pub clients: usize,
+    pub errors: Vec<String>,
+}
+
+/// Quote text as a Luau string literal that stays on one line
+fn luau_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 || c == '\u{7f}' => {
+                quoted.push_str(&format!("\\{:03}", c as u32))
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Put a counter before the first statement on each line, and the counts lookup before the
+/// first statement of the script. Returns the new source and the counted lines.
+pub fn instrument(source: &str, path: &str) -> Result<(String, Vec<usize>), String> {
+    let result = full_moon::parse_fallible(source, LuaVersion::luau());
+    if !result.errors().is_empty() {
+        return Err("has Luau syntax errors".to_string());
+    }
+    let mut visitor = StatementVisitor::default();
+    visitor.visit_ast(result.ast());
+
+    let mut statements = visitor.statements;
+    statements.sort_unstable();
+    let Some(&(first, _)) = statements.first() else {
+        return Err("has no statements".to_string());
+    };
+    // Sorted by offset, so each line's statements are together
+    let mut counted: Vec<(usize, usize)> = Vec::new();
+    for (offset, line) in statements {
+        if counted.last().map(|(_, last)| *last) != Some(line) {
+            counted.push((offset, line));
+        }
+    }
+
+    let mut instrumented = source.to_string();
+    for (offset, line) in counted.iter().rev() {
+        instrumented.insert_str(*offset, &format!("{COUNTS_LOCAL}[{line}] += 1; "));
+    }
+    instrumented.insert_str(
+        first,
+        &format!(
+            "local {COUNTS_LOCAL} = require(game:GetService(\"ReplicatedStorage\"):WaitForChild(\"MCPCoverage\")).register({}); ",
+            luau_string(path)
+        ),
+    );
+    let lines = counted.into_iter().map(|(_, line)| line).collect();
+    Ok((instrumented, lines))
+}
+
+/// Instrument every collected script that can be
+pub fn plan(collected: &CollectedScripts) -> Result<Plan, String> {
+    let layout = script_export::layout(collected, &collected.place_name)?;
+    let mut plan = Plan {
+        scripts: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mut paths: Vec<String> = Vec::with_capacity(collected.nodes.len());
+    for (index, node) in collected.nodes.iter().enumerate() {
+        let path = match node.parent {
+            Some(parent) => format!("{}.{}", paths[parent], node.name),
+            None => node.name.clone(),
+        };
+        paths.push(path.clone());
+        let Some(source) = &node.source else {
+            continue;
+        };
+        if source.contains(COUNTS_LOCAL) {
+            plan.skipped.push(format!(
+                "{path}: already instrumented; revert_script restores the source saved before coverage_start"
+            ));
+            continue;
+        }
+        match instrument(source, &path) {
+            Ok((instrumented, lines)) => plan.scripts.push(CoveredScript {
+                path,
+                class_name: node.class_name.clone(),
+                client: node.class_name != "Script"
+                    || node.run_context.as_deref() == Some("Client"),
+                file: layout.node_files.get(index).and_then(|files| {
+                    files
+                        .iter()
+                        .find(|file| {
+                            file.extension()
+                                .is_some_and(|extension| extension == "luau")
+                        })
+                        .cloned()
+                }),
+                original: source.clone(),
+                instrumented,
+                lines,
+            }),
+            Err(reason) => plan.skipped.push(format!("{path}: {reason}")),
+        }
+    }
+    Ok(plan)
+}
+
+impl CoverageSession {
+    /// The run in a place's history directory, if one is active
+    pub fn load(place_dir: &Path) -> Result<Option<Self>, String> {
+        playtest_helper::load_record(place_dir, SESSION_FILE)
+    }
+
+    /// Write the run, or remove it once no scripts are left to restore
+    pub fn save(&self, place_dir: &Path) -> Result<(), String> {
+        if self.scripts.is_empty() {
+            playtest_helper::remove_record(place_dir, SESSION_FILE)
+        } else {
+            playtest_helper::save_record(place_dir, SESSION_FILE, self)
+        }
+    }
+
+    /// Whether any script may run on a client, so the clients are asked for counts too
+    fn has_client_scripts(&self) -> bool {
+        self.scripts.iter().any(|script| script.client)
+    }
+
+    /// Server code that returns the server's and every client's counts as JSON
+    pub fn collect_code(&self) -> String {
+        HELPER.collect_code(None, self.has_client_scripts())
+    }
+}
+
+/// Add one VM's `{ [path] = { { line, count } } }` counts. Empty tables come back from
+/// JSONEncode as arrays, so anything but an object has no counts.
+fn add_counts(counts: &mut HashMap<String, BTreeMap<usize, u64>>, snapshot: &Value) {
+    let Some(scripts) = snapshot.as_object() else {
+        return;
+    };
+    for (path, pairs) in scripts {
+        let lines = counts.entry(path.clone()).or_default();
+        for pair in pairs.as_array().into_iter().flatten() {
+            let line = pair.get(0).and_then(Value::as_u64);
+            let count = pair.get(1).and_then(Value::as_f64);
+            if let (Some(line), Some(count)) = (line, count) {
+                *lines.entry(line as usize).or_default() += count as u64;
+            }
+        }
+    }
+}
+
+/// Parse what collect_code returns
+pub fn parse_hits(json: &str) -> Result<Hits, String> {
+    let collected = HELPER.parse(json)?;
+    let mut hits = Hits {
+        counts: HashMap::new(),
+        clients: collected.clients.len(),
+        errors: collected.errors,
+    };
+    for snapshot in collected.server.iter().chain(&collected.clients) {
+        add_counts(&mut hits.counts, snapshot);
+    }
+    Ok(hits)
+}
+
+impl FileCoverage {
+    fn hit(&self) -> usize {
+        self.lines.iter().filter(|(_, count)| *count > 0).count()
+    }
+
+    /// Unrun lines as ranges of consecutive line numbers, e.g. "4-6, 10"
+    fn uncovered(&self) -> String {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for (line, _) in self.lines.iter().filter(|(_, count)| *count == 0) {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == *line => *end = *line,
+                _ => ranges.push((*line, *line)),
+            }
+        }
+        ranges
+            .iter()
+            .map(|(start, end)| {
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{start}-{end}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn percent(hit: usize, found: usize) -> f64 {
+    if found == 0 {
+        100.0
+    } else {
+        hit as f64 * 100.0 / found as f64
+    }
+}
+
+impl CoverageReport {
+    pub fn new(session: &CoverageSession, hits: &Hits) -> Self {
+        let mut files: Vec<FileCoverage> = session
+            .scripts
+            .iter()
+            .map(|script| {
+                let counts = hits.counts.get(&script.path);
+                FileCoverage {
+                    path: script.path.clone(),
+                    file: script.file.clone(),
+                    lines: script
+                        .lines
+                        .iter()
+                        .map(|line| {
+                            let count = counts.and_then(|counts| counts.get(line)).copied();
+                            (*line, count.unwrap_or(0))
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Self {
+            files,
+            clients: hits.clients,
+            errors: hits.errors.clone(),
+        }
+    }
+
+    fn totals(&self) -> (usize, usize) {
+        self.files.iter().fold((0, 0), |(hit, found), file| {
+            (hit + file.hit(), found + file.lines.len())
+        })
+    }
+
+    /// Overall and per-script percentages, with each script's unrun lines
+    pub fn summary(&self) -> String {
+        let (hit, found) = self.totals();
+        let mut text = format!(
+            "[SUCCESS] Covered {hit} of {found} line(s) ({:.1}%) in {} script(s), with counts from the server and {} client(s)",
+            percent(hit, found),
+            self.files.len(),
+            self.clients
+        );
+        if !self.errors.is_empty() {
+            text.push_str(&format!("\n\nCollection problems ({}):", self.errors.len()));
+            for error in &self.errors {
+                text.push_str(&format!("\n  {error}"));
+            }
+        }
+        text.push('\n');
+        for file in &self.files {
+            text.push_str(&format!(
+                "\n{:>6.1}%  {}/{}  {}",
+                percent(file.hit(), file.lines.len()),
+                file.hit(),
+                file.lines.len(),
+                file.path
+            ));
+            let uncovered = file.uncovered();
+            if !uncovered.is_empty() {
+                text.push_str(&format!("  (not run: {uncovered})"));
+            }
+        }
+        text
+    }
+
+    /// Per-script totals and the count of every counted line
+    pub fn to_json(&self) -> Value {
+        let (hit, found) = self.totals();
+        json!({
+            "lines_found": found,
+            "lines_hit": hit,
+            "percent": percent(hit, found),
+            "clients": self.clients,
+            "errors": self.errors,
+            "files": self.files.iter().map(|file| json!({
+                "path": file.path,
+                "file": file.file.as_ref().map(|file| file.to_string_lossy().replace('\\', "/")),
+                "lines_found": file.lines.len(),
+                "lines_hit": file.hit(),
+                "percent": percent(file.hit(), file.lines.len()),
+                "lines": file.lines.iter().map(|(line, count)| json!({
+                    "line": line,
+                    "hits": count,
+                })).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    /// LCOV tracefile. Source files are where export_scripts writes each script, so tools
+    /// run in an export directory find them.
+    pub fn to_lcov(&self) -> String {
+        let mut lcov = String::new();
+        for file in &self.files {
+            let source_file = file
+                .file
+                .as_ref()
+                .map(|file| file.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_else(|| file.path.clone());
+            lcov.push_str(&format!("TN:\nSF:{source_file}\n"));
+            for (line, count) in &file.lines {
+                lcov.push_str(&format!("DA:{line},{count}\n"));
+            }
+            lcov.push_str(&format!(
+                "LF:{}\nLH:{}\nend_of_record\n",
+                file.lines.len(),
+                file.hit()
+            ));
+        }
+        lcov
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.to_json()).map_err(|e| e.to_string())?;
+        write_file(path, &json)
+    }
+
+    pub fn write_lcov(&self, path: &Path) -> Result<(), String> {
+        write_file(path, &self.to_lcov())
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    std::fs::write(path, contents).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instrument_counts_each_line_in_place() {
+        let source = "local x = 1\nif x then\n\tprint(x) print(x)\nend\nreturn x\n";
+        let (instrumented, lines) = instrument(source, "Shared.Module").unwrap();
+        assert_eq!(lines, vec![1, 2, 3, 5]);
+        assert_eq!(instrumented.lines().count(), source.lines().count());
+        let counted: Vec<&str> = instrumented.lines().collect();
+        assert!(counted[0].starts_with(
+            "local __mcp_coverage = require(game:GetService(\"ReplicatedStorage\"):WaitForChild(\"MCPCoverage\")).register(\"Shared.Module\"); __mcp_coverage[1] += 1; local x = 1"
+        ));
+        assert_eq!(counted[2], "\t__mcp_coverage[3] += 1; print(x) print(x)");
+        assert_eq!(counted[3], "end");
+        assert_eq!(counted[4], "__mcp_coverage[5] += 1; return x");
+        assert!(full_moon::parse_fallible(&instrumented, LuaVersion::luau())
+            .errors()
+            .is_empty());
+    }
+
+    #[test]
+    fn instrument_skips_types_and_rejects_broken_scripts() {
+        let (instrumented, lines) = instrument(
+            "type Point = { x: number }\nlocal p: Point = { x = 1 }\n",
+            "P",
+        )
+        .unwrap();
+        assert_eq!(lines, vec![2]);
+        assert!(instrumented.starts_with("type Point = { x: number }\n"));
+        assert!(instrument("local = 1", "Broken").is_err());
+        assert!(instrument("-- only a comment\n", "Empty").is_err());
+    }
+
+    #[test]
+    fn instrument_quotes_paths_as_luau_strings() {
+        let (instrumented, _) = instrument("return 1", "Workspace.\"Odd\\Name\"\n").unwrap();
+        assert!(instrumented.contains(r#"register("Workspace.\"Odd\\Name\"\010")"#));
+    }
+
+    #[test]
+    fn session_is_kept_on_disk_until_no_scripts_are_left() {
+        let place_dir = std::env::temp_dir().join(format!("coverage-{}", uuid::Uuid::new_v4()));
+        assert!(CoverageSession::load(&place_dir).unwrap().is_none());
+        let mut session = CoverageSession {
+            place_id: 1,
+            place_name: "Place".to_string(),
+            scripts: vec![CoveredScript {
+                path: "ServerScriptService.Main".to_string(),
+                class_name: "Script".to_string(),
+                client: false,
+                file: None,
+                original: "return 1".to_string(),
+                instrumented: "__mcp_coverage[1] += 1; return 1".to_string(),
+                lines: vec![1],
+            }],
+        };
+        session.save(&place_dir).unwrap();
+        let loaded = CoverageSession::load(&place_dir).unwrap().unwrap();
+        assert_eq!(loaded.scripts[0].original, "return 1");
+
+        session.scripts.clear();
+        session.save(&place_dir).unwrap();
+        assert!(CoverageSession::load(&place_dir).unwrap().is_none());
+        std::fs::remove_dir_all(&place_dir).unwrap();
+    }
+}
@@ -0,0 +1,412 @@
+//! Heightmap images for import_heightmap and export_terrain_heightmap.
+//!
+//! On import the image is resampled to one column per terrain voxel, each column gets a
+//! material from the ramp, and the result is split into chunks that the plugin writes with
+//! `Terrain:WriteVoxels`. Export runs the other way: the plugin reads chunks of columns and
+//! their surfaces are assembled into a 16-bit heightmap and a material map. Chunks stay
+//! well under the region limit in `terrain.rs`.
+
+use crate::terrain::{TerrainMaterial, TerrainRegion, VOXEL_SIZE};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Columns per side of one chunk sent to the plugin
+pub const CHUNK_COLUMNS: usize = 64;
+
+/// Largest heightmap in columns per side (4096 studs)
+pub const MAX_COLUMNS: usize = 1024;
+
+/// Tallest terrain a heightmap may produce, in studs. 64x64 columns of this height is
+/// 1,048,576 voxels per chunk.
+pub const MAX_HEIGHT: f64 = 1024.0;
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct MaterialRule {
+    #[schemars(description = "Material for columns matching this rule")]
+    pub material: TerrainMaterial,
+    #[schemars(description = "Minimum column height in studs above the base")]
+    pub min_height: Option<f64>,
+    #[schemars(description = "Maximum column height in studs above the base")]
+    pub max_height: Option<f64>,
+    #[schemars(description = "Minimum slope in degrees (0 is flat, 90 is vertical)")]
+    pub min_slope: Option<f64>,
+    #[schemars(description = "Maximum slope in degrees")]
+    pub max_slope: Option<f64>,
+}
+
+impl MaterialRule {
+    fn matches(&self, height: f64, slope: f64) -> bool {
+        self.min_height.is_none_or(|min| height >= min)
+            && self.max_height.is_none_or(|max| height <= max)
+            && self.min_slope.is_none_or(|min| slope >= min)
+            && self.max_slope.is_none_or(|max| slope <= max)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let bounds = [
+            self.min_height,
+            self.max_height,
+            self.min_slope,
+            self.max_slope,
+        ];
+        if bounds.iter().flatten().any(|b| !b.is_finite()) {
+            return Err("Material rule bounds must be finite numbers".to_string());
+        }
+        if [self.min_slope, self.max_slope]
+            .iter()
+            .flatten()
+            .any(|s| !(0.0..=90.0).contains(s))
+        {
+            return Err("Material rule slopes must be between 0 and 90 degrees".to_string());
+        }
+        if let (Some(min), Some(max)) = (self.min_height, self.max_height) {
+            if min > max {
+                return Err(format!(
+                    "Material rule min_height {min} is above max_height {max}"
+                ));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_slope, self.max_slope) {
+            if min > max {
+                return Err(format!(
+                    "Material rule min_slope {min} is above max_slope {max}"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The ramp used when the caller doesn't provide one: rock on steep slopes, snow near
+/// the top, sand along the shoreline
+pub fn default_ramp(height_scale: f64, water_level: Option<f64>) -> Vec<MaterialRule> {
+    let rule = |material| MaterialRule {
+        material,
+        min_height: None,
+        max_height: None,
+        min_slope: None,
+        max_slope: None,
+    };
+    let mut ramp = vec![
+        MaterialRule {
+            min_slope: Some(45.0),
+            ..rule(TerrainMaterial::Rock)
+        },
+        MaterialRule {
+            min_height: Some(height_scale * 0.8),
+            ..rule(TerrainMaterial::Snow)
+        },
+    ];
+    if let Some(water_level) = water_level {
+        ramp.push(MaterialRule {
+            max_height: Some(water_level + 2.0),
+            ..rule(TerrainMaterial::Sand)
+        });
+    }
+    ramp
+}
+
+/// Column heights sampled from an image, row-major with x varying fastest
+pub struct Heightfield {
+    pub columns_x: usize,
+    pub columns_z: usize,
+    pub heights: Vec<f64>,
+    /// Whether the source image had color channels that were folded into luminance
+    pub converted_from_color: bool,
+}
+
+impl Heightfield {
+    /// Decode an image and bilinearly resample it to the given column grid.
+    /// Black is height 0 and white is `height_scale`.
+    pub fn load(
+        path: &Path,
+        columns_x: usize,
+        columns_z: usize,
+        height_scale: f64,
+    ) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|e| format!("Failed to read heightmap {}: {e}", path.display()))?;
+        let converted_from_color = image.color().has_color();
+        let luma = image.to_luma16();
+        let (width, height) = luma.dimensions();
+
+        let sample = |x: f64, y: f64| -> f64 {
+            let x = x.clamp(0.0, (width - 1) as f64);
+            let y = y.clamp(0.0, (height - 1) as f64);
+            let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+            let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+            let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+            let value = |x, y| luma.get_pixel(x, y).0[0] as f64 / u16::MAX as f64;
+            let top = value(x0, y0) * (1.0 - tx) + value(x1, y0) * tx;
+            let bottom = value(x0, y1) * (1.0 - tx) + value(x1, y1) * tx;
+            top * (1.0 - ty) + bottom * ty
+        };
+
+        let mut heights = Vec::with_capacity(columns_x * columns_z);
+        for z in 0..columns_z {
+            for x in 0..columns_x {
+                // Sample at column centers so the image edges line up with the terrain edges
+                let u = (x as f64 + 0.5) / columns_x as f64 * width as f64 - 0.5;
+                let v = (z as f64 + 0.5) / columns_z as f64 * height as f64 - 0.5;
+                heights.push(sample(u, v) * height_scale);
+            }
+        }
+
+        Ok(Self {
+            columns_x,
+            columns_z,
+            heights,
+            converted_from_color,
+        })
+    }
+
+    fn height(&self, x: usize, z: usize) -> f64 {
+        self.heights[z * self.columns_x + x]
+    }
+
+    /// Slope in degrees from central differences between neighboring columns
+    fn slope(&self, x: usize, z: usize) -> f64 {
+        let gradient = |a: f64, b: f64, steps: usize| (b - a) / (steps as f64 * VOXEL_SIZE);
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(self.columns_x - 1));
+        let (z0, z1) = (z.saturating_sub(1), (z + 1).min(self.columns_z - 1));
+        let dx = if x1 > x0 {
+            gradient(self.height(x0, z), self.height(x1, z), x1 - x0)
+        } else {
+            0.0
+        };
+        let dz = if z1 > z0 {
+            gradient(self.height(x, z0), self.height(x, z1), z1 - z0)
+        } else {
+            0.0
+        };
+        dx.hypot(dz).atan().to_degrees()
+    }
+
+    /// Pick each column's material from the first matching rule
+    pub fn materials(
+        &self,
+        ramp: &[MaterialRule],
+        default_material: TerrainMaterial,
+    ) -> Vec<TerrainMaterial> {
+        let mut materials = Vec::with_capacity(self.heights.len());
+        for z in 0..self.columns_z {
+            for x in 0..self.columns_x {
+                let (height, slope) = (self.height(x, z), self.slope(x, z));
+                let material = ramp
+                    .iter()
+                    .find(|rule| rule.matches(height, slope))
+                    .map_or(default_material, |rule| rule.material);
+                materials.push(material);
+            }
+        }
+        materials
+    }
+
+    /// Split into chunk payloads. `origin` is the grid-aligned minimum corner and every
+    /// chunk spans `voxels_y` layers, so terrain left above the heightmap is cleared evenly.
+    pub fn chunks(
+        &self,
+        materials: &[TerrainMaterial],
+        origin: [f64; 3],
+        voxels_y: u32,
+        water_level: Option<f64>,
+    ) -> Vec<HeightmapChunk> {
+        let mut chunks = Vec::new();
+        for chunk_z in (0..self.columns_z).step_by(CHUNK_COLUMNS) {
+            for chunk_x in (0..self.columns_x).step_by(CHUNK_COLUMNS) {
+                let columns_x = CHUNK_COLUMNS.min(self.columns_x - chunk_x);
+                let columns_z = CHUNK_COLUMNS.min(self.columns_z - chunk_z);
+                let mut heights = Vec::with_capacity(columns_x * columns_z);
+                let mut chunk_materials = Vec::with_capacity(columns_x * columns_z);
+                for z in chunk_z..chunk_z + columns_z {
+                    for x in chunk_x..chunk_x + columns_x {
+                        // Centistud precision keeps the payload small without visible steps
+                        heights.push((self.height(x, z) * 100.0).round() / 100.0);
+                        chunk_materials.push(materials[z * self.columns_x + x]);
+                    }
+                }
+                chunks.push(HeightmapChunk {
+                    origin: [
+                        origin[0] + chunk_x as f64 * VOXEL_SIZE,
+                        origin[1],
+                        origin[2] + chunk_z as f64 * VOXEL_SIZE,
+                    ],
+                    columns_x,
+                    columns_z,
+                    voxels_y,
+                    heights,
+                    materials: chunk_materials,
+                    water_level,
+                });
+            }
+        }
+        chunks
+    }
+}
+
+/// Plugin payload for one `WriteVoxels` call
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct HeightmapChunk {
+    /// Minimum corner of the chunk, aligned to the voxel grid
+    pub origin: [f64; 3],
+    pub columns_x: usize,
+    pub columns_z: usize,
+    /// Layers written above the origin; voxels above a column's height become water or air
+    pub voxels_y: u32,
+    /// Column heights in studs above the origin, row-major with x varying fastest
+    pub heights: Vec<f64>,
+    pub materials: Vec<TerrainMaterial>,
+    /// Water surface in studs above the origin
+    pub water_level: Option<f64>,
+}
+
+/// Split an export region into full-height chunks of `CHUNK_COLUMNS` columns per side.
+/// Returns each chunk's column offset within the region along with its aligned region.
+pub fn export_chunks(region: &TerrainRegion) -> Vec<(usize, usize, TerrainRegion)> {
+    let aligned = region.aligned();
+    let [columns_x, columns_z] = export_columns(region);
+    let mut chunks = Vec::new();
+    for chunk_z in (0..columns_z).step_by(CHUNK_COLUMNS) {
+        for chunk_x in (0..columns_x).step_by(CHUNK_COLUMNS) {
+            let min_x = aligned.min[0] + chunk_x as f64 * VOXEL_SIZE;
+            let min_z = aligned.min[2] + chunk_z as f64 * VOXEL_SIZE;
+            let chunk = TerrainRegion {
+                min: [min_x, aligned.min[1], min_z],
+                max: [
+                    (min_x + CHUNK_COLUMNS as f64 * VOXEL_SIZE).min(aligned.max[0]),
+                    aligned.max[1],
+                    (min_z + CHUNK_COLUMNS as f64 * VOXEL_SIZE).min(aligned.max[2]),
+                ],
+            };
+            chunks.push((chunk_x, chunk_z, chunk));
+        }
+    }
+    chunks
+}
+
+/// Columns along x and z once the region is aligned to the voxel grid
+pub fn export_columns(region: &TerrainRegion) -> [usize; 2] {
+    let aligned = region.aligned();
+    [0, 2].map(|i| ((aligned.max[i] - aligned.min[i]) / VOXEL_SIZE) as usize)
+}
+
+/// Plugin payload for reading the surface of one chunk of columns
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct ExportHeightmapChunk {
+    pub region: TerrainRegion,
+}
+
+/// Surface of one chunk as reported by the plugin. Materials index into `palette`, and
+/// columns without terrain use Air.
+#[derive(Debug, Deserialize)]
+pub struct ChunkSurface {
+    pub columns_x: usize,
+    pub columns_z: usize,
+    /// Ground height in studs above the region's minimum y, row-major with x varying fastest
+    pub heights: Vec<f64>,
+    pub materials: Vec<usize>,
+    pub palette: Vec<TerrainMaterial>,
+}
+
+/// Column surfaces for a whole export region, filled in one chunk at a time
+pub struct TerrainSurface {
+    pub columns_x: usize,
+    pub columns_z: usize,
+    heights: Vec<f64>,
+    materials: Vec<Option<TerrainMaterial>>,
+}
+
+impl TerrainSurface {
+    pub fn new(columns_x: usize, columns_z: usize) -> Self {
+        Self {
+            columns_x,
+            columns_z,
+            heights: vec![0.0; columns_x * columns_z],
+            materials: vec![None; columns_x * columns_z],
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        offset_x: usize,
+        offset_z: usize,
+        chunk: &ChunkSurface,
+    ) -> Result<(), String> {
+        let columns = chunk.columns_x * chunk.columns_z;
+        if chunk.heights.len() != columns
+            || chunk.materials.len() != columns
+            || offset_x + chunk.columns_x > self.columns_x
+            || offset_z + chunk.columns_z > self.columns_z
+        {
+            return Err(
+                "Plugin returned a chunk that doesn't match the requested region".to_string(),
+            );
+        }
+        for z in 0..chunk.columns_z {
+            for x in 0..chunk.columns_x {
+                let source = z * chunk.columns_x + x;
+                let target = (offset_z + z) * self.columns_x + offset_x + x;
+                self.heights[target] = chunk.heights[source];
+                let index = chunk.materials[source];
+                let material = *chunk.palette.get(index).ok_or_else(|| {
+                    format!("Plugin returned material index {index} outside its palette")
+                })?;
+                self.materials[target] = Some(material).filter(|m| *m != TerrainMaterial::Air);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lowest and highest ground among columns that have terrain
+    pub fn height_range(&self) -> Option<(f64, f64)> {
+        self.heights
+            .iter()
+            .zip(&self.materials)
+            .filter(|(_, material)| material.is_some())
+            .map(|(height, _)| *height)
+            .fold(None, |range, h| match range {
+                None => Some((h, h)),
+                Some((min, max)) => Some((min.min(h), max.max(h))),
+            })
+    }
+
+    /// Number of columns per surface material, most common first
+    pub fn material_counts(&self) -> Vec<(TerrainMaterial, usize)> {
+        let mut counts: Vec<(TerrainMaterial, usize)> = Vec::new();
+        for material in self.materials.iter().flatten() {
+            match counts.iter_mut().find(|(m, _)| m == material) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((*material, 1)),
+            }
+        }
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts
+    }
+
+    /// 16-bit grayscale heightmap where black is the region's minimum y and white its
+    /// maximum, so re-importing with height_scale set to the region height round-trips
+    pub fn height_image(
+        &self,
+        region_height: f64,
+    ) -> image::ImageBuffer<image::Luma<u16>, Vec<u16>> {
+        let pixels = self
+            .heights
+            .iter()
+            .map(|h| ((h / region_height).clamp(0.0, 1.0) * u16::MAX as f64).round() as u16)
+            .collect();
+        image::ImageBuffer::from_raw(self.columns_x as u32, self.columns_z as u32, pixels)
+            .expect("buffer size matches dimensions")
+    }
+
+    /// RGB material map using `TerrainMaterial::map_color`; columns without terrain are black
+    pub fn material_image(&self) -> image::RgbImage {
+        let pixels = self
+            .materials
+            .iter()
+            .flat_map(|material| material.map_or([0, 0, 0], |m| m.map_color()))
+            .collect();
+        image::RgbImage::from_raw(self.columns_x as u32, self.columns_z as u32, pixels)
+            .expect("buffer size matches dimensions")
+    }
+}
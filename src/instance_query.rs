@@ -0,0 +1,285 @@
+//! Selector syntax for tools that target a set of instances.
+//!
+//! A selector is a whitespace-separated list of `key:value` terms that must all match:
+//!
+//! - `class:BasePart` - instance IsA the class (comma-separate for any-of: `class:Part,MeshPart`)
+//! - `name:Door*` - name matches a glob (`*` and `?` wildcards)
+//! - `tag:Interactive` - instance has the CollectionService tag
+//! - `attr:Team=Red` - attribute equals (`=`) or differs from (`!=`) a value
+//! - `under:Workspace.Map` - instance is a descendant of the path
+//! - `prop:Transparency>0.5` - property comparison (`=`, `!=`, `<`, `<=`, `>`, `>=`)
+//!
+//! Values containing spaces can be wrapped in double quotes (`name:"Spawn Point"`).
+//! Selectors are parsed and validated here so the plugin only ever sees well-formed input.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+const SELECTOR_KEYS: &str = "class, name, tag, attr, under, prop";
+
+#[derive(Debug, Default, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct InstanceSelector {
+    /// Instance must be one of these classes (IsA)
+    pub classes: Vec<String>,
+    /// Glob the instance name must match
+    pub name: Option<String>,
+    /// CollectionService tags the instance must have
+    pub tags: Vec<String>,
+    pub attributes: Vec<Comparison>,
+    /// Path the instance must be a descendant of
+    pub ancestor: Option<String>,
+    pub properties: Vec<Comparison>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct Comparison {
+    pub name: String,
+    pub op: CompareOp,
+    pub value: SelectorValue,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+#[serde(untagged)]
+pub enum SelectorValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl InstanceSelector {
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let terms = tokenize(query)?;
+        if terms.is_empty() {
+            return Err(format!(
+                "Empty selector. Use one or more key:value terms ({SELECTOR_KEYS})"
+            ));
+        }
+
+        let mut selector = Self::default();
+        for term in terms {
+            let (key, value) = term.split_once(':').ok_or_else(|| {
+                format!("Invalid term '{term}'. Expected key:value with key one of: {SELECTOR_KEYS}")
+            })?;
+            if value.is_empty() {
+                return Err(format!("Missing value for '{key}:'"));
+            }
+
+            match key {
+                "class" => {
+                    for class_name in value.split(',') {
+                        validate_class(class_name)?;
+                        selector.classes.push(class_name.to_string());
+                    }
+                }
+                "name" => {
+                    if selector.name.is_some() {
+                        return Err("Only one name: term is allowed".to_string());
+                    }
+                    selector.name = Some(unquote(value).to_string());
+                }
+                "tag" => selector.tags.push(unquote(value).to_string()),
+                "attr" => {
+                    let comparison = parse_comparison(value)?;
+                    if !matches!(comparison.op, CompareOp::Eq | CompareOp::Ne) {
+                        return Err(format!(
+                            "Attribute terms only support '=' and '!=': attr:{value}"
+                        ));
+                    }
+                    selector.attributes.push(comparison);
+                }
+                "under" => {
+                    if selector.ancestor.is_some() {
+                        return Err("Only one under: term is allowed".to_string());
+                    }
+                    let path = unquote(value);
+                    if path.split('.').any(str::is_empty) {
+                        return Err(format!("Invalid path in under:{value}"));
+                    }
+                    selector.ancestor = Some(path.to_string());
+                }
+                "prop" => selector.properties.push(parse_comparison(value)?),
+                _ => {
+                    return Err(format!(
+                        "Unknown selector key '{key}'. Expected one of: {SELECTOR_KEYS}"
+                    ))
+                }
+            }
+        }
+
+        selector.validate_properties()?;
+        Ok(selector)
+    }
+
+    /// When the selector names classes, every property term must exist on at least one of them
+    fn validate_properties(&self) -> Result<(), String> {
+        if self.classes.is_empty() || self.properties.is_empty() {
+            return Ok(());
+        }
+        let database = rbx_reflection_database::get()
+            .map_err(|e| format!("Failed to load reflection database: {e}"))?;
+
+        for comparison in &self.properties {
+            let found = self.classes.iter().any(|class_name| {
+                database.classes.get(class_name.as_str()).is_some_and(|class| {
+                    database
+                        .superclasses_iter(class)
+                        .any(|c| c.properties.contains_key(comparison.name.as_str()))
+                })
+            });
+            if !found {
+                return Err(format!(
+                    "Property '{}' does not exist on {}",
+                    comparison.name,
+                    self.classes.join(" or ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn validate_class(class_name: &str) -> Result<(), String> {
+    if class_name.is_empty() {
+        return Err("Empty class name in class: term".to_string());
+    }
+    let database = rbx_reflection_database::get()
+        .map_err(|e| format!("Failed to load reflection database: {e}"))?;
+    if database.classes.contains_key(class_name) {
+        return Ok(());
+    }
+    let suggestion = database
+        .classes
+        .keys()
+        .find(|name| name.eq_ignore_ascii_case(class_name));
+    Err(match suggestion {
+        Some(name) => format!("Unknown class '{class_name}'. Did you mean '{name}'?"),
+        None => format!("Unknown class '{class_name}'"),
+    })
+}
+
+/// Split on whitespace, keeping double-quoted sections (which may contain spaces) intact
+fn tokenize(query: &str) -> Result<Vec<String>, String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("Unterminated quote in selector".to_string());
+    }
+    if !current.is_empty() {
+        terms.push(current);
+    }
+    Ok(terms)
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+fn parse_comparison(term: &str) -> Result<Comparison, String> {
+    // The earliest operator wins; at the same position the longer one does, so "<=" isn't read as "<"
+    const OPERATORS: [(&str, CompareOp); 6] = [
+        ("!=", CompareOp::Ne),
+        ("<=", CompareOp::Le),
+        (">=", CompareOp::Ge),
+        ("=", CompareOp::Eq),
+        ("<", CompareOp::Lt),
+        (">", CompareOp::Gt),
+    ];
+
+    let (index, symbol, op) = OPERATORS
+        .iter()
+        .filter_map(|(symbol, op)| term.find(symbol).map(|index| (index, *symbol, *op)))
+        .min_by_key(|(index, symbol, _)| (*index, std::cmp::Reverse(symbol.len())))
+        .ok_or_else(|| format!("Missing comparison operator in '{term}'"))?;
+
+    let name = &term[..index];
+    let raw_value = &term[index + symbol.len()..];
+
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(format!("Invalid name '{name}' in '{term}'"));
+    }
+    if raw_value.is_empty() {
+        return Err(format!("Missing value in '{term}'"));
+    }
+
+    let value = parse_value(raw_value);
+    if !matches!(op, CompareOp::Eq | CompareOp::Ne) && !matches!(value, SelectorValue::Number(_)) {
+        return Err(format!("Operator '{symbol}' requires a number in '{term}'"));
+    }
+
+    Ok(Comparison {
+        name: name.to_string(),
+        op,
+        value,
+    })
+}
+
+fn parse_value(raw: &str) -> SelectorValue {
+    if raw.len() >= 2 && raw.starts_with('"') && raw.ends_with('"') {
+        return SelectorValue::String(raw[1..raw.len() - 1].to_string());
+    }
+    match raw {
+        "true" => SelectorValue::Bool(true),
+        "false" => SelectorValue::Bool(false),
+        _ => raw
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .map(SelectorValue::Number)
+            .unwrap_or_else(|| SelectorValue::String(raw.to_string())),
+    }
+}
+
+/// A single instance path or a selector, for tools that accept either
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct InstanceTarget {
+    pub path: Option<String>,
+    pub selector: Option<InstanceSelector>,
+}
+
+impl InstanceTarget {
+    pub fn from_args(path: Option<String>, query: Option<String>) -> Result<Self, String> {
+        match (path, query) {
+            (Some(path), None) if !path.is_empty() => Ok(Self {
+                path: Some(path),
+                selector: None,
+            }),
+            (None, Some(query)) => Ok(Self {
+                path: None,
+                selector: Some(InstanceSelector::parse(&query)?),
+            }),
+            (Some(_), Some(_)) => Err("Provide either path or query, not both".to_string()),
+            _ => Err("Provide a path or a query".to_string()),
+        }
+    }
+}
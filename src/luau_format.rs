@@ -0,0 +1,53 @@
+//! StyLua formatting for write_script and the format_script tool.
+//!
+//! Sources are formatted in the server with the StyLua library, using the same
+//! stylua.toml lookup as the StyLua CLI, so scripts written by tools match the rest of
+//! the project. The config is read on every call, so edits to it apply without a restart.
+
+use std::path::{Path, PathBuf};
+use stylua_lib::{Config, OutputVerification};
+
+/// Config file names the StyLua CLI looks for, in order
+const CONFIG_FILES: [&str; 2] = ["stylua.toml", ".stylua.toml"];
+
+/// Server-wide formatting settings, from the command line
+#[derive(Debug, Clone, Default)]
+pub struct FormatSettings {
+    /// Whether write_script formats when a call doesn't say
+    pub format_by_default: bool,
+    /// StyLua config to use instead of searching for one
+    pub config_path: Option<PathBuf>,
+}
+
+impl FormatSettings {
+    /// Load the StyLua config: the configured file, otherwise the first stylua.toml or
+    /// .stylua.toml in the working directory or its parents, otherwise StyLua's defaults
+    pub fn load_config(&self) -> Result<Config, String> {
+        let config_file = match &self.config_path {
+            Some(path) => Some(path.clone()),
+            None => std::env::current_dir()
+                .ok()
+                .and_then(|dir| find_config(&dir)),
+        };
+        let Some(file) = config_file else {
+            return Ok(Config::default());
+        };
+
+        let contents = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+        toml::from_str(&contents).map_err(|e| format!("Failed to parse {}: {e}", file.display()))
+    }
+}
+
+fn find_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .flat_map(|dir| CONFIG_FILES.iter().map(move |name| dir.join(name)))
+        .find(|file| file.is_file())
+}
+
+/// Format a script. Like the StyLua CLI, the output isn't checked against the input AST,
+/// which would reject every file that sort_requires reorders.
+pub fn format(source: &str, config: Config) -> Result<String, String> {
+    stylua_lib::format_code(source, config, None, OutputVerification::None)
+        .map_err(|e| format!("StyLua couldn't format the script: {e}"))
+}
@@ -0,0 +1,341 @@
+//! Luau linting for the lint_scripts tool.
+//!
+//! Scripts are linted with selene-lib, the library behind the selene linter the plugin
+//! source is checked with, configured by a project's selene.toml. The `roblox` standard
+//! library that the selene CLI downloads is built here instead, from selene's bundled
+//! Roblox base plus the reflection database's enums, so linting works offline.
+
+use crate::script_export::CollectedScripts;
+use selene_full_moon::LuaVersion;
+use selene_lib::lints::Severity;
+use selene_lib::standard_library::StandardLibrary;
+use selene_lib::{Checker, CheckerConfig};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const CONFIG_FILE: &str = "selene.toml";
+pub const DEFAULT_MAX_DIAGNOSTICS: usize = 200;
+pub const MAX_DIAGNOSTICS: usize = 2000;
+
+/// Places are Roblox code, so the Roblox library is the default rather than selene's lua51
+const DEFAULT_STD: &str = "roblox";
+
+/// A finding in one script, with one-based line and column
+#[derive(Debug)]
+pub struct LintDiagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub severity: &'static str,
+    pub rule: &'static str,
+    pub message: String,
+    /// How to fix it, from selene's notes and labels
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct LintResults {
+    pub scripts_linted: usize,
+    pub scripts_flagged: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Whether diagnostics past max_diagnostics were left out of the output
+    pub truncated: bool,
+    pub output: String,
+}
+
+pub struct Linter {
+    checker: Checker<toml::Value>,
+    lua_version: LuaVersion,
+    /// The selene.toml in use, if any
+    pub config_file: Option<PathBuf>,
+    pub std: String,
+}
+
+/// The nearest selene.toml in `dir` or its parents
+fn find_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|file| file.is_file())
+}
+
+impl Linter {
+    /// Load a selene.toml (or the one in a directory). Without a path, the nearest
+    /// selene.toml in the working directory or its parents is used if there is one, like
+    /// the StyLua config, and selene's defaults otherwise.
+    pub fn load(config_path: Option<&Path>) -> Result<Self, String> {
+        let config_file = match config_path {
+            Some(path) if path.is_dir() => Some(path.join(CONFIG_FILE)),
+            Some(path) => Some(path.to_path_buf()),
+            None => std::env::current_dir()
+                .ok()
+                .and_then(|dir| find_config(&dir)),
+        };
+
+        let config: CheckerConfig<toml::Value> = match &config_file {
+            Some(file) => {
+                let contents = std::fs::read_to_string(file)
+                    .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+                toml::from_str(&contents)
+                    .map_err(|e| format!("Failed to parse {}: {e}", file.display()))?
+            }
+            None => CheckerConfig::default(),
+        };
+
+        let std_name = config
+            .std
+            .clone()
+            .unwrap_or_else(|| DEFAULT_STD.to_string());
+        let std_dir = config_file
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new("."));
+        let mut standard_library = StandardLibrary::default();
+        for name in std_name.split('+') {
+            standard_library.extend(load_std(name, std_dir)?);
+        }
+
+        let lua_version = standard_library.lua_version().0;
+        let checker = Checker::new(config, standard_library)
+            .map_err(|e| format!("Invalid lint configuration: {e}"))?;
+        Ok(Self {
+            checker,
+            lua_version,
+            config_file,
+            std: std_name,
+        })
+    }
+
+    /// Lint every collected script in tree order, listing at most `max_diagnostics`
+    /// findings. The counts cover every script either way.
+    pub fn run(&self, collected: &CollectedScripts, max_diagnostics: usize) -> LintResults {
+        let mut results = LintResults::default();
+        let mut listed = 0;
+        let mut paths: Vec<String> = Vec::with_capacity(collected.nodes.len());
+        for node in &collected.nodes {
+            let path = match node.parent {
+                Some(parent) => format!("{}.{}", paths[parent], node.name),
+                None => node.name.clone(),
+            };
+            paths.push(path);
+
+            let Some(source) = &node.source else {
+                continue;
+            };
+            results.scripts_linted += 1;
+            let diagnostics = self.lint(source);
+            if diagnostics.is_empty() {
+                continue;
+            }
+            results.scripts_flagged += 1;
+            for diagnostic in &diagnostics {
+                match diagnostic.severity {
+                    "error" => results.errors += 1,
+                    _ => results.warnings += 1,
+                }
+            }
+            if listed == max_diagnostics {
+                results.truncated = true;
+                continue;
+            }
+
+            results.output.push_str(&format!(
+                "\n{} ({})\n",
+                paths.last().unwrap(),
+                node.class_name
+            ));
+            for diagnostic in &diagnostics {
+                if listed == max_diagnostics {
+                    results.truncated = true;
+                    break;
+                }
+                listed += 1;
+                results.output.push_str(&format!(
+                    "  {}:{} {} {}: {}\n",
+                    diagnostic.line,
+                    diagnostic.column,
+                    diagnostic.severity,
+                    diagnostic.rule,
+                    diagnostic.message
+                ));
+                for suggestion in &diagnostic.suggestions {
+                    results
+                        .output
+                        .push_str(&format!("      fix: {suggestion}\n"));
+                }
+            }
+        }
+        results
+    }
+
+    /// Lint one script. Source that doesn't parse is reported as a single error.
+    pub fn lint(&self, source: &str) -> Vec<LintDiagnostic> {
+        let lines = LineIndex::new(source);
+        let ast = match selene_full_moon::parse_fallible(source, self.lua_version).into_result() {
+            Ok(ast) => ast,
+            Err(errors) => {
+                return errors
+                    .iter()
+                    .take(1)
+                    .map(|error| {
+                        let start = error.range().0;
+                        LintDiagnostic {
+                            line: start.line(),
+                            column: start.character(),
+                            severity: "error",
+                            rule: "parse_error",
+                            message: format!("Not linted: {}", error.error_message()),
+                            suggestions: Vec::new(),
+                        }
+                    })
+                    .collect();
+            }
+        };
+
+        let mut diagnostics: Vec<LintDiagnostic> = self
+            .checker
+            .test_on(&ast)
+            .into_iter()
+            .filter_map(|checked| {
+                let severity = match checked.severity {
+                    Severity::Allow => return None,
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+                let diagnostic = checked.diagnostic;
+                let (line, column) = lines.position(diagnostic.primary_label.range.0 as usize);
+                let mut suggestions: Vec<String> = diagnostic
+                    .primary_label
+                    .message
+                    .into_iter()
+                    .chain(diagnostic.notes)
+                    .collect();
+                if suggestions.is_empty() {
+                    suggestions.extend(default_suggestion(diagnostic.code).map(str::to_string));
+                }
+                Some(LintDiagnostic {
+                    line,
+                    column,
+                    severity,
+                    rule: diagnostic.code,
+                    message: diagnostic.message,
+                    suggestions,
+                })
+            })
+            .collect();
+        diagnostics.sort_by_key(|d| (d.line, d.column));
+        diagnostics
+    }
+}
+
+/// A fix for rules whose diagnostics don't carry one
+fn default_suggestion(rule: &str) -> Option<&'static str> {
+    Some(match rule {
+        "unused_variable" => "remove it, or start its name with _ if it's unused on purpose",
+        "undefined_variable" => {
+            "declare it with local, or add the global to a custom std library in selene.toml"
+        }
+        "shadowing" => "rename one of the variables",
+        "empty_if" | "empty_loop" => "remove the empty block, or fill it in",
+        "multiple_statements" => "put each statement on its own line",
+        "unbalanced_assignments" => "give every variable on the left a value on the right",
+        "global_usage" => "use a local variable or a ModuleScript instead of _G",
+        "unscoped_variables" => "declare the variable with local",
+        "if_same_then_else" => "merge the branches, since they do the same thing",
+        "ifs_same_cond" => "remove the duplicated condition",
+        "incorrect_standard_library_use" => "check the name against the API reference",
+        "parenthese_conditions" => "remove the parentheses around the condition",
+        "must_use" => "use the return value, or remove the call",
+        "divide_by_zero" => "use math.huge for infinity, or 0/0 for NaN",
+        _ => return None,
+    })
+}
+
+/// Resolve one `std` name: the generated Roblox library, one bundled with selene, or a
+/// `<name>.yml` file next to selene.toml, as the selene CLI does
+fn load_std(name: &str, dir: &Path) -> Result<StandardLibrary, String> {
+    if name == "roblox" {
+        return Ok(roblox_std().clone());
+    }
+    if let Some(std) = StandardLibrary::from_name(name) {
+        return Ok(std);
+    }
+
+    let file = dir.join(format!("{name}.yml"));
+    let contents = std::fs::read_to_string(&file).map_err(|_| {
+        format!(
+            "Unknown standard library '{name}': it isn't built in and {} doesn't exist",
+            file.display()
+        )
+    })?;
+    let mut std: StandardLibrary = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse {}: {e}", file.display()))?;
+    if let Some(base) = std.base.clone() {
+        let mut based = load_std(&base, dir)?;
+        based.extend(std);
+        std = based;
+    }
+    Ok(std)
+}
+
+/// selene's Roblox base library plus the scripting globals and every enum
+fn roblox_std() -> &'static StandardLibrary {
+    static ROBLOX_STD: OnceLock<StandardLibrary> = OnceLock::new();
+    ROBLOX_STD.get_or_init(|| {
+        let mut globals = Map::new();
+        for name in ["game", "workspace", "script", "plugin"] {
+            globals.insert(name.to_string(), json!({ "struct": "Instance" }));
+        }
+        if let Ok(database) = rbx_reflection_database::get() {
+            for (enum_name, descriptor) in &database.enums {
+                for item in descriptor.items.keys() {
+                    globals.insert(
+                        format!("Enum.{enum_name}.{item}"),
+                        json!({ "struct": "EnumItem" }),
+                    );
+                }
+                globals.insert(
+                    format!("Enum.{enum_name}.GetEnumItems"),
+                    json!({ "args": [], "method": true, "must_use": true }),
+                );
+                for (method, argument) in [("FromName", "string"), ("FromValue", "number")] {
+                    globals.insert(
+                        format!("Enum.{enum_name}.{method}"),
+                        json!({ "args": [{ "type": argument }], "method": true, "must_use": true }),
+                    );
+                }
+            }
+        }
+
+        let mut std = StandardLibrary::roblox_base();
+        let generated: StandardLibrary =
+            serde_json::from_value(json!({ "globals": Value::Object(globals) }))
+                .expect("generated Roblox globals are well-formed");
+        std.extend(generated);
+        std
+    })
+}
+
+/// Byte offset to one-based line and column
+struct LineIndex {
+    starts: Vec<usize>,
+    source_len: usize,
+}
+
+impl LineIndex {
+    fn new(source: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            starts,
+            source_len: source.len(),
+        }
+    }
+
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source_len);
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        (line + 1, offset - self.starts[line] + 1)
+    }
+}
@@ -0,0 +1,996 @@
+//! Local Luau runs for the run_luau_local tool and the `run-luau-local` subcommand.
+//!
+//! Pure ModuleScripts don't need Studio. The instance tree comes from a Rojo project, such
+//! as an export_scripts directory, or from a place or model file, and scripts run in an
+//! embedded Luau VM. Instances are userdata backed by that tree with the read-only half of
+//! the Instance API, and `game:GetService` returns the service from the tree or an empty
+//! stand-in for services the tree doesn't have. `require` loads ModuleScripts from their
+//! Source the way the plugin's TestRunner does, so TestEZ specs run through the same runner.
+//!
+//! Nothing yields: `task.wait` returns at once and spawned functions run right away, so the
+//! timeout is what stops a script waiting on something that never happens. Roblox types and
+//! events (Instance.new, Vector3, Changed) aren't there, and scripts that use them fail.
+
+use crate::model_file::read_model;
+use crate::project_sync::ProjectLayout;
+use crate::test_report::{TestReport, TEST_RUNNER_SOURCE};
+use mlua::{
+    AnyUserData, Function, Lua, MetaMethod, MultiValue, Table, UserData, UserDataFields,
+    UserDataMethods, Value, VmState,
+};
+use rbx_dom_weak::types::Variant;
+use rbx_dom_weak::{ustr, WeakDom};
+use rbx_reflection::ClassTag;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_TIMEOUT_SECONDS: f64 = 10.0;
+pub const MAX_TIMEOUT_SECONDS: f64 = 300.0;
+
+/// Lines of print and warn output kept; later lines are only counted
+const MAX_OUTPUT_LINES: usize = 1000;
+const MEMORY_LIMIT: usize = 1024 * 1024 * 1024;
+
+/// The DataModel is always the first node
+const GAME: usize = 0;
+
+/// Registry table of each instance's userdata, so an instance is the same value (and the
+/// same table key) every time a script sees it
+const INSTANCES_KEY: &str = "run_luau_local_instances";
+
+/// Module loading, output and the `task` library. Written in Luau so errors thrown by
+/// scripts pass through require untouched, like in Studio.
+const PRELUDE: &str = r#"
+local compile, describe, write, now = ...
+
+local results = {}
+local loading = {}
+
+-- Resolves a require-by-string path relative to the requiring script
+local function resolveStringRequire(from, path)
+	local segments = string.split(path, "/")
+	local current
+	if segments[1] == "." then
+		current = from.Parent
+	elseif segments[1] == ".." then
+		current = from.Parent and from.Parent.Parent
+	elseif segments[1] == "@self" then
+		current = from
+	else
+		return nil
+	end
+	for index = 2, #segments do
+		local segment = segments[index]
+		if not current then
+			return nil
+		end
+		if segment == ".." then
+			current = current.Parent
+		elseif segment ~= "" and segment ~= "." then
+			current = current:FindFirstChild(segment)
+		end
+	end
+	return current
+end
+
+local requireFrom
+
+local function load(module)
+	local cached = results[module]
+	if cached then
+		return cached[1]
+	end
+	if loading[module] then
+		error("Requested module was required recursively: " .. module:GetFullName(), 3)
+	end
+
+	local env = setmetatable({ script = module, require = requireFrom(module) }, { __index = _G })
+	local chunk, compileError = compile(module, env)
+	if not chunk then
+		error(compileError, 0)
+	end
+
+	loading[module] = true
+	local returned = table.pack(pcall(chunk))
+	loading[module] = nil
+	if not returned[1] then
+		error(returned[2], 0)
+	end
+	if returned.n ~= 2 then
+		error(module:GetFullName() .. ": Module code did not return exactly one value", 0)
+	end
+	results[module] = { returned[2] }
+	return returned[2]
+end
+
+function requireFrom(caller)
+	return function(target)
+		if type(target) == "string" then
+			if not caller then
+				error("Can't resolve require path '" .. target .. "' without a script", 2)
+			end
+			target = resolveStringRequire(caller, target)
+				or error("Couldn't resolve require path '" .. target .. "' from " .. caller:GetFullName(), 2)
+		end
+		if typeof(target) ~= "Instance" or not target:IsA("ModuleScript") then
+			error("Attempted to call require with invalid argument(s)", 2)
+		end
+		return load(target)
+	end
+end
+
+local function join(...)
+	local parts = table.pack(...)
+	for index = 1, parts.n do
+		parts[index] = tostring(parts[index])
+	end
+	return table.concat(parts, " ", 1, parts.n)
+end
+
+print = function(...)
+	write(join(...))
+end
+
+warn = function(...)
+	write("[warn] " .. join(...))
+end
+
+local function resume(thread, ...)
+	local ok, err = coroutine.resume(thread, ...)
+	if not ok then
+		write("[error] " .. describe(err))
+	end
+end
+
+task = {
+	spawn = function(callback, ...)
+		local thread = if type(callback) == "thread" then callback else coroutine.create(callback)
+		resume(thread, ...)
+		return thread
+	end,
+	delay = function(_, callback, ...)
+		return task.spawn(callback, ...)
+	end,
+	wait = function()
+		return 0
+	end,
+	cancel = function(thread)
+		coroutine.close(thread)
+	end,
+	synchronize = function() end,
+	desynchronize = function() end,
+}
+task.defer = task.spawn
+wait = task.wait
+spawn = task.defer
+delay = task.delay
+tick = now
+time = os.clock
+elapsedTime = os.clock
+
+local function run(callback, ...)
+	return xpcall(callback, function(err)
+		return debug.traceback(describe(err), 2)
+	end, ...)
+end
+
+return requireFrom, run
+"#;
+
+/// What a local run does
+pub enum LocalTask {
+    /// Run a snippet, optionally as the given script so relative requires work
+    Code {
+        code: String,
+        script: Option<String>,
+    },
+    /// Run TestEZ specs through the plugin's TestRunner
+    Tests {
+        paths: Vec<String>,
+        framework_path: Option<String>,
+        name_filter: Option<String>,
+    },
+}
+
+pub enum Outcome {
+    /// The snippet's return values, rendered
+    Returned(Vec<String>),
+    /// The error and its traceback
+    Failed(String),
+    Tests(TestReport),
+}
+
+pub struct LocalRun {
+    pub outcome: Outcome,
+    pub output: Vec<String>,
+    /// Output lines past MAX_OUTPUT_LINES
+    pub dropped: usize,
+    pub elapsed: Duration,
+}
+
+enum Attribute {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+struct Node {
+    name: String,
+    class_name: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    source: Option<String>,
+    attributes: Vec<(String, Attribute)>,
+}
+
+/// Load a Rojo project (a .project.json file or a directory with default.project.json)
+/// or a .rbxl, .rbxlx, .rbxm, .rbxmx or .model.json file
+pub fn load_dom(path: &Path) -> Result<WeakDom, String> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    if path.is_dir() || file_name.ends_with(".project.json") {
+        return ProjectLayout::load(path)?.build();
+    }
+    if file_name.ends_with(".rbxl") || file_name.ends_with(".rbxlx") {
+        let file = std::fs::File::open(path)
+            .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+        let reader = std::io::BufReader::new(file);
+        return if file_name.ends_with(".rbxl") {
+            rbx_binary::from_reader(reader).map_err(|e| e.to_string())
+        } else {
+            rbx_xml::from_reader_default(reader).map_err(|e| e.to_string())
+        }
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()));
+    }
+    Ok(read_model(path)?.0)
+}
+
+/// The instances a local run sees, flattened from a WeakDom
+pub struct InstanceTree {
+    nodes: Vec<Node>,
+}
+
+#[derive(Default)]
+struct Output {
+    lines: Vec<String>,
+    dropped: usize,
+}
+
+/// An instance as Luau sees it
+#[derive(Clone, Copy)]
+struct Instance(usize);
+
+impl InstanceTree {
+    /// Load the tree of anything `load_dom` reads
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Ok(Self::from_dom(&load_dom(path)?))
+    }
+
+    fn from_dom(dom: &WeakDom) -> Self {
+        let mut nodes = vec![Node {
+            name: "Game".to_string(),
+            class_name: "DataModel".to_string(),
+            parent: None,
+            children: Vec::new(),
+            source: None,
+            attributes: Vec::new(),
+        }];
+        let mut pending: Vec<_> = dom
+            .root()
+            .children()
+            .iter()
+            .rev()
+            .map(|referent| (*referent, GAME))
+            .collect();
+        while let Some((referent, parent)) = pending.pop() {
+            let Some(instance) = dom.get_by_ref(referent) else {
+                continue;
+            };
+            let source = match instance.properties.get(&ustr("Source")) {
+                Some(Variant::String(source)) => Some(source.clone()),
+                _ => None,
+            };
+            let mut attributes = Vec::new();
+            if let Some(Variant::Attributes(values)) = instance.properties.get(&ustr("Attributes"))
+            {
+                for (name, value) in values.iter() {
+                    let value = match value {
+                        Variant::Bool(value) => Attribute::Bool(*value),
+                        Variant::Float64(value) => Attribute::Number(*value),
+                        Variant::Float32(value) => Attribute::Number(*value as f64),
+                        Variant::Int32(value) => Attribute::Number(*value as f64),
+                        Variant::Int64(value) => Attribute::Number(*value as f64),
+                        Variant::String(value) => Attribute::String(value.clone()),
+                        // Roblox types have no Luau counterpart here
+                        _ => continue,
+                    };
+                    attributes.push((name.clone(), value));
+                }
+            }
+
+            let index = nodes.len();
+            nodes[parent].children.push(index);
+            nodes.push(Node {
+                name: instance.name.clone(),
+                class_name: instance.class.to_string(),
+                parent: Some(parent),
+                children: Vec::new(),
+                source,
+                attributes,
+            });
+            for child in instance.children().iter().rev() {
+                pending.push((*child, index));
+            }
+        }
+        Self { nodes }
+    }
+
+    /// Resolve a dot-separated path like "ReplicatedStorage.Shared.Util"
+    fn find(&self, path: &str) -> Option<usize> {
+        let path = path.strip_prefix("game.").unwrap_or(path);
+        if path == "game" {
+            return Some(GAME);
+        }
+        path.split('.')
+            .try_fold(GAME, |current, name| self.child(current, name))
+    }
+
+    fn child(&self, id: usize, name: &str) -> Option<usize> {
+        self.nodes[id]
+            .children
+            .iter()
+            .copied()
+            .find(|child| self.nodes[*child].name == name)
+    }
+
+    /// Descendants in the order GetDescendants returns them
+    fn descendants(&self, id: usize) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut pending: Vec<usize> = self.nodes[id].children.iter().rev().copied().collect();
+        while let Some(current) = pending.pop() {
+            found.push(current);
+            pending.extend(self.nodes[current].children.iter().rev());
+        }
+        found
+    }
+
+    fn ancestors(&self, id: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.nodes[id].parent, |current| self.nodes[*current].parent)
+    }
+
+    fn find_ancestor(&self, id: usize, matches: impl Fn(&Node) -> bool) -> Option<usize> {
+        self.ancestors(id)
+            .find(|ancestor| matches(&self.nodes[*ancestor]))
+    }
+
+    fn full_name(&self, id: usize) -> String {
+        let mut names = vec![self.nodes[id].name.as_str()];
+        // Like Roblox, paths start below the DataModel
+        names.extend(
+            self.ancestors(id)
+                .filter(|ancestor| *ancestor != GAME)
+                .map(|ancestor| self.nodes[ancestor].name.as_str()),
+        );
+        names.reverse();
+        names.join(".")
+    }
+
+    fn is_a(&self, id: usize, class_name: &str) -> bool {
+        is_a(&self.nodes[id].class_name, class_name)
+    }
+
+    /// The service of this class, added empty when the tree doesn't have it
+    fn service(&mut self, class_name: &str) -> Option<usize> {
+        let found = self.nodes[GAME]
+            .children
+            .iter()
+            .copied()
+            .find(|child| self.nodes[*child].class_name == class_name);
+        if found.is_some() {
+            return found;
+        }
+        let database = rbx_reflection_database::get().ok()?;
+        let class = database.classes.get(class_name)?;
+        if !class.tags.contains(&ClassTag::Service) {
+            return None;
+        }
+        let index = self.nodes.len();
+        self.nodes[GAME].children.push(index);
+        self.nodes.push(Node {
+            name: class_name.to_string(),
+            class_name: class_name.to_string(),
+            parent: Some(GAME),
+            children: Vec::new(),
+            source: None,
+            attributes: Vec::new(),
+        });
+        Some(index)
+    }
+}
+
+fn is_a(class_name: &str, base: &str) -> bool {
+    if class_name == base {
+        return true;
+    }
+    let Ok(database) = rbx_reflection_database::get() else {
+        return base == "Instance";
+    };
+    database
+        .classes
+        .get(class_name)
+        .is_some_and(|class| database.superclasses_iter(class).any(|c| c.name == base))
+}
+
+fn tree(lua: &Lua) -> mlua::Result<mlua::AppDataRef<'_, InstanceTree>> {
+    lua.app_data_ref()
+        .ok_or_else(|| mlua::Error::runtime("The instance tree isn't loaded"))
+}
+
+fn wrap(lua: &Lua, id: usize) -> mlua::Result<AnyUserData> {
+    let instances: Table = lua.named_registry_value(INSTANCES_KEY)?;
+    if let Some(existing) = instances.raw_get::<Option<AnyUserData>>(id)? {
+        return Ok(existing);
+    }
+    let instance = lua.create_userdata(Instance(id))?;
+    instances.raw_set(id, &instance)?;
+    Ok(instance)
+}
+
+fn wrap_all(lua: &Lua, ids: Vec<usize>) -> mlua::Result<Vec<AnyUserData>> {
+    ids.into_iter().map(|id| wrap(lua, id)).collect()
+}
+
+fn wrap_some(lua: &Lua, id: Option<usize>) -> mlua::Result<Option<AnyUserData>> {
+    id.map(|id| wrap(lua, id)).transpose()
+}
+
+fn not_a_member(tree: &InstanceTree, id: usize, key: &str) -> mlua::Error {
+    let node = &tree.nodes[id];
+    mlua::Error::runtime(format!(
+        "{key} is not a valid member of {} \"{}\"",
+        node.class_name,
+        tree.full_name(id)
+    ))
+}
+
+impl UserData for Instance {
+    fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+        fields.add_meta_field(MetaMethod::Type, "Instance");
+        fields.add_field_method_get("Name", |lua, this| {
+            Ok(tree(lua)?.nodes[this.0].name.clone())
+        });
+        fields.add_field_method_get("ClassName", |lua, this| {
+            Ok(tree(lua)?.nodes[this.0].class_name.clone())
+        });
+        fields.add_field_method_get("Parent", |lua, this| {
+            let parent = tree(lua)?.nodes[this.0].parent;
+            wrap_some(lua, parent)
+        });
+    }
+
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("GetFullName", |lua, this, ()| {
+            Ok(tree(lua)?.full_name(this.0))
+        });
+        methods.add_method("IsA", |lua, this, class_name: String| {
+            Ok(tree(lua)?.is_a(this.0, &class_name))
+        });
+        methods.add_method(
+            "FindFirstChild",
+            |lua, this, (name, recursive): (String, Option<bool>)| {
+                let found = {
+                    let tree = tree(lua)?;
+                    if recursive.unwrap_or(false) {
+                        tree.descendants(this.0)
+                            .into_iter()
+                            .find(|id| tree.nodes[*id].name == name)
+                    } else {
+                        tree.child(this.0, &name)
+                    }
+                };
+                wrap_some(lua, found)
+            },
+        );
+        methods.add_method("FindFirstChildOfClass", |lua, this, class_name: String| {
+            let found = {
+                let tree = tree(lua)?;
+                tree.nodes[this.0]
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|child| tree.nodes[*child].class_name == class_name)
+            };
+            wrap_some(lua, found)
+        });
+        methods.add_method(
+            "FindFirstChildWhichIsA",
+            |lua, this, (class_name, recursive): (String, Option<bool>)| {
+                let found = {
+                    let tree = tree(lua)?;
+                    let candidates = if recursive.unwrap_or(false) {
+                        tree.descendants(this.0)
+                    } else {
+                        tree.nodes[this.0].children.clone()
+                    };
+                    candidates
+                        .into_iter()
+                        .find(|id| tree.is_a(*id, &class_name))
+                };
+                wrap_some(lua, found)
+            },
+        );
+        methods.add_method("FindFirstAncestor", |lua, this, name: String| {
+            let found = { tree(lua)?.find_ancestor(this.0, |node| node.name == name) };
+            wrap_some(lua, found)
+        });
+        methods.add_method(
+            "FindFirstAncestorOfClass",
+            |lua, this, class_name: String| {
+                let found =
+                    { tree(lua)?.find_ancestor(this.0, |node| node.class_name == class_name) };
+                wrap_some(lua, found)
+            },
+        );
+        methods.add_method(
+            "FindFirstAncestorWhichIsA",
+            |lua, this, class_name: String| {
+                let found = {
+                    tree(lua)?.find_ancestor(this.0, |node| is_a(&node.class_name, &class_name))
+                };
+                wrap_some(lua, found)
+            },
+        );
+        methods.add_method(
+            "WaitForChild",
+            |lua, this, (name, timeout): (String, Option<f64>)| {
+                let found = {
+                    let tree = tree(lua)?;
+                    let found = tree.child(this.0, &name);
+                    // Nothing can add the child later, so waiting without a timeout never ends
+                    if found.is_none() && timeout.is_none() {
+                        return Err(mlua::Error::runtime(format!(
+                            "Infinite yield: {name} is not a child of {}",
+                            tree.full_name(this.0)
+                        )));
+                    }
+                    found
+                };
+                wrap_some(lua, found)
+            },
+        );
+        methods.add_method("GetChildren", |lua, this, ()| {
+            let children = tree(lua)?.nodes[this.0].children.clone();
+            wrap_all(lua, children)
+        });
+        methods.add_method("GetDescendants", |lua, this, ()| {
+            let descendants = tree(lua)?.descendants(this.0);
+            wrap_all(lua, descendants)
+        });
+        methods.add_method("IsDescendantOf", |lua, this, other: AnyUserData| {
+            let other = other.borrow::<Instance>()?.0;
+            Ok(tree(lua)?.ancestors(this.0).any(|id| id == other))
+        });
+        methods.add_method("IsAncestorOf", |lua, this, other: AnyUserData| {
+            let other = other.borrow::<Instance>()?.0;
+            Ok(tree(lua)?.ancestors(other).any(|id| id == this.0))
+        });
+        methods.add_method("GetAttribute", |lua, this, name: String| {
+            let tree = tree(lua)?;
+            let value = tree.nodes[this.0]
+                .attributes
+                .iter()
+                .find(|(attribute, _)| *attribute == name);
+            match value {
+                Some((_, value)) => attribute_value(lua, value),
+                None => Ok(Value::Nil),
+            }
+        });
+        methods.add_method("GetAttributes", |lua, this, ()| {
+            let tree = tree(lua)?;
+            let attributes = lua.create_table()?;
+            for (name, value) in &tree.nodes[this.0].attributes {
+                attributes.set(name.as_str(), attribute_value(lua, value)?)?;
+            }
+            Ok(attributes)
+        });
+        methods.add_method("GetService", |lua, this, class_name: String| {
+            let found = {
+                let mut tree = lua
+                    .app_data_mut::<InstanceTree>()
+                    .ok_or_else(|| mlua::Error::runtime("The instance tree isn't loaded"))?;
+                if this.0 != GAME {
+                    return Err(not_a_member(&tree, this.0, "GetService"));
+                }
+                tree.service(&class_name).ok_or_else(|| {
+                    mlua::Error::runtime(format!("'{class_name}' is not a valid Service name"))
+                })?
+            };
+            wrap(lua, found)
+        });
+        methods.add_meta_method(MetaMethod::Index, |lua, this, key: String| {
+            let (found, class_name) = {
+                let tree = tree(lua)?;
+                (
+                    tree.child(this.0, &key),
+                    tree.nodes[this.0].class_name.clone(),
+                )
+            };
+            if let Some(child) = found {
+                return Ok(Value::UserData(wrap(lua, child)?));
+            }
+            if let Some(method) = service_method(lua, &class_name, &key)? {
+                return Ok(Value::Function(method));
+            }
+            Err(not_a_member(&*tree(lua)?, this.0, &key))
+        });
+        methods.add_meta_method(
+            MetaMethod::NewIndex,
+            |lua, this, (key, _): (String, Value)| -> mlua::Result<()> {
+                Err(mlua::Error::runtime(format!(
+                    "Can't set {key} of {}: instances are read-only when running locally",
+                    tree(lua)?.full_name(this.0)
+                )))
+            },
+        );
+        methods.add_meta_method(MetaMethod::ToString, |lua, this, ()| {
+            Ok(tree(lua)?.nodes[this.0].name.clone())
+        });
+    }
+}
+
+fn attribute_value(lua: &Lua, value: &Attribute) -> mlua::Result<Value> {
+    Ok(match value {
+        Attribute::Bool(value) => Value::Boolean(*value),
+        Attribute::Number(value) => Value::Number(*value),
+        Attribute::String(value) => Value::String(lua.create_string(value)?),
+    })
+}
+
+/// The service methods pure modules commonly call
+fn service_method(lua: &Lua, class_name: &str, key: &str) -> mlua::Result<Option<Function>> {
+    let method = match (class_name, key) {
+        ("HttpService", "JSONEncode") => {
+            lua.create_function(|_, (_, value): (AnyUserData, Value)| {
+                let serializable = value
+                    .to_serializable()
+                    .encode_empty_tables_as_array(true)
+                    .sort_keys(true);
+                serde_json::to_string(&serializable)
+                    .map_err(|e| mlua::Error::runtime(format!("Can't convert to JSON: {e}")))
+            })?
+        }
+        ("HttpService", "JSONDecode") => {
+            lua.create_function(|lua, (_, text): (AnyUserData, String)| {
+                use mlua::LuaSerdeExt;
+                let value: serde_json::Value = serde_json::from_str(&text)
+                    .map_err(|e| mlua::Error::runtime(format!("Can't parse JSON: {e}")))?;
+                let options = mlua::SerializeOptions::new()
+                    .serialize_none_to_null(false)
+                    .serialize_unit_to_null(false);
+                lua.to_value_with(&value, options)
+            })?
+        }
+        ("HttpService", "GenerateGUID") => {
+            lua.create_function(|_, (_, braces): (AnyUserData, Option<bool>)| {
+                let guid = uuid::Uuid::new_v4().to_string().to_uppercase();
+                Ok(if braces.unwrap_or(true) {
+                    format!("{{{guid}}}")
+                } else {
+                    guid
+                })
+            })?
+        }
+        // Runs behave like the plugin's edit mode
+        ("RunService", "IsStudio" | "IsEdit" | "IsServer") => {
+            lua.create_function(|_, _: AnyUserData| Ok(true))?
+        }
+        ("RunService", "IsClient" | "IsRunning" | "IsRunMode") => {
+            lua.create_function(|_, _: AnyUserData| Ok(false))?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(method))
+}
+
+/// The innermost message of an error raised in a Rust callback
+fn error_message(error: &mlua::Error) -> String {
+    match error {
+        mlua::Error::CallbackError { cause, .. } => error_message(cause),
+        mlua::Error::WithContext { cause, .. } => error_message(cause),
+        mlua::Error::RuntimeError(message) => message.clone(),
+        mlua::Error::SyntaxError { message, .. } => message.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Drop the frames of mlua's glue and of the prelude from a traceback
+fn clean_traceback(text: &str) -> String {
+    text.lines()
+        .filter(|line| {
+            let line = line.trim();
+            !line.starts_with("__mlua") && !line.starts_with("prelude:") && line != "[C]: in ?"
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render(lua: &Lua, value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("{:?}", text.to_string_lossy()),
+        Value::Table(_) => {
+            let serializable = value
+                .to_serializable()
+                .encode_empty_tables_as_array(true)
+                .sort_keys(true)
+                .deny_unsupported_types(false);
+            serde_json::to_string_pretty(&serializable)
+                .unwrap_or_else(|_| value.to_string().unwrap_or_default())
+        }
+        Value::UserData(data) => match data.borrow::<Instance>() {
+            Ok(instance) => tree(lua)
+                .map(|tree| tree.full_name(instance.0))
+                .unwrap_or_default(),
+            Err(_) => value.to_string().unwrap_or_default(),
+        },
+        other => other.to_string().unwrap_or_else(|e| error_message(&e)),
+    }
+}
+
+/// Run code or specs against the tree in a fresh VM
+pub fn run(tree: InstanceTree, task: LocalTask, timeout: Duration) -> Result<LocalRun, String> {
+    let script = match &task {
+        LocalTask::Code {
+            script: Some(path), ..
+        } => Some(
+            tree.find(path)
+                .ok_or_else(|| format!("No instance at {path}"))?,
+        ),
+        _ => None,
+    };
+
+    let lua = Lua::new();
+    lua.set_app_data(tree);
+    lua.set_app_data(Output::default());
+    let start = Instant::now();
+    let outcome = execute(&lua, task, script, timeout)
+        .map_err(|e| format!("Failed to run Luau: {}", error_message(&e)))?;
+    let elapsed = start.elapsed();
+    let output = lua.remove_app_data::<Output>().unwrap_or_default();
+    Ok(LocalRun {
+        outcome,
+        output: output.lines,
+        dropped: output.dropped,
+        elapsed,
+    })
+}
+
+fn execute(
+    lua: &Lua,
+    task: LocalTask,
+    script: Option<usize>,
+    timeout: Duration,
+) -> mlua::Result<Outcome> {
+    lua.set_memory_limit(MEMORY_LIMIT)?;
+    lua.set_named_registry_value(INSTANCES_KEY, lua.create_table()?)?;
+
+    let globals = lua.globals();
+    globals.set("game", wrap(lua, GAME)?)?;
+    let workspace = lua
+        .app_data_mut::<InstanceTree>()
+        .and_then(|mut tree| tree.service("Workspace"));
+    globals.set("workspace", wrap_some(lua, workspace)?)?;
+
+    let compile = lua.create_function(|lua, (module, env): (AnyUserData, Table)| {
+        let id = module.borrow::<Instance>()?.0;
+        let (source, name) = {
+            let tree = tree(lua)?;
+            (tree.nodes[id].source.clone(), tree.full_name(id))
+        };
+        let Some(source) = source else {
+            return Ok((None, Some(format!("{name} has no Source"))));
+        };
+        match lua
+            .load(source)
+            .set_name(format!("={name}"))
+            .set_environment(env)
+            .into_function()
+        {
+            Ok(chunk) => Ok((Some(chunk), None)),
+            Err(e) => Ok((None, Some(error_message(&e)))),
+        }
+    })?;
+    let describe = lua.create_function(|_, error: Value| Ok(render_error(&error)))?;
+    let write = lua.create_function(|lua, text: String| {
+        if let Some(mut output) = lua.app_data_mut::<Output>() {
+            if output.lines.len() < MAX_OUTPUT_LINES {
+                output.lines.push(text);
+            } else {
+                output.dropped += 1;
+            }
+        }
+        Ok(())
+    })?;
+    let now = lua.create_function(|_, ()| {
+        Ok(SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs_f64())
+            .unwrap_or_default())
+    })?;
+    let (require_from, run): (Function, Function) = lua
+        .load(PRELUDE)
+        .set_name("=prelude")
+        .call((compile, describe, write, now))?;
+    globals.set("require", require_from.call::<Function>(Value::Nil)?)?;
+
+    let deadline = Instant::now() + timeout;
+    lua.set_interrupt(move |_| {
+        if Instant::now() >= deadline {
+            Err(mlua::Error::runtime("Timed out"))
+        } else {
+            Ok(VmState::Continue)
+        }
+    });
+    let timed_out = || {
+        Outcome::Failed(format!(
+            "Timed out after {} seconds. Nothing yields when running locally, so a script waiting on an event or a loop that waits for a change never finishes.",
+            timeout.as_secs_f64()
+        ))
+    };
+
+    match task {
+        LocalTask::Code { code, .. } => {
+            let env = lua.create_table()?;
+            let meta = lua.create_table()?;
+            meta.set("__index", &globals)?;
+            env.set_metatable(Some(meta))?;
+            if let Some(id) = script {
+                let instance = wrap(lua, id)?;
+                env.set("require", require_from.call::<Function>(&instance)?)?;
+                env.set("script", instance)?;
+            }
+            let chunk = match lua
+                .load(code)
+                .set_name("=run_luau_local")
+                .set_environment(env)
+                .into_function()
+            {
+                Ok(chunk) => chunk,
+                Err(e) => return Ok(Outcome::Failed(error_message(&e))),
+            };
+
+            let mut returned = match run.call::<MultiValue>(chunk) {
+                Ok(returned) => returned,
+                Err(_) if Instant::now() >= deadline => return Ok(timed_out()),
+                Err(e) => return Ok(Outcome::Failed(error_message(&e))),
+            };
+            let ok = matches!(returned.pop_front(), Some(Value::Boolean(true)));
+            if !ok {
+                if Instant::now() >= deadline {
+                    return Ok(timed_out());
+                }
+                let message = returned.front().map(render_error).unwrap_or_default();
+                return Ok(Outcome::Failed(clean_traceback(&message)));
+            }
+            Ok(Outcome::Returned(
+                returned.iter().map(|value| render(lua, value)).collect(),
+            ))
+        }
+        LocalTask::Tests {
+            paths,
+            framework_path,
+            name_filter,
+        } => {
+            let runner: Table = lua
+                .load(TEST_RUNNER_SOURCE)
+                .set_name("=TestRunner")
+                .call(())?;
+            let options = lua.create_table()?;
+            options.set("paths", paths)?;
+            // Jest-Lua needs Studio's module loader, so local runs are TestEZ only
+            options.set("framework", "testez")?;
+            options.set("framework_path", framework_path)?;
+            options.set("name_filter", name_filter)?;
+
+            let run_tests: Function = runner.get("run")?;
+            let mut returned = match run.call::<MultiValue>((run_tests, options, false)) {
+                Ok(returned) => returned,
+                Err(_) if Instant::now() >= deadline => return Ok(timed_out()),
+                Err(e) => return Ok(Outcome::Failed(error_message(&e))),
+            };
+            let ok = matches!(returned.pop_front(), Some(Value::Boolean(true)));
+            let result = returned.pop_front().unwrap_or(Value::Nil);
+            if !ok {
+                if Instant::now() >= deadline {
+                    return Ok(timed_out());
+                }
+                return Ok(Outcome::Failed(clean_traceback(&render_error(&result))));
+            }
+            let json = result.to_string()?;
+            Ok(match serde_json::from_str::<TestReport>(&json) {
+                Ok(report) => Outcome::Tests(report),
+                Err(e) => Outcome::Failed(format!("Invalid test runner response: {e}")),
+            })
+        }
+    }
+}
+
+fn render_error(error: &Value) -> String {
+    match error {
+        Value::Error(error) => error_message(error),
+        other => other.to_string().unwrap_or_else(|e| error_message(&e)),
+    }
+}
+
+impl LocalRun {
+    /// Whether the code ran without error, or every test passed
+    pub fn passed(&self) -> bool {
+        match &self.outcome {
+            Outcome::Returned(_) => true,
+            Outcome::Failed(_) => false,
+            Outcome::Tests(report) => report.count("failed") == 0 && report.errors.is_empty(),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let mut text = match &self.outcome {
+            Outcome::Returned(values) => {
+                let mut text = format!(
+                    "[SUCCESS] Ran in {:.1} ms",
+                    self.elapsed.as_secs_f64() * 1000.0
+                );
+                if !values.is_empty() {
+                    text.push_str("\n\nReturned:");
+                    for value in values {
+                        text.push_str(&format!("\n{value}"));
+                    }
+                }
+                text
+            }
+            Outcome::Failed(message) => message.clone(),
+            Outcome::Tests(report) => report.summary("local"),
+        };
+        if !self.output.is_empty() {
+            text.push_str(&format!(
+                "\n\nOutput ({} line(s)):",
+                self.output.len() + self.dropped
+            ));
+            for line in &self.output {
+                text.push_str(&format!("\n{line}"));
+            }
+            if self.dropped > 0 {
+                text.push_str(&format!("\n... {} more line(s)", self.dropped));
+            }
+        }
+        text
+    }
+}
+
+/// Entry point of the `run-luau-local` subcommand. Prints the same summary as the tool and
+/// exits with status 1 when the code errors or a test fails.
+pub fn run_cli(source: &Path, task: LocalTask, timeout: f64, junit: Option<&Path>) -> ! {
+    let result = if timeout > 0.0 && timeout <= MAX_TIMEOUT_SECONDS {
+        InstanceTree::load(source)
+            .and_then(|tree| run(tree, task, Duration::from_secs_f64(timeout)))
+    } else {
+        Err(format!(
+            "--timeout must be above 0 and at most {MAX_TIMEOUT_SECONDS}"
+        ))
+    };
+    let run = match result {
+        Ok(run) => run,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1)
+        }
+    };
+    println!("{}", run.summary());
+    if let (Outcome::Tests(report), Some(path)) = (&run.outcome, junit) {
+        if let Err(e) = report.write_junit(path) {
+            eprintln!("{e}");
+            std::process::exit(1)
+        }
+    }
+    std::process::exit(if run.passed() { 0 } else { 1 })
+}
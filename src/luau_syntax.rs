@@ -0,0 +1,60 @@
+//! Luau syntax checks for the tools that write script source.
+//!
+//! Sources are parsed with full-moon before they reach the plugin, so code that doesn't
+//! parse is rejected with a position instead of surfacing later as a runtime error in
+//! the output log.
+
+use full_moon::LuaVersion;
+
+/// Errors after the first few are usually cascades of the same mistake
+const MAX_LISTED_ERRORS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    /// One-based line and column
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Parse a script as Luau, returning every syntax error found
+pub fn check(source: &str) -> Result<(), Vec<SyntaxError>> {
+    let result = full_moon::parse_fallible(source, LuaVersion::luau());
+    if result.errors().is_empty() {
+        return Ok(());
+    }
+    Err(result
+        .errors()
+        .iter()
+        .map(|error| {
+            let (start, _) = error.range();
+            SyntaxError {
+                line: start.line(),
+                column: start.character(),
+                message: error.error_message().into_owned(),
+            }
+        })
+        .collect())
+}
+
+/// Describe syntax errors for a tool result
+pub fn describe(path: &str, errors: &[SyntaxError]) -> String {
+    let mut text = format!(
+        "{path} has {} Luau syntax error(s); nothing was written:",
+        errors.len()
+    );
+    for error in errors.iter().take(MAX_LISTED_ERRORS) {
+        text.push_str(&format!(
+            "\n  line {}, column {}: {}",
+            error.line, error.column, error.message
+        ));
+    }
+    if errors.len() > MAX_LISTED_ERRORS {
+        text.push_str(&format!(
+            "\n  ... and {} more",
+            errors.len() - MAX_LISTED_ERRORS
+        ));
+    }
+    text.push_str("\nFix the source, or pass allow_invalid: true to write it anyway.");
+    text
+}
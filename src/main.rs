@@ -1,20 +1,44 @@
 use axum::routing::{get, post};
-use clap::Parser;
+use clap::{ArgGroup, Parser, Subcommand};
 use color_eyre::eyre::Result;
+use luau_format::FormatSettings;
+use luau_local::LocalTask;
 use rbx_studio_server::{
     dud_proxy_loop, get_input_commands_handler, get_server_code_handler,
     post_input_command_handler, post_server_code_result_handler, proxy_handler, request_handler,
     response_handler, AppState, RBXStudioServer, STUDIO_PLUGIN_PORT,
 };
 use rmcp::ServiceExt;
+use script_history::HistoryStore;
 use std::io;
 use std::net::Ipv4Addr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing_subscriber::{self, EnvFilter};
+mod attributes;
+mod coverage;
 mod error;
+mod heightmap;
 mod install;
+mod instance_query;
+mod luau_format;
+mod luau_lint;
+mod luau_local;
+mod luau_syntax;
+mod model_file;
+mod module_graph;
+mod playtest_helper;
+mod probes;
+mod project_sync;
 mod rbx_studio_server;
+mod script_export;
+mod script_history;
+mod script_patch;
+mod script_refactor;
+mod script_search;
+mod terrain;
+mod test_report;
 
 /// Kill any existing process using our port to prevent stale server issues.
 /// This is necessary because old MCP server processes can linger and cause conflicts.
@@ -89,6 +113,109 @@ struct Args {
     /// Run as MCP server on stdio
     #[arg(short, long)]
     stdio: bool,
+
+    /// Format scripts written by write_script with StyLua unless a call passes format: false
+    #[arg(long)]
+    format_scripts: bool,
+
+    /// StyLua config for formatting scripts. Defaults to the nearest stylua.toml or
+    /// .stylua.toml in the working directory or its parents.
+    #[arg(long, value_name = "PATH")]
+    stylua_config: Option<PathBuf>,
+
+    /// Directory for the revisions of scripts the tools overwrite, the probes add_probe
+    /// tracks and the active coverage run. Defaults to a RobloxStudioMCP directory in the user's local data directory.
+    #[arg(long, value_name = "PATH")]
+    history_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build a Rojo project to a place or model file (used by sync_project)
+    #[command(hide = true)]
+    BuildProject { project: PathBuf, output: PathBuf },
+
+    /// Run Luau code or TestEZ specs against a Rojo project or place file, without Studio.
+    /// Exits with status 1 when the code errors or a test fails.
+    #[command(group = ArgGroup::new("run").required(true).args(["code", "file", "tests"]))]
+    RunLuauLocal {
+        /// Rojo project (a .project.json file, or a directory with default.project.json)
+        /// or a .rbxl, .rbxlx, .rbxm or .rbxmx file
+        source: PathBuf,
+
+        /// Luau code to run
+        #[arg(short = 'e', long)]
+        code: Option<String>,
+
+        /// File of Luau code to run
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Path of the instance the code runs as `script`, for relative string requires
+        #[arg(long, value_name = "PATH", conflicts_with = "tests")]
+        script: Option<String>,
+
+        /// Run the TestEZ specs
+        #[arg(long)]
+        tests: bool,
+
+        /// Instance to search for specs; repeat for several
+        #[arg(long = "path", value_name = "PATH", requires = "tests")]
+        paths: Vec<String>,
+
+        /// Path to the TestEZ ModuleScript
+        #[arg(long, value_name = "PATH", requires = "tests")]
+        framework_path: Option<String>,
+
+        /// Only run tests whose full name matches this Lua pattern
+        #[arg(long, value_name = "PATTERN", requires = "tests")]
+        name_filter: Option<String>,
+
+        /// Write a JUnit XML report of the tests
+        #[arg(long, value_name = "PATH", requires = "tests")]
+        junit: Option<PathBuf>,
+
+        /// Seconds before the run is stopped
+        #[arg(long, default_value_t = luau_local::DEFAULT_TIMEOUT_SECONDS)]
+        timeout: f64,
+    },
+
+    /// Export the scripts of a place file or Rojo place project to a Rojo directory
+    /// layout, like the export_scripts tool does for the place open in Studio
+    ExportScripts {
+        /// Rojo project (a .project.json file, or a directory with default.project.json)
+        /// or a .rbxl or .rbxlx file
+        source: PathBuf,
+
+        /// Directory to write the layout to
+        output_dir: PathBuf,
+
+        /// Name in default.project.json; defaults to the source's file name
+        #[arg(long)]
+        project_name: Option<String>,
+    },
+
+    /// Write a Rojo sourcemap.json for a place file or Rojo place project, like the
+    /// generate_sourcemap tool does for the place open in Studio
+    Sourcemap {
+        /// Rojo project (a .project.json file, or a directory with default.project.json)
+        /// or a .rbxl or .rbxlx file
+        source: PathBuf,
+
+        /// Export directory to write sourcemap.json to
+        output_dir: PathBuf,
+
+        /// Name of the sourcemap's root; defaults to the source's file name
+        #[arg(long)]
+        project_name: Option<String>,
+
+        /// Also map instances that aren't scripts
+        #[arg(long)]
+        include_non_scripts: bool,
+    },
 }
 
 #[tokio::main]
@@ -102,6 +229,53 @@ async fn main() -> Result<()> {
         .init();
 
     let args = Args::parse();
+    match args.command {
+        Some(Command::BuildProject { project, output }) => project_sync::run_build(project, output),
+        Some(Command::RunLuauLocal {
+            source,
+            code,
+            file,
+            script,
+            tests,
+            paths,
+            framework_path,
+            name_filter,
+            junit,
+            timeout,
+        }) => {
+            let task = if tests {
+                LocalTask::Tests {
+                    paths,
+                    framework_path,
+                    name_filter,
+                }
+            } else {
+                let code = match file {
+                    Some(file) => std::fs::read_to_string(&file)?,
+                    None => code.unwrap_or_default(),
+                };
+                LocalTask::Code { code, script }
+            };
+            luau_local::run_cli(&source, task, timeout, junit.as_deref());
+        }
+        Some(Command::ExportScripts {
+            source,
+            output_dir,
+            project_name,
+        }) => script_export::run_export_cli(&source, &output_dir, project_name),
+        Some(Command::Sourcemap {
+            source,
+            output_dir,
+            project_name,
+            include_non_scripts,
+        }) => script_export::run_sourcemap_cli(
+            &source,
+            &output_dir,
+            project_name,
+            include_non_scripts,
+        ),
+        None => {}
+    }
     if !args.stdio {
         return install::install().await;
     }
@@ -144,7 +318,13 @@ async fn main() -> Result<()> {
     };
 
     // Create an instance of our counter router
-    let service = RBXStudioServer::new(Arc::clone(&server_state))
+    let formatting = FormatSettings {
+        format_by_default: args.format_scripts,
+        config_path: args.stylua_config,
+    };
+    let history_dir = args.history_dir.unwrap_or_else(HistoryStore::default_root);
+    let history = HistoryStore::new(history_dir);
+    let service = RBXStudioServer::new(Arc::clone(&server_state), formatting, history)
         .serve(rmcp::transport::stdio())
         .await
         .inspect_err(|e| {
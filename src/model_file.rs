@@ -0,0 +1,1076 @@
+//! Conversion between instance trees serialized by the plugin and rbx-dom model files.
+//!
+//! The plugin can't enumerate an instance's properties, so the server asks it for the
+//! classes in a subtree, builds a property manifest from the reflection database, and
+//! has the plugin read exactly those properties. Values come back tagged with their
+//! Luau `typeof` and are decoded here against the type the database expects.
+//!
+//! Imports go the other way: a model file is read into a WeakDom here and flattened
+//! into the same tagged form, which the plugin decodes while it builds the instances.
+
+use rbx_dom_weak::types::{
+    Attributes, Axes, BrickColor, CFrame, Color3, Color3uint8, ColorSequence,
+    ColorSequenceKeypoint, Content, ContentId, ContentType, CustomPhysicalProperties, Enum,
+    EnumItem, Faces, Font, FontStyle, FontWeight, Matrix3, NumberRange, NumberSequence,
+    NumberSequenceKeypoint, PhysicalProperties, Ray, Rect, Ref, Tags, UDim, UDim2, Variant,
+    VariantType, Vector2, Vector2int16, Vector3, Vector3int16,
+};
+use rbx_dom_weak::{InstanceBuilder, WeakDom};
+use rbx_reflection::{
+    ClassTag, DataType, PropertyDescriptor, PropertyKind, PropertySerialization,
+    ReflectionDatabase, Scriptability,
+};
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Properties Studio regenerates for every copy of an instance
+const REGENERATED_PROPERTIES: [&str; 3] = ["UniqueId", "HistoryId", "ScriptGuid"];
+
+/// Custom-scriptability properties the plugin reads anyway: Source directly, and
+/// WorldPivotData and Scale through Model:GetPivot() and Model:GetScale()
+const CUSTOM_READABLE_PROPERTIES: [&str; 3] = ["Source", "WorldPivotData", "Scale"];
+
+/// Custom-scriptability properties the plugin sets anyway: Source directly, and
+/// WorldPivotData through Model.WorldPivot. Scale is left out because Model:ScaleTo()
+/// would resize parts that the file already stores at their scaled size.
+const CUSTOM_WRITABLE_PROPERTIES: [&str; 2] = ["Source", "WorldPivotData"];
+
+/// Properties carried outside the manifest: Name is part of every instance record,
+/// and tags and attributes have their own fields
+const SEPARATE_PROPERTIES: [&str; 3] = ["Name", "Tags", "Attributes"];
+
+/// Rojo's JSON model format, which can be read but not written
+const JSON_MODEL_EXTENSION: &str = ".model.json";
+
+/// A value as encoded by the plugin: `{"type": typeof(value), "value": ...}`
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct EncodedValue {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub value: Value,
+}
+
+/// One instance from or for the plugin, listed in pre-order so parents come before children
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct SerializedInstance {
+    pub class_name: String,
+    pub name: String,
+    /// Index of the parent in the instance list; roots have none
+    pub parent: Option<usize>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub properties: HashMap<String, EncodedValue>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub attributes: HashMap<String, EncodedValue>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SerializedTree {
+    pub instances: Vec<SerializedInstance>,
+    /// Properties the plugin could not read or encode
+    #[serde(default)]
+    pub failures: Vec<String>,
+}
+
+/// Properties to read for each class, plus the serialized properties that can't be read
+pub struct PropertyManifest {
+    pub properties: HashMap<String, Vec<String>>,
+    pub unsupported: BTreeSet<String>,
+}
+
+/// Instances to create from a model file, plus everything that couldn't be carried over
+pub struct BuildPlan {
+    pub instances: Vec<SerializedInstance>,
+    /// Properties in the file that plugins can't set
+    pub unsupported: BTreeSet<String>,
+    /// Instances left out, with their descendants, because their class can't be created
+    pub skipped: Vec<String>,
+    /// Values that couldn't be converted
+    pub failures: Vec<String>,
+}
+
+/// Model file formats rbx-dom can read and write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    Binary,
+    Xml,
+}
+
+impl ModelFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "rbxm" => Some(Self::Binary),
+            "rbxmx" => Some(Self::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// Build the list of properties the plugin should read for the given classes
+pub fn property_manifest(classes: &[String]) -> Result<PropertyManifest, String> {
+    let database = rbx_reflection_database::get()
+        .map_err(|e| format!("Failed to load reflection database: {e}"))?;
+
+    let mut manifest = PropertyManifest {
+        properties: HashMap::new(),
+        unsupported: BTreeSet::new(),
+    };
+    for class_name in classes {
+        let Some(class) = database.classes.get(class_name.as_str()) else {
+            manifest.unsupported.insert(format!(
+                "{class_name} (class not in the reflection database)"
+            ));
+            continue;
+        };
+
+        let mut names = Vec::new();
+        for ancestor in database.superclasses_iter(class) {
+            for (name, property) in &ancestor.properties {
+                let serializes = matches!(
+                    property.kind,
+                    PropertyKind::Canonical {
+                        serialization: PropertySerialization::Serializes
+                            | PropertySerialization::SerializesAs(_)
+                    }
+                );
+                if !serializes
+                    || SEPARATE_PROPERTIES.contains(&name.as_ref())
+                    || REGENERATED_PROPERTIES.contains(&name.as_ref())
+                {
+                    continue;
+                }
+
+                let readable = matches!(
+                    property.scriptability,
+                    Scriptability::ReadWrite | Scriptability::Read
+                ) || CUSTOM_READABLE_PROPERTIES.contains(&name.as_ref());
+                if !readable {
+                    manifest.unsupported.insert(format!(
+                        "{}.{name} (not readable from plugins)",
+                        ancestor.name
+                    ));
+                } else if !is_supported_type(&property.data_type) {
+                    manifest.unsupported.insert(format!(
+                        "{}.{name} ({:?} values are not supported)",
+                        ancestor.name, property.data_type
+                    ));
+                } else {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        manifest.properties.insert(class_name.clone(), names);
+    }
+    Ok(manifest)
+}
+
+fn is_supported_type(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Enum(_) => true,
+        DataType::Value(ty) => matches!(
+            ty,
+            VariantType::Axes
+                | VariantType::Bool
+                | VariantType::BrickColor
+                | VariantType::CFrame
+                | VariantType::Color3
+                | VariantType::Color3uint8
+                | VariantType::ColorSequence
+                | VariantType::Content
+                | VariantType::ContentId
+                | VariantType::Faces
+                | VariantType::Float32
+                | VariantType::Float64
+                | VariantType::Font
+                | VariantType::Int32
+                | VariantType::Int64
+                | VariantType::NumberRange
+                | VariantType::NumberSequence
+                | VariantType::OptionalCFrame
+                | VariantType::PhysicalProperties
+                | VariantType::Ray
+                | VariantType::Rect
+                | VariantType::Ref
+                | VariantType::String
+                | VariantType::UDim
+                | VariantType::UDim2
+                | VariantType::Vector2
+                | VariantType::Vector2int16
+                | VariantType::Vector3
+                | VariantType::Vector3int16
+        ),
+        _ => false,
+    }
+}
+
+/// Convert a serialized tree into a WeakDom. Returns the dom, the referents of the
+/// roots, and every property or attribute that couldn't be converted.
+pub fn build_dom(tree: SerializedTree) -> Result<(WeakDom, Vec<Ref>, Vec<String>), String> {
+    let database = rbx_reflection_database::get()
+        .map_err(|e| format!("Failed to load reflection database: {e}"))?;
+
+    let mut failures = tree.failures;
+    let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+    let referents: Vec<Ref> = tree.instances.iter().map(|_| Ref::new()).collect();
+    let mut roots = Vec::new();
+
+    for (index, instance) in tree.instances.into_iter().enumerate() {
+        let mut builder = InstanceBuilder::new(instance.class_name.as_str())
+            .with_referent(referents[index])
+            .with_name(instance.name.as_str());
+
+        let class = database.classes.get(instance.class_name.as_str());
+        for (name, encoded) in instance.properties {
+            let descriptor = class.and_then(|class| {
+                database
+                    .superclasses_iter(class)
+                    .find_map(|c| c.properties.get(name.as_str()))
+            });
+            let Some(descriptor) = descriptor else {
+                failures.push(format!("{}.{name}: unknown property", instance.class_name));
+                continue;
+            };
+            match decode_property(&encoded, &descriptor.data_type, &referents) {
+                Ok(value) => builder.add_property(name.as_str(), value),
+                Err(e) => failures.push(format!("{}.{name}: {e}", instance.class_name)),
+            }
+        }
+
+        if !instance.attributes.is_empty() {
+            let mut attributes = Attributes::new();
+            for (name, encoded) in instance.attributes {
+                match decode_attribute(&encoded) {
+                    Ok(value) => {
+                        attributes.insert(name, value);
+                    }
+                    Err(e) => {
+                        failures.push(format!("{} attribute '{name}': {e}", instance.class_name))
+                    }
+                }
+            }
+            builder.add_property("Attributes", attributes);
+        }
+        if !instance.tags.is_empty() {
+            builder.add_property("Tags", Tags::from(instance.tags));
+        }
+
+        let parent = match instance.parent {
+            Some(parent) if parent < index => referents[parent],
+            Some(parent) => {
+                return Err(format!(
+                    "Instance {index} lists parent {parent}, which does not come before it"
+                ))
+            }
+            None => {
+                roots.push(referents[index]);
+                dom.root_ref()
+            }
+        };
+        dom.insert(parent, builder);
+    }
+
+    Ok((dom, roots, failures))
+}
+
+/// What a build plan is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanMode {
+    /// Create new instances, so values equal to the class default can be left out
+    Import,
+    /// Update existing instances, so every value is sent. Top-level instances may be
+    /// services, which the plugin looks up instead of creating.
+    Sync,
+}
+
+/// Flatten the children of the dom root into the plugin's build order. Instances that
+/// can't be created are left out along with their descendants, and properties are
+/// left out when they can't be set from a plugin.
+pub fn build_plan(dom: &WeakDom, mode: PlanMode) -> Result<BuildPlan, String> {
+    let database = rbx_reflection_database::get()
+        .map_err(|e| format!("Failed to load reflection database: {e}"))?;
+
+    let mut plan = BuildPlan {
+        instances: Vec::new(),
+        unsupported: BTreeSet::new(),
+        skipped: Vec::new(),
+        failures: Vec::new(),
+    };
+
+    // First walk: pick the instances to build so references can be resolved to indices
+    let mut order: Vec<(Ref, Option<usize>, String)> = Vec::new();
+    let mut pending: Vec<(Ref, Option<usize>, String)> = dom
+        .root()
+        .children()
+        .iter()
+        .rev()
+        .map(|r| (*r, None, String::new()))
+        .collect();
+    while let Some((referent, parent, parent_path)) = pending.pop() {
+        let Some(instance) = dom.get_by_ref(referent) else {
+            continue;
+        };
+        let path = if parent_path.is_empty() {
+            instance.name.clone()
+        } else {
+            format!("{parent_path}.{}", instance.name)
+        };
+        let creatable = match check_creatable(database, instance.class.as_str()) {
+            Err(_)
+                if mode == PlanMode::Sync
+                    && parent.is_none()
+                    && is_service(instance.class.as_str()) =>
+            {
+                Ok(())
+            }
+            result => result,
+        };
+        if let Err(reason) = creatable {
+            let descendants = dom.descendants_of(referent).count() - 1;
+            plan.skipped.push(if descendants > 0 {
+                format!("{path}: {reason} (skipped with {descendants} descendant(s))")
+            } else {
+                format!("{path}: {reason}")
+            });
+            continue;
+        }
+        let index = order.len();
+        for child in instance.children().iter().rev() {
+            pending.push((*child, Some(index), path.clone()));
+        }
+        order.push((referent, parent, path));
+    }
+
+    let indices: HashMap<Ref, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(index, (referent, _, _))| (*referent, index))
+        .collect();
+
+    for (referent, parent, path) in order {
+        let instance = dom
+            .get_by_ref(referent)
+            .ok_or("Model references a missing instance")?;
+        let class_name = instance.class.as_str();
+        let class = database
+            .classes
+            .get(class_name)
+            .ok_or_else(|| format!("{class_name} is not in the reflection database"))?;
+
+        let mut record = SerializedInstance {
+            class_name: class_name.to_string(),
+            name: instance.name.clone(),
+            parent,
+            properties: HashMap::new(),
+            attributes: HashMap::new(),
+            tags: Vec::new(),
+        };
+
+        for (name, value) in &instance.properties {
+            let name = name.as_str();
+            match value {
+                Variant::Tags(tags) => {
+                    record.tags = tags.iter().map(str::to_string).collect();
+                    continue;
+                }
+                Variant::Attributes(attributes) => {
+                    for (attribute, value) in attributes.iter() {
+                        match encode_variant(value, None, &indices) {
+                            Ok(encoded) => {
+                                record.attributes.insert(attribute.clone(), encoded);
+                            }
+                            Err(e) => plan
+                                .failures
+                                .push(format!("{path} attribute '{attribute}': {e}")),
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            if SEPARATE_PROPERTIES.contains(&name) || REGENERATED_PROPERTIES.contains(&name) {
+                continue;
+            }
+            let is_default = database.find_default_property(class, name) == Some(value);
+            if is_default && mode == PlanMode::Import {
+                continue;
+            }
+            // Unset references are the default for new instances
+            if matches!(value, Variant::Ref(r) if r.is_none()) {
+                continue;
+            }
+
+            let Some((owner, descriptor)) = database
+                .superclasses_iter(class)
+                .find_map(|c| c.properties.get(name).map(|d| (c, d)))
+            else {
+                plan.unsupported
+                    .insert(format!("{class_name}.{name} (unknown property)"));
+                continue;
+            };
+            let writable = matches!(descriptor.scriptability, Scriptability::ReadWrite)
+                || CUSTOM_WRITABLE_PROPERTIES.contains(&name);
+            let problem = if !writable {
+                Some("not settable from plugins".to_string())
+            } else if !is_supported_type(&descriptor.data_type) {
+                Some(format!(
+                    "{:?} values are not supported",
+                    descriptor.data_type
+                ))
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                // Nothing is lost when a value that can't be set is the default anyway
+                if !is_default {
+                    plan.unsupported
+                        .insert(format!("{}.{name} ({problem})", owner.name));
+                }
+                continue;
+            }
+
+            let enum_type = match &descriptor.data_type {
+                DataType::Enum(enum_name) => Some(enum_name.as_ref()),
+                _ => None,
+            };
+            match encode_variant(value, enum_type, &indices) {
+                Ok(encoded) => {
+                    record.properties.insert(name.to_string(), encoded);
+                }
+                Err(e) => plan.failures.push(format!("{path}.{name}: {e}")),
+            }
+        }
+        plan.instances.push(record);
+    }
+
+    Ok(plan)
+}
+
+fn is_service(class_name: &str) -> bool {
+    rbx_reflection_database::get()
+        .ok()
+        .and_then(|database| database.classes.get(class_name))
+        .is_some_and(|class| class.tags.contains(&ClassTag::Service))
+}
+
+fn check_creatable(database: &ReflectionDatabase, class_name: &str) -> Result<(), String> {
+    let class = database
+        .classes
+        .get(class_name)
+        .ok_or_else(|| format!("{class_name} is not in the reflection database"))?;
+    if class.tags.contains(&ClassTag::Service) {
+        return Err(format!("{class_name} is a service"));
+    }
+    if class.tags.contains(&ClassTag::NotCreatable) {
+        return Err(format!("{class_name} can't be created by plugins"));
+    }
+    Ok(())
+}
+
+/// Write the given roots and their descendants to a .rbxm or .rbxmx file
+pub fn write_model(path: &Path, dom: &WeakDom, roots: &[Ref]) -> Result<(), String> {
+    let format = ModelFormat::from_path(path)
+        .ok_or_else(|| format!("{} must end in .rbxm or .rbxmx", path.display()))?;
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    let writer = std::io::BufWriter::new(file);
+    match format {
+        ModelFormat::Binary => rbx_binary::to_writer(writer, dom, roots).map_err(|e| e.to_string()),
+        ModelFormat::Xml => {
+            rbx_xml::to_writer_default(writer, dom, roots).map_err(|e| e.to_string())
+        }
+    }
+    .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Read a .rbxm, .rbxmx or Rojo .model.json file. Returns the dom and any .model.json
+/// properties that couldn't be resolved.
+pub fn read_model(path: &Path) -> Result<(WeakDom, Vec<String>), String> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let reader = std::io::BufReader::new(file);
+
+    if file_name.ends_with(JSON_MODEL_EXTENSION) {
+        let model: JsonModel = serde_json::from_reader(reader)
+            .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+        // Like Rojo, the root takes its name from the file unless the model sets one
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let stem = &file_name[..file_name.len() - JSON_MODEL_EXTENSION.len()];
+        let mut dom = WeakDom::new(InstanceBuilder::new("DataModel"));
+        let mut failures = Vec::new();
+        let root = dom.root_ref();
+        insert_json_model(&mut dom, root, model, stem, &mut failures)?;
+        return Ok((dom, failures));
+    }
+
+    let dom = match ModelFormat::from_path(path) {
+        Some(ModelFormat::Binary) => rbx_binary::from_reader(reader).map_err(|e| e.to_string()),
+        Some(ModelFormat::Xml) => rbx_xml::from_reader_default(reader).map_err(|e| e.to_string()),
+        None => {
+            return Err(format!(
+                "{} must end in .rbxm, .rbxmx or .model.json",
+                path.display()
+            ))
+        }
+    }
+    .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    Ok((dom, Vec::new()))
+}
+
+/// An instance in a Rojo .model.json file
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonModel {
+    #[serde(alias = "Name")]
+    name: Option<String>,
+    #[serde(alias = "ClassName")]
+    class_name: String,
+    #[serde(alias = "Children", default)]
+    children: Vec<JsonModel>,
+    #[serde(alias = "Properties", default)]
+    properties: HashMap<String, Value>,
+    #[serde(default)]
+    attributes: HashMap<String, Value>,
+}
+
+fn insert_json_model(
+    dom: &mut WeakDom,
+    parent: Ref,
+    model: JsonModel,
+    default_name: &str,
+    failures: &mut Vec<String>,
+) -> Result<(), String> {
+    let database = rbx_reflection_database::get()
+        .map_err(|e| format!("Failed to load reflection database: {e}"))?;
+
+    let name = model.name.unwrap_or_else(|| default_name.to_string());
+    let mut builder = InstanceBuilder::new(model.class_name.as_str()).with_name(name.as_str());
+    for (property, value) in model.properties {
+        match resolve_json_value(database, &model.class_name, &property, value) {
+            Ok(value) => builder.add_property(property.as_str(), value),
+            Err(e) => failures.push(format!("{name}.{property}: {e}")),
+        }
+    }
+    if !model.attributes.is_empty() {
+        let mut attributes = Attributes::new();
+        for (attribute, value) in model.attributes {
+            let resolved = match value {
+                Value::Bool(value) => Ok(Variant::Bool(value)),
+                Value::Number(value) => Ok(Variant::Float64(value.as_f64().unwrap_or_default())),
+                Value::String(value) => Ok(Variant::String(value)),
+                value => serde_json::from_value::<Variant>(value).map_err(|e| e.to_string()),
+            };
+            match resolved {
+                Ok(value) => {
+                    attributes.insert(attribute, value);
+                }
+                Err(e) => failures.push(format!("{name} attribute '{attribute}': {e}")),
+            }
+        }
+        builder.add_property("Attributes", attributes);
+    }
+
+    let referent = dom.insert(parent, builder);
+    for child in model.children {
+        let child_name = child.class_name.clone();
+        insert_json_model(dom, referent, child, &child_name, failures)?;
+    }
+    Ok(())
+}
+
+fn find_descriptor<'a>(
+    database: &'a ReflectionDatabase<'a>,
+    class_name: &str,
+    property: &str,
+) -> Option<&'a PropertyDescriptor<'a>> {
+    let class = database.classes.get(class_name)?;
+    database
+        .superclasses_iter(class)
+        .find_map(|c| c.properties.get(property))
+}
+
+/// Resolve a .model.json property the way Rojo does: either fully qualified, like
+/// `{"Vector3": [1, 2, 3]}`, or a bare value interpreted by the property's type
+fn resolve_json_value(
+    database: &ReflectionDatabase,
+    class_name: &str,
+    property: &str,
+    value: Value,
+) -> Result<Variant, String> {
+    if value.as_object().is_some_and(|object| object.len() == 1) {
+        if let Ok(variant) = serde_json::from_value::<Variant>(value.clone()) {
+            return Ok(variant);
+        }
+    }
+
+    let descriptor = find_descriptor(database, class_name, property)
+        .ok_or_else(|| "unknown property".to_string())?;
+    let ty = match &descriptor.data_type {
+        DataType::Enum(enum_name) => {
+            let items = database
+                .enums
+                .get(enum_name.as_ref())
+                .map(|e| &e.items)
+                .ok_or_else(|| format!("unknown enum {enum_name}"))?;
+            return match &value {
+                Value::String(item) => items
+                    .get(item.as_str())
+                    .map(|v| Variant::Enum(Enum::from_u32(*v)))
+                    .ok_or_else(|| format!("'{item}' is not a member of Enum.{enum_name}")),
+                Value::Number(n) => Ok(Variant::Enum(Enum::from_u32(
+                    n.as_u64().ok_or("expected an enum value")? as u32,
+                ))),
+                _ => Err(format!("expected a member of Enum.{enum_name}")),
+            };
+        }
+        DataType::Value(ty) => *ty,
+        _ => return Err("unsupported data type".to_string()),
+    };
+
+    let mismatch = || format!("expected a {ty:?} value, got {value}");
+    Ok(match (ty, &value) {
+        (VariantType::Bool, Value::Bool(b)) => Variant::Bool(*b),
+        (VariantType::Float32, Value::Number(n)) => {
+            Variant::Float32(n.as_f64().unwrap_or_default() as f32)
+        }
+        (VariantType::Float64, Value::Number(n)) => {
+            Variant::Float64(n.as_f64().unwrap_or_default())
+        }
+        (VariantType::Int32, Value::Number(n)) => {
+            Variant::Int32(n.as_f64().unwrap_or_default() as i32)
+        }
+        (VariantType::Int64, Value::Number(n)) => {
+            Variant::Int64(n.as_f64().unwrap_or_default() as i64)
+        }
+        (VariantType::String, Value::String(s)) => Variant::String(s.clone()),
+        (VariantType::Content, Value::String(s)) => Variant::Content(Content::from_uri(s.as_str())),
+        (VariantType::ContentId, Value::String(s)) => {
+            Variant::ContentId(ContentId::from(s.as_str()))
+        }
+        (VariantType::Tags, Value::Array(_)) => Variant::Tags(Tags::from(
+            names(&value)?
+                .into_iter()
+                .map(str::to_string)
+                .collect::<Vec<_>>(),
+        )),
+        (VariantType::Vector2, Value::Array(_)) => {
+            let [x, y] = numbers::<2>(&value)?;
+            Variant::Vector2(Vector2::new(x as f32, y as f32))
+        }
+        (VariantType::Vector3, Value::Array(_)) => Variant::Vector3(vector3(numbers::<3>(&value)?)),
+        (VariantType::Color3, Value::Array(_)) => Variant::Color3(color3(&value)?),
+        (VariantType::CFrame, Value::Array(_)) => Variant::CFrame(cframe(&value)?),
+        (VariantType::Font | VariantType::Attributes, Value::Object(_)) => {
+            let mut qualified = serde_json::Map::new();
+            qualified.insert(format!("{ty:?}"), value.clone());
+            serde_json::from_value::<Variant>(Value::Object(qualified))
+                .map_err(|e| e.to_string())?
+        }
+        _ => return Err(mismatch()),
+    })
+}
+
+fn numbers<const N: usize>(value: &Value) -> Result<[f64; N], String> {
+    let array = value
+        .as_array()
+        .filter(|array| array.len() == N)
+        .ok_or_else(|| format!("expected an array of {N} numbers"))?;
+    let mut result = [0.0; N];
+    for (slot, item) in result.iter_mut().zip(array) {
+        *slot = item.as_f64().ok_or("expected a number")?;
+    }
+    Ok(result)
+}
+
+fn number_rows<const N: usize>(value: &Value) -> Result<Vec<[f64; N]>, String> {
+    value
+        .as_array()
+        .ok_or("expected an array")?
+        .iter()
+        .map(numbers::<N>)
+        .collect()
+}
+
+fn vector3(v: [f64; 3]) -> Vector3 {
+    Vector3::new(v[0] as f32, v[1] as f32, v[2] as f32)
+}
+
+fn cframe(value: &Value) -> Result<CFrame, String> {
+    // Same order as CFrame:GetComponents(): position, then the rotation matrix row by row
+    let c = numbers::<12>(value)?;
+    Ok(CFrame::new(
+        vector3([c[0], c[1], c[2]]),
+        Matrix3::new(
+            vector3([c[3], c[4], c[5]]),
+            vector3([c[6], c[7], c[8]]),
+            vector3([c[9], c[10], c[11]]),
+        ),
+    ))
+}
+
+fn color3(value: &Value) -> Result<Color3, String> {
+    let [r, g, b] = numbers::<3>(value)?;
+    Ok(Color3::new(r as f32, g as f32, b as f32))
+}
+
+fn names(value: &Value) -> Result<Vec<&str>, String> {
+    value
+        .as_array()
+        .ok_or("expected an array of names")?
+        .iter()
+        .map(|v| v.as_str().ok_or_else(|| "expected a name".to_string()))
+        .collect()
+}
+
+fn enum_value(value: &Value) -> Result<u32, String> {
+    value
+        .get("value")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .ok_or_else(|| "expected an EnumItem".to_string())
+}
+
+fn font(value: &Value) -> Result<Font, String> {
+    let family = value
+        .get("family")
+        .and_then(Value::as_str)
+        .ok_or("expected a font family")?;
+    let weight = value
+        .get("weight")
+        .and_then(Value::as_u64)
+        .and_then(|w| FontWeight::from_u16(w as u16))
+        .unwrap_or_default();
+    let style = value
+        .get("style")
+        .and_then(Value::as_u64)
+        .and_then(|s| FontStyle::from_u8(s as u8))
+        .unwrap_or_default();
+    Ok(Font::new(family, weight, style))
+}
+
+/// Decode a value whose Luau type alone determines its Variant, as for attributes
+fn decode_attribute(encoded: &EncodedValue) -> Result<Variant, String> {
+    let value = &encoded.value;
+    Ok(match encoded.kind.as_str() {
+        "boolean" => Variant::Bool(value.as_bool().ok_or("expected a boolean")?),
+        "number" => Variant::Float64(value.as_f64().ok_or("expected a number")?),
+        "string" => Variant::String(value.as_str().ok_or("expected a string")?.to_string()),
+        "Vector2" => {
+            let [x, y] = numbers::<2>(value)?;
+            Variant::Vector2(Vector2::new(x as f32, y as f32))
+        }
+        "Vector3" => Variant::Vector3(vector3(numbers::<3>(value)?)),
+        "CFrame" => Variant::CFrame(cframe(value)?),
+        "Color3" => Variant::Color3(color3(value)?),
+        "BrickColor" => {
+            let number = value.as_u64().ok_or("expected a BrickColor number")?;
+            Variant::BrickColor(
+                BrickColor::from_number(number as u16).ok_or("unknown BrickColor number")?,
+            )
+        }
+        "UDim" => {
+            let [scale, offset] = numbers::<2>(value)?;
+            Variant::UDim(UDim::new(scale as f32, offset as i32))
+        }
+        "UDim2" => {
+            let [xs, xo, ys, yo] = numbers::<4>(value)?;
+            Variant::UDim2(UDim2::new(
+                UDim::new(xs as f32, xo as i32),
+                UDim::new(ys as f32, yo as i32),
+            ))
+        }
+        "Rect" => {
+            let [x0, y0, x1, y1] = numbers::<4>(value)?;
+            Variant::Rect(Rect::new(
+                Vector2::new(x0 as f32, y0 as f32),
+                Vector2::new(x1 as f32, y1 as f32),
+            ))
+        }
+        "NumberRange" => {
+            let [min, max] = numbers::<2>(value)?;
+            Variant::NumberRange(NumberRange::new(min as f32, max as f32))
+        }
+        "NumberSequence" => Variant::NumberSequence(NumberSequence {
+            keypoints: number_rows::<3>(value)?
+                .into_iter()
+                .map(|[t, v, e]| NumberSequenceKeypoint::new(t as f32, v as f32, e as f32))
+                .collect(),
+        }),
+        "ColorSequence" => Variant::ColorSequence(ColorSequence {
+            keypoints: number_rows::<4>(value)?
+                .into_iter()
+                .map(|[t, r, g, b]| {
+                    ColorSequenceKeypoint::new(t as f32, Color3::new(r as f32, g as f32, b as f32))
+                })
+                .collect(),
+        }),
+        "Font" => Variant::Font(font(value)?),
+        "EnumItem" => Variant::EnumItem(EnumItem {
+            ty: value
+                .get("enumType")
+                .and_then(Value::as_str)
+                .ok_or("expected an enum type")?
+                .to_string(),
+            value: enum_value(value)?,
+        }),
+        other => return Err(format!("{other} values are not supported")),
+    })
+}
+
+/// Decode a property value into the Variant type the reflection database expects
+fn decode_property(
+    encoded: &EncodedValue,
+    data_type: &DataType,
+    referents: &[Ref],
+) -> Result<Variant, String> {
+    let value = &encoded.value;
+    let ty = match data_type {
+        DataType::Enum(_) => return Ok(Variant::Enum(Enum::from_u32(enum_value(value)?))),
+        DataType::Value(ty) => *ty,
+        _ => return Err("unsupported data type".to_string()),
+    };
+
+    Ok(match ty {
+        VariantType::Bool => Variant::Bool(value.as_bool().ok_or("expected a boolean")?),
+        VariantType::Int32 => Variant::Int32(value.as_f64().ok_or("expected a number")? as i32),
+        VariantType::Int64 => Variant::Int64(value.as_f64().ok_or("expected a number")? as i64),
+        VariantType::Float32 => Variant::Float32(value.as_f64().ok_or("expected a number")? as f32),
+        VariantType::Float64 => Variant::Float64(value.as_f64().ok_or("expected a number")?),
+        VariantType::String => {
+            Variant::String(value.as_str().ok_or("expected a string")?.to_string())
+        }
+        VariantType::ContentId => {
+            Variant::ContentId(ContentId::from(value.as_str().ok_or("expected a string")?))
+        }
+        VariantType::Content => Variant::Content(match value.as_str() {
+            Some(uri) if !uri.is_empty() => Content::from_uri(uri),
+            _ => Content::none(),
+        }),
+        VariantType::Vector2int16 => {
+            let [x, y] = numbers::<2>(value)?;
+            Variant::Vector2int16(Vector2int16::new(x as i16, y as i16))
+        }
+        VariantType::Vector3int16 => {
+            let [x, y, z] = numbers::<3>(value)?;
+            Variant::Vector3int16(Vector3int16::new(x as i16, y as i16, z as i16))
+        }
+        VariantType::OptionalCFrame => Variant::OptionalCFrame(match value {
+            Value::Null => None,
+            _ => Some(cframe(value)?),
+        }),
+        VariantType::Color3uint8 => Variant::Color3uint8(Color3uint8::from(color3(value)?)),
+        VariantType::Ray => {
+            let [ox, oy, oz, dx, dy, dz] = numbers::<6>(value)?;
+            Variant::Ray(Ray::new(vector3([ox, oy, oz]), vector3([dx, dy, dz])))
+        }
+        VariantType::PhysicalProperties => match value {
+            Value::Null => Variant::PhysicalProperties(PhysicalProperties::Default),
+            _ => {
+                let [density, friction, elasticity, friction_weight, elasticity_weight, absorption] =
+                    numbers::<6>(value)?;
+                Variant::PhysicalProperties(PhysicalProperties::Custom(
+                    CustomPhysicalProperties::new(
+                        density as f32,
+                        friction as f32,
+                        elasticity as f32,
+                        friction_weight as f32,
+                        elasticity_weight as f32,
+                        absorption as f32,
+                    ),
+                ))
+            }
+        },
+        VariantType::Faces => {
+            let mut bits = 0;
+            for name in names(value)? {
+                bits |= match name {
+                    "Right" => Faces::RIGHT,
+                    "Top" => Faces::TOP,
+                    "Back" => Faces::BACK,
+                    "Left" => Faces::LEFT,
+                    "Bottom" => Faces::BOTTOM,
+                    "Front" => Faces::FRONT,
+                    other => return Err(format!("unknown face '{other}'")),
+                }
+                .bits();
+            }
+            Variant::Faces(Faces::from_bits(bits).ok_or("invalid faces")?)
+        }
+        VariantType::Axes => {
+            let mut bits = 0;
+            for name in names(value)? {
+                bits |= match name {
+                    "X" => Axes::X,
+                    "Y" => Axes::Y,
+                    "Z" => Axes::Z,
+                    other => return Err(format!("unknown axis '{other}'")),
+                }
+                .bits();
+            }
+            Variant::Axes(Axes::from_bits(bits).ok_or("invalid axes")?)
+        }
+        VariantType::Ref => match value.as_u64() {
+            Some(index) => Variant::Ref(
+                *referents
+                    .get(index as usize)
+                    .ok_or("reference to an unknown instance")?,
+            ),
+            None => Variant::Ref(Ref::none()),
+        },
+        _ => decode_attribute(encoded)?,
+    })
+}
+
+fn encoded(kind: &str, value: Value) -> EncodedValue {
+    EncodedValue {
+        kind: kind.to_string(),
+        value,
+    }
+}
+
+fn vector3_json(v: Vector3) -> Value {
+    json!([v.x, v.y, v.z])
+}
+
+fn cframe_json(c: &CFrame) -> Value {
+    let (p, m) = (c.position, c.orientation);
+    json!([p.x, p.y, p.z, m.x.x, m.x.y, m.x.z, m.y.x, m.y.y, m.y.z, m.z.x, m.z.y, m.z.z])
+}
+
+/// Encode a value in the plugin's tagged form; the inverse of `decode_property`.
+/// `enum_type` names the enum for Enum properties, and `indices` maps referents to
+/// their position in the build order.
+fn encode_variant(
+    value: &Variant,
+    enum_type: Option<&str>,
+    indices: &HashMap<Ref, usize>,
+) -> Result<EncodedValue, String> {
+    Ok(match value {
+        Variant::Bool(b) => encoded("boolean", json!(b)),
+        Variant::Int32(n) => encoded("number", json!(n)),
+        Variant::Int64(n) => encoded("number", json!(n)),
+        Variant::Float32(n) => encoded("number", json!(n)),
+        Variant::Float64(n) => encoded("number", json!(n)),
+        Variant::String(s) => encoded("string", json!(s)),
+        Variant::ContentId(id) => encoded("string", json!(id.as_str())),
+        Variant::Content(content) => match content.value() {
+            ContentType::None => encoded("Content", Value::Null),
+            ContentType::Uri(uri) => encoded("Content", json!(uri)),
+            _ => return Err("Content that points at an object is not supported".to_string()),
+        },
+        Variant::Enum(item) => {
+            let enum_type = enum_type.ok_or("enum value without an enum type")?;
+            encoded(
+                "EnumItem",
+                json!({ "enumType": enum_type, "value": item.to_u32() }),
+            )
+        }
+        Variant::EnumItem(item) => encoded(
+            "EnumItem",
+            json!({ "enumType": item.ty, "value": item.value }),
+        ),
+        Variant::Vector2(v) => encoded("Vector2", json!([v.x, v.y])),
+        Variant::Vector2int16(v) => encoded("Vector2int16", json!([v.x, v.y])),
+        Variant::Vector3(v) => encoded("Vector3", vector3_json(*v)),
+        Variant::Vector3int16(v) => encoded("Vector3int16", json!([v.x, v.y, v.z])),
+        Variant::CFrame(c) => encoded("CFrame", cframe_json(c)),
+        Variant::OptionalCFrame(c) => match c {
+            Some(c) => encoded("CFrame", cframe_json(c)),
+            None => encoded("nil", Value::Null),
+        },
+        Variant::Color3(c) => encoded("Color3", json!([c.r, c.g, c.b])),
+        Variant::Color3uint8(c) => {
+            let c = Color3::from(*c);
+            encoded("Color3", json!([c.r, c.g, c.b]))
+        }
+        Variant::BrickColor(color) => encoded("BrickColor", json!(*color as u16)),
+        Variant::UDim(u) => encoded("UDim", json!([u.scale, u.offset])),
+        Variant::UDim2(u) => encoded(
+            "UDim2",
+            json!([u.x.scale, u.x.offset, u.y.scale, u.y.offset]),
+        ),
+        Variant::Rect(r) => encoded("Rect", json!([r.min.x, r.min.y, r.max.x, r.max.y])),
+        Variant::NumberRange(r) => encoded("NumberRange", json!([r.min, r.max])),
+        Variant::NumberSequence(sequence) => encoded(
+            "NumberSequence",
+            Value::Array(
+                sequence
+                    .keypoints
+                    .iter()
+                    .map(|k| json!([k.time, k.value, k.envelope]))
+                    .collect(),
+            ),
+        ),
+        Variant::ColorSequence(sequence) => encoded(
+            "ColorSequence",
+            Value::Array(
+                sequence
+                    .keypoints
+                    .iter()
+                    .map(|k| json!([k.time, k.color.r, k.color.g, k.color.b]))
+                    .collect(),
+            ),
+        ),
+        Variant::PhysicalProperties(PhysicalProperties::Default) => encoded("nil", Value::Null),
+        Variant::PhysicalProperties(PhysicalProperties::Custom(p)) => encoded(
+            "PhysicalProperties",
+            json!([
+                p.density(),
+                p.friction(),
+                p.elasticity(),
+                p.friction_weight(),
+                p.elasticity_weight(),
+                p.acoustic_absorption()
+            ]),
+        ),
+        Variant::Faces(faces) => {
+            let names: Vec<&str> = [
+                (Faces::RIGHT, "Right"),
+                (Faces::TOP, "Top"),
+                (Faces::BACK, "Back"),
+                (Faces::LEFT, "Left"),
+                (Faces::BOTTOM, "Bottom"),
+                (Faces::FRONT, "Front"),
+            ]
+            .into_iter()
+            .filter(|(face, _)| faces.contains(*face))
+            .map(|(_, name)| name)
+            .collect();
+            encoded("Faces", json!(names))
+        }
+        Variant::Axes(axes) => {
+            let names: Vec<&str> = [(Axes::X, "X"), (Axes::Y, "Y"), (Axes::Z, "Z")]
+                .into_iter()
+                .filter(|(axis, _)| axes.contains(*axis))
+                .map(|(_, name)| name)
+                .collect();
+            encoded("Axes", json!(names))
+        }
+        Variant::Font(font) => encoded(
+            "Font",
+            json!({
+                "family": font.family,
+                "weight": font.weight.as_u16(),
+                "style": font.style.as_u8(),
+            }),
+        ),
+        Variant::Ray(ray) => {
+            let (o, d) = (ray.origin, ray.direction);
+            encoded("Ray", json!([o.x, o.y, o.z, d.x, d.y, d.z]))
+        }
+        Variant::Ref(referent) => match indices.get(referent) {
+            Some(index) => encoded("Instance", json!(index)),
+            None => return Err("references an instance that isn't being imported".to_string()),
+        },
+        other => return Err(format!("{:?} values are not supported", other.ty())),
+    })
+}
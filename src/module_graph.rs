@@ -0,0 +1,863 @@
+//! Static `require` resolution for the module_graph tool.
+//!
+//! Every script collected from the place is parsed with full-moon and each `require(...)`
+//! argument is evaluated against the collected instance tree. `script`, `game`,
+//! `workspace`, `.Parent`, `.Child` and `["Child"]` indexing, `GetService`, `WaitForChild`,
+//! `FindFirstChild`, `FindFirstAncestor`, string requires (`./`, `../`, `@self`) and locals
+//! assigned any of these are followed. Anything dynamic is reported as unresolved rather
+//! than guessed.
+
+use crate::script_export::CollectedScripts;
+use full_moon::ast::{
+    Ast, Call, Expression, FunctionArgs, FunctionCall, Index, LastStmt, LocalAssignment, Prefix,
+    Suffix, Var,
+};
+use full_moon::node::Node;
+use full_moon::tokenizer::{TokenReference, TokenType};
+use full_moon::visitors::Visitor;
+use full_moon::LuaVersion;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Longest require expression quoted back in a report
+const MAX_EXPRESSION_LENGTH: usize = 120;
+
+#[derive(Debug, Serialize)]
+pub struct ScriptEntry {
+    pub path: String,
+    pub class_name: String,
+    pub requires: Vec<RequireEdge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequireEdge {
+    pub path: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnresolvedRequire {
+    pub script: String,
+    pub line: usize,
+    pub expression: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssetRequire {
+    pub script: String,
+    pub line: usize,
+    pub asset_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyntaxProblem {
+    pub script: String,
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DependencyGraph {
+    /// Every script in tree order, with the modules it requires
+    pub scripts: Vec<ScriptEntry>,
+    /// Each cycle as a path that starts and ends at the same module
+    pub cycles: Vec<Vec<String>>,
+    pub unresolved: Vec<UnresolvedRequire>,
+    /// Requires of published modules by asset id, which can't be followed
+    pub asset_requires: Vec<AssetRequire>,
+    /// ModuleScripts that no resolved require points at
+    pub unrequired_modules: Vec<String>,
+    /// Scripts that don't fully parse; requires before the error are still followed
+    pub syntax_errors: Vec<SyntaxProblem>,
+}
+
+/// A value an expression can evaluate to
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Target {
+    Game,
+    Node(usize),
+    /// What requiring the ModuleScript at this node returns
+    Module(usize),
+    Unknown(String),
+}
+
+/// The collected instance tree, indexed for lookups by name
+pub(crate) struct Tree<'a> {
+    pub(crate) collected: &'a CollectedScripts,
+    pub(crate) paths: Vec<String>,
+    children: Vec<Vec<usize>>,
+    services: Vec<usize>,
+}
+
+impl<'a> Tree<'a> {
+    pub(crate) fn new(collected: &'a CollectedScripts) -> Self {
+        let nodes = &collected.nodes;
+        let mut paths: Vec<String> = Vec::with_capacity(nodes.len());
+        let mut children = vec![Vec::new(); nodes.len()];
+        let mut services = Vec::new();
+        for (index, node) in nodes.iter().enumerate() {
+            match node.parent {
+                Some(parent) => {
+                    paths.push(format!("{}.{}", paths[parent], node.name));
+                    children[parent].push(index);
+                }
+                None => {
+                    paths.push(node.name.clone());
+                    services.push(index);
+                }
+            }
+        }
+        Self {
+            collected,
+            paths,
+            children,
+            services,
+        }
+    }
+
+    /// The node at a path, with or without a leading "game."
+    pub(crate) fn find(&self, path: &str) -> Option<usize> {
+        let path = path.strip_prefix("game.").unwrap_or(path);
+        self.paths.iter().position(|candidate| candidate == path)
+    }
+
+    fn describe(&self, target: &Target) -> String {
+        match target {
+            Target::Game => "game".to_string(),
+            Target::Node(index) => self.paths[*index].clone(),
+            Target::Module(index) => format!("what {} returns", self.paths[*index]),
+            Target::Unknown(reason) => reason.clone(),
+        }
+    }
+
+    fn child(&self, target: Target, name: &str) -> Target {
+        let candidates = match &target {
+            Target::Game => &self.services,
+            Target::Node(index) => &self.children[*index],
+            Target::Module(_) => {
+                return Target::Unknown(format!("{} isn't an instance", self.describe(&target)))
+            }
+            Target::Unknown(_) => return target,
+        };
+        match candidates
+            .iter()
+            .find(|&&child| self.collected.nodes[child].name == name)
+        {
+            Some(&child) => Target::Node(child),
+            None => Target::Unknown(format!(
+                "there's no script at or under {}.{name}",
+                self.describe(&target)
+            )),
+        }
+    }
+
+    pub(crate) fn parent(&self, target: Target) -> Target {
+        match target {
+            Target::Node(index) => match self.collected.nodes[index].parent {
+                Some(parent) => Target::Node(parent),
+                None => Target::Game,
+            },
+            Target::Game => Target::Unknown("game has no Parent".to_string()),
+            Target::Module(_) => {
+                Target::Unknown(format!("{} isn't an instance", self.describe(&target)))
+            }
+            unknown => unknown,
+        }
+    }
+
+    /// Whether any child of the target has this name
+    pub(crate) fn has_child(&self, target: &Target, name: &str) -> bool {
+        let candidates = match target {
+            Target::Game => &self.services,
+            Target::Node(index) => &self.children[*index],
+            _ => return false,
+        };
+        candidates
+            .iter()
+            .any(|&child| self.collected.nodes[child].name == name)
+    }
+
+    fn service(&self, class_name: &str) -> Target {
+        match self
+            .services
+            .iter()
+            .find(|&&service| self.collected.nodes[service].class_name == class_name)
+        {
+            Some(&service) => Target::Node(service),
+            None => Target::Unknown(format!("the place has no scripts in {class_name}")),
+        }
+    }
+
+    fn ancestor(&self, target: Target, name: &str) -> Target {
+        let mut current = self.parent(target.clone());
+        while let Target::Node(index) = current {
+            if self.collected.nodes[index].name == name {
+                return current;
+            }
+            current = self.parent(current);
+        }
+        Target::Unknown(format!(
+            "{} has no ancestor named {name}",
+            self.describe(&target)
+        ))
+    }
+}
+
+/// Resolve every require in the collected scripts
+pub fn build(collected: &CollectedScripts) -> DependencyGraph {
+    let tree = Tree::new(collected);
+    let mut graph = DependencyGraph::default();
+
+    for (index, node) in collected.nodes.iter().enumerate() {
+        let Some(source) = &node.source else {
+            continue;
+        };
+        let result = full_moon::parse_fallible(source, LuaVersion::luau());
+        if let Some(error) = result.errors().first() {
+            graph.syntax_errors.push(SyntaxProblem {
+                script: tree.paths[index].clone(),
+                line: error.range().0.line(),
+                message: error.error_message().into_owned(),
+            });
+        }
+
+        let mut visitor = RequireVisitor {
+            resolver: Resolver::new(&tree, index, result.ast()),
+            source,
+            requires: Vec::new(),
+        };
+        visitor.visit_ast(result.ast());
+
+        let mut entry = ScriptEntry {
+            path: tree.paths[index].clone(),
+            class_name: node.class_name.clone(),
+            requires: Vec::new(),
+        };
+        for require in visitor.requires {
+            match require.target {
+                Ok(target) => entry.requires.push(RequireEdge {
+                    path: tree.paths[target].clone(),
+                    line: require.line,
+                }),
+                Err(RequireProblem::Asset(asset_id)) => graph.asset_requires.push(AssetRequire {
+                    script: entry.path.clone(),
+                    line: require.line,
+                    asset_id,
+                }),
+                Err(RequireProblem::Unresolved(reason)) => {
+                    graph.unresolved.push(UnresolvedRequire {
+                        script: entry.path.clone(),
+                        line: require.line,
+                        expression: require.expression,
+                        reason,
+                    })
+                }
+            }
+        }
+        graph.scripts.push(entry);
+    }
+
+    let by_path: HashMap<&str, usize> = graph
+        .scripts
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| (entry.path.as_str(), i))
+        .collect();
+    let edges: Vec<Vec<usize>> = graph
+        .scripts
+        .iter()
+        .map(|entry| {
+            let mut targets: Vec<usize> = entry
+                .requires
+                .iter()
+                .filter_map(|edge| by_path.get(edge.path.as_str()).copied())
+                .collect();
+            targets.sort_unstable();
+            targets.dedup();
+            targets
+        })
+        .collect();
+
+    let mut required = vec![false; graph.scripts.len()];
+    for targets in &edges {
+        for &target in targets {
+            required[target] = true;
+        }
+    }
+    graph.unrequired_modules = graph
+        .scripts
+        .iter()
+        .zip(&required)
+        .filter(|(entry, required)| entry.class_name == "ModuleScript" && !**required)
+        .map(|(entry, _)| entry.path.clone())
+        .collect();
+
+    graph.cycles = find_cycles(&edges)
+        .into_iter()
+        .map(|cycle| {
+            cycle
+                .into_iter()
+                .map(|i| graph.scripts[i].path.clone())
+                .collect()
+        })
+        .collect();
+    graph
+}
+
+enum RequireProblem {
+    Asset(String),
+    Unresolved(String),
+}
+
+struct FoundRequire {
+    line: usize,
+    expression: String,
+    target: Result<usize, RequireProblem>,
+}
+
+struct RequireVisitor<'a, 'b> {
+    resolver: Resolver<'a, 'b>,
+    source: &'b str,
+    requires: Vec<FoundRequire>,
+}
+
+impl Visitor for RequireVisitor<'_, '_> {
+    fn visit_local_assignment_end(&mut self, assignment: &LocalAssignment) {
+        // Assigned after the values are visited, so `local x = require(x.Child)` reads the old x
+        self.resolver.assign_locals(assignment);
+    }
+
+    fn visit_function_call(&mut self, call: &FunctionCall) {
+        let Prefix::Name(name) = call.prefix() else {
+            return;
+        };
+        if name.token().to_string() != "require" {
+            return;
+        }
+        let Some(Suffix::Call(Call::AnonymousCall(args))) = call.suffixes().next() else {
+            return;
+        };
+        let Some((start, end)) = call.range() else {
+            return;
+        };
+        let text = self
+            .source
+            .get(start.bytes()..end.bytes())
+            .unwrap_or_default();
+        let expression = shorten(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+        let target = self.resolver.require_target(args);
+        self.requires.push(FoundRequire {
+            line: start.line(),
+            expression,
+            target,
+        });
+    }
+}
+
+/// Evaluates expressions in one script against the instance tree
+pub(crate) struct Resolver<'a, 'b> {
+    pub(crate) tree: &'b Tree<'a>,
+    pub(crate) script: usize,
+    /// Locals assigned an instance expression or a required module. Scopes aren't
+    /// tracked, so the latest assignment of a name wins.
+    locals: HashMap<String, Target>,
+    /// The local a ModuleScript returns, e.g. `M` in `return M`, which stands for the
+    /// module's own value however it was assigned
+    pub(crate) module_table: Option<String>,
+}
+
+impl<'a, 'b> Resolver<'a, 'b> {
+    pub(crate) fn new(tree: &'b Tree<'a>, script: usize, ast: &Ast) -> Self {
+        let module_table = match ast.nodes().last_stmt() {
+            Some(LastStmt::Return(ret))
+                if tree.collected.nodes[script].class_name == "ModuleScript" =>
+            {
+                let mut returns = ret.returns().iter();
+                match (returns.next(), returns.next()) {
+                    (Some(Expression::Var(Var::Name(name))), None) => {
+                        Some(name.token().to_string())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        Self {
+            tree,
+            script,
+            locals: HashMap::new(),
+            module_table,
+        }
+    }
+
+    pub(crate) fn assign_locals(&mut self, assignment: &LocalAssignment) {
+        let mut values = assignment.expressions().iter();
+        for name in assignment.names() {
+            let name = name.token().to_string();
+            match values.next() {
+                Some(value) => {
+                    let target = self.evaluate(value);
+                    self.locals.insert(name, target);
+                }
+                None => {
+                    self.locals.remove(&name);
+                }
+            }
+        }
+    }
+
+    fn require_target(&self, args: &FunctionArgs) -> Result<usize, RequireProblem> {
+        let target = match args {
+            FunctionArgs::Parentheses { arguments, .. } => match arguments.iter().next() {
+                Some(Expression::Number(number)) => {
+                    return Err(RequireProblem::Asset(number.token().to_string()))
+                }
+                Some(argument) => match string_literal(argument) {
+                    Some(path) => self.string_require(&path),
+                    None => self.evaluate(argument),
+                },
+                None => Target::Unknown("require has no argument".to_string()),
+            },
+            FunctionArgs::String(token) => match token.token_type() {
+                TokenType::StringLiteral { literal, .. } => self.string_require(literal),
+                _ => Target::Unknown("not an instance path".to_string()),
+            },
+            _ => Target::Unknown("not an instance path".to_string()),
+        };
+        match target {
+            Target::Node(index)
+                if self.tree.collected.nodes[index].class_name == "ModuleScript" =>
+            {
+                Ok(index)
+            }
+            Target::Node(index) => Err(RequireProblem::Unresolved(format!(
+                "{} is a {}, not a ModuleScript",
+                self.tree.paths[index], self.tree.collected.nodes[index].class_name
+            ))),
+            Target::Game => Err(RequireProblem::Unresolved(
+                "game isn't a ModuleScript".to_string(),
+            )),
+            Target::Module(_) => Err(RequireProblem::Unresolved(format!(
+                "{} isn't a ModuleScript",
+                self.tree.describe(&target)
+            ))),
+            Target::Unknown(reason) => Err(RequireProblem::Unresolved(reason)),
+        }
+    }
+
+    /// A Luau require-by-string path, relative to the script
+    pub(crate) fn string_require(&self, path: &str) -> Target {
+        let mut segments = path.split('/');
+        let script = Target::Node(self.script);
+        let mut target = match segments.next() {
+            Some(".") => self.tree.parent(script),
+            Some("..") => self.tree.parent(self.tree.parent(script)),
+            Some("@self") => script,
+            _ => {
+                return Target::Unknown(format!(
+                    "\"{path}\" isn't relative; only ./, ../ and @self paths are followed"
+                ))
+            }
+        };
+        for segment in segments {
+            target = match segment {
+                "" | "." => target,
+                ".." => self.tree.parent(target),
+                name => self.tree.child(target, name),
+            };
+        }
+        target
+    }
+
+    fn evaluate(&self, expression: &Expression) -> Target {
+        match expression {
+            Expression::Parentheses { expression, .. } => self.evaluate(expression),
+            Expression::TypeAssertion { expression, .. } => self.evaluate(expression),
+            Expression::Var(Var::Name(name)) => self.name(name),
+            Expression::Var(Var::Expression(var)) => self.chain(var.prefix(), var.suffixes()),
+            Expression::FunctionCall(call) => self.chain(call.prefix(), call.suffixes()),
+            _ => Target::Unknown(format!(
+                "`{}` isn't an instance path",
+                shorten(expression.to_string().trim())
+            )),
+        }
+    }
+
+    pub(crate) fn name(&self, name: &TokenReference) -> Target {
+        let name = name.token().to_string();
+        if self.module_table.as_ref() == Some(&name) {
+            return Target::Module(self.script);
+        }
+        match name.as_str() {
+            "script" => Target::Node(self.script),
+            "game" => Target::Game,
+            "workspace" => self.tree.service("Workspace"),
+            _ => self.locals.get(&name).cloned().unwrap_or_else(|| {
+                Target::Unknown(format!("`{name}` isn't a local assigned an instance path"))
+            }),
+        }
+    }
+
+    /// What the start of a chain evaluates to, and how many of its suffixes that took:
+    /// one for `require(...)`, otherwise none
+    pub(crate) fn head(&self, prefix: &Prefix, suffixes: &[&Suffix]) -> (Target, usize) {
+        match prefix {
+            Prefix::Name(name) => {
+                if name.token().to_string() == "require" {
+                    if let Some(Suffix::Call(Call::AnonymousCall(args))) = suffixes.first() {
+                        let target = match self.require_target(args) {
+                            Ok(index) => Target::Module(index),
+                            Err(RequireProblem::Asset(asset_id)) => {
+                                Target::Unknown(format!("requires asset {asset_id}"))
+                            }
+                            Err(RequireProblem::Unresolved(reason)) => Target::Unknown(reason),
+                        };
+                        return (target, 1);
+                    }
+                }
+                (self.name(name), 0)
+            }
+            Prefix::Expression(expression) => (self.evaluate(expression), 0),
+            _ => (Target::Unknown("not an instance path".to_string()), 0),
+        }
+    }
+
+    fn chain<'s>(&self, prefix: &Prefix, suffixes: impl Iterator<Item = &'s Suffix>) -> Target {
+        let suffixes: Vec<&Suffix> = suffixes.collect();
+        let (mut target, taken) = self.head(prefix, &suffixes);
+        for suffix in &suffixes[taken..] {
+            if let Target::Unknown(_) = target {
+                return target;
+            }
+            target = self.step(target, suffix);
+        }
+        target
+    }
+
+    /// `.Name` on a target
+    pub(crate) fn index(&self, target: Target, name: &str) -> Target {
+        match name {
+            "Parent" => self.tree.parent(target),
+            child => self.tree.child(target, child),
+        }
+    }
+
+    /// Apply one index or call of a chain to a target
+    pub(crate) fn step(&self, target: Target, suffix: &Suffix) -> Target {
+        if let Target::Unknown(_) = target {
+            return target;
+        }
+        match suffix {
+            Suffix::Index(Index::Dot { name, .. }) => self.index(target, &name.token().to_string()),
+            Suffix::Index(Index::Brackets { expression, .. }) => match string_literal(expression) {
+                Some(child) => self.tree.child(target, &child),
+                None => Target::Unknown("indexed with a computed name".to_string()),
+            },
+            Suffix::Call(Call::MethodCall(method)) => {
+                let name = method.name().token().to_string();
+                let argument = match method.args() {
+                    FunctionArgs::Parentheses { arguments, .. } => {
+                        arguments.iter().next().and_then(string_literal)
+                    }
+                    FunctionArgs::String(token) => match token.token_type() {
+                        TokenType::StringLiteral { literal, .. } => Some(literal.to_string()),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match (name.as_str(), argument) {
+                    ("GetService" | "FindService", Some(class_name))
+                        if matches!(target, Target::Game) =>
+                    {
+                        self.tree.service(&class_name)
+                    }
+                    ("WaitForChild" | "FindFirstChild", Some(child)) => {
+                        self.tree.child(target, &child)
+                    }
+                    ("FindFirstAncestor", Some(ancestor)) => self.tree.ancestor(target, &ancestor),
+                    (_, None) => {
+                        Target::Unknown(format!(":{name}() is called with a computed name"))
+                    }
+                    _ => Target::Unknown(format!(":{name}() isn't followed")),
+                }
+            }
+            Suffix::Call(_) => Target::Unknown("calls a function".to_string()),
+            _ => target,
+        }
+    }
+}
+
+fn string_literal(expression: &Expression) -> Option<String> {
+    match expression {
+        Expression::String(token) => match token.token_type() {
+            TokenType::StringLiteral { literal, .. } => Some(literal.to_string()),
+            _ => None,
+        },
+        Expression::Parentheses { expression, .. } => string_literal(expression),
+        _ => None,
+    }
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= MAX_EXPRESSION_LENGTH {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(MAX_EXPRESSION_LENGTH).collect();
+    short.push_str("...");
+    short
+}
+
+/// Find each strongly connected group of modules with Tarjan's algorithm, and report one
+/// cycle through each
+fn find_cycles(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State<'e> {
+        edges: &'e [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn connect(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next);
+        state.low[node] = state.next;
+        state.next += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+        for &target in &state.edges[node] {
+            match state.index[target] {
+                None => {
+                    connect(state, target);
+                    state.low[node] = state.low[node].min(state.low[target]);
+                }
+                Some(index) if state.on_stack[target] => {
+                    state.low[node] = state.low[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+        if Some(state.low[node]) == state.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        edges,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        on_stack: vec![false; edges.len()],
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for node in 0..edges.len() {
+        if state.index[node].is_none() {
+            connect(&mut state, node);
+        }
+    }
+
+    let mut cycles: Vec<Vec<usize>> = state
+        .components
+        .into_iter()
+        .filter(|component| component.len() > 1 || edges[component[0]].contains(&component[0]))
+        .map(|mut component| {
+            component.sort_unstable();
+            cycle_through(edges, &component)
+        })
+        .collect();
+    cycles.sort();
+    cycles
+}
+
+/// The shortest cycle from the first member of a strongly connected group back to itself
+fn cycle_through(edges: &[Vec<usize>], component: &[usize]) -> Vec<usize> {
+    let start = component[0];
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut queue = std::collections::VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &target in &edges[node] {
+            if target == start {
+                let mut cycle = vec![node];
+                let mut current = node;
+                while current != start {
+                    current = previous[&current];
+                    cycle.push(current);
+                }
+                cycle.reverse();
+                cycle.push(start);
+                return cycle;
+            }
+            if component.binary_search(&target).is_ok() && !previous.contains_key(&target) {
+                previous.insert(target, node);
+                queue.push_back(target);
+            }
+        }
+    }
+    component.to_vec()
+}
+
+impl DependencyGraph {
+    pub fn require_count(&self) -> usize {
+        self.scripts.iter().map(|entry| entry.requires.len()).sum()
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Graphviz source: Scripts and LocalScripts are ellipses, ModuleScripts boxes,
+    /// modules nothing requires dashed, and edges that are part of a cycle red
+    pub fn to_dot(&self) -> String {
+        let mut cycle_edges: Vec<(&str, &str)> = Vec::new();
+        for cycle in &self.cycles {
+            for pair in cycle.windows(2) {
+                cycle_edges.push((&pair[0], &pair[1]));
+            }
+        }
+
+        let mut dot = String::from("digraph modules {\n  rankdir=LR;\n  node [shape=box];\n");
+        for entry in &self.scripts {
+            let mut attributes = Vec::new();
+            if entry.class_name != "ModuleScript" {
+                attributes.push("shape=ellipse");
+            }
+            if self.unrequired_modules.contains(&entry.path) {
+                attributes.push("style=dashed");
+            }
+            dot.push_str(&format!("  {}", quote(&entry.path)));
+            if !attributes.is_empty() {
+                dot.push_str(&format!(" [{}]", attributes.join(", ")));
+            }
+            dot.push_str(";\n");
+        }
+        for entry in &self.scripts {
+            let mut targets: Vec<&str> = entry.requires.iter().map(|e| e.path.as_str()).collect();
+            targets.sort_unstable();
+            targets.dedup();
+            for target in targets {
+                dot.push_str(&format!("  {} -> {}", quote(&entry.path), quote(target)));
+                if cycle_edges.contains(&(entry.path.as_str(), target)) {
+                    dot.push_str(" [color=red]");
+                }
+                dot.push_str(";\n");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script_export::ScriptNode;
+
+    fn node(
+        name: &str,
+        class_name: &str,
+        parent: Option<usize>,
+        source: Option<&str>,
+    ) -> ScriptNode {
+        ScriptNode {
+            name: name.to_string(),
+            class_name: class_name.to_string(),
+            parent,
+            source: source.map(str::to_string),
+            run_context: None,
+            disabled: false,
+            partial: false,
+            unexported: false,
+        }
+    }
+
+    fn graph(nodes: Vec<ScriptNode>) -> DependencyGraph {
+        build(&CollectedScripts {
+            place_name: "Place".to_string(),
+            nodes,
+        })
+    }
+
+    fn requires<'a>(graph: &'a DependencyGraph, path: &str) -> Vec<&'a str> {
+        let entry = graph
+            .scripts
+            .iter()
+            .find(|entry| entry.path == path)
+            .unwrap();
+        entry
+            .requires
+            .iter()
+            .map(|edge| edge.path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn a_local_reassigned_from_itself_resolves_against_its_old_value() {
+        let graph = graph(vec![
+            node("ReplicatedStorage", "ReplicatedStorage", None, None),
+            node("Shared", "Folder", Some(0), None),
+            node("Util", "ModuleScript", Some(1), Some("return {}")),
+            node("ServerScriptService", "ServerScriptService", None, None),
+            node(
+                "Main",
+                "Script",
+                Some(3),
+                Some("local Shared = game:GetService(\"ReplicatedStorage\").Shared\nlocal Shared = require(Shared.Util)\n"),
+            ),
+        ]);
+        assert!(graph.unresolved.is_empty());
+        assert_eq!(
+            requires(&graph, "ServerScriptService.Main"),
+            vec!["ReplicatedStorage.Shared.Util"]
+        );
+    }
+
+    #[test]
+    fn finds_cycles_and_unrequired_modules() {
+        let graph = graph(vec![
+            node("ReplicatedStorage", "ReplicatedStorage", None, None),
+            node(
+                "A",
+                "ModuleScript",
+                Some(0),
+                Some("local B = require(script.Parent.B)\nreturn {}"),
+            ),
+            node(
+                "B",
+                "ModuleScript",
+                Some(0),
+                Some("local A = require(script.Parent.A)\nreturn {}"),
+            ),
+            node("Lonely", "ModuleScript", Some(0), Some("return {}")),
+        ]);
+        assert_eq!(
+            requires(&graph, "ReplicatedStorage.A"),
+            vec!["ReplicatedStorage.B"]
+        );
+        // A cycle ends where it starts
+        assert_eq!(
+            graph.cycles,
+            vec![vec![
+                "ReplicatedStorage.A",
+                "ReplicatedStorage.B",
+                "ReplicatedStorage.A"
+            ]]
+        );
+        assert_eq!(graph.unrequired_modules, vec!["ReplicatedStorage.Lonely"]);
+    }
+}
@@ -0,0 +1,200 @@
+//! What the coverage and probe tools share: a helper ModuleScript in ReplicatedStorage
+//! that scripts rewritten by the MCP server report to, and the statements those rewrites
+//! go in front of.
+//!
+//! Each Luau VM that requires a helper, the playtest server and every client, has its own
+//! copy of its data. Clients hand theirs to the server through a Collect RemoteFunction
+//! under the helper, and server code run through MCPServerCodeRunner gathers it all.
+//!
+//! Which scripts were rewritten, and their sources before, are recorded in a JSON file in
+//! the place's history directory before a rewrite reaches Studio, so the originals can be
+//! put back even after the server restarted.
+
+use full_moon::ast::{Block, Stmt};
+use full_moon::node::Node;
+use full_moon::visitors::Visitor;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// How long the server waits for each client's data
+const CLIENT_TIMEOUT_SECONDS: u32 = 5;
+
+/// A helper ModuleScript in ReplicatedStorage
+pub struct Helper {
+    pub name: &'static str,
+    /// The module table its source returns
+    pub table: &'static str,
+    /// Function of the table that returns a VM's data, which clients answer Collect with
+    pub collect: &'static str,
+    /// Tool that installs it, named when it's missing
+    pub installer: &'static str,
+    /// What makes a client require it, named when a client doesn't answer
+    pub client_trigger: &'static str,
+}
+
+/// Byte offset and line of each executable statement
+#[derive(Default)]
+pub struct StatementVisitor {
+    pub statements: Vec<(usize, usize)>,
+}
+
+impl Visitor for StatementVisitor {
+    fn visit_block(&mut self, block: &Block) {
+        for stmt in block.stmts() {
+            // Type declarations don't run
+            if matches!(
+                stmt,
+                Stmt::TypeDeclaration(_)
+                    | Stmt::ExportedTypeDeclaration(_)
+                    | Stmt::TypeFunction(_)
+                    | Stmt::ExportedTypeFunction(_)
+            ) {
+                continue;
+            }
+            if let Some(position) = stmt.start_position() {
+                self.statements.push((position.bytes(), position.line()));
+            }
+        }
+        if let Some(position) = block.last_stmt().and_then(|last| last.start_position()) {
+            self.statements.push((position.bytes(), position.line()));
+        }
+    }
+}
+
+/// What the server and clients returned, as collect_code encodes it
+pub struct Collected {
+    pub server: Option<Value>,
+    pub clients: Vec<Value>,
+    pub errors: Vec<String>,
+}
+
+impl Helper {
+    /// The module's source: `body` defines the table and a RunService local, and this
+    /// adds the Collect RemoteFunction and returns the table
+    pub fn source(&self, body: &str) -> String {
+        format!(
+            r#"{body}
+if RunService:IsRunning() then
+	if RunService:IsServer() then
+		local remote = script:FindFirstChild("Collect") or Instance.new("RemoteFunction")
+		remote.Name = "Collect"
+		remote.Parent = script
+	else
+		task.spawn(function()
+			script:WaitForChild("Collect").OnClientInvoke = {table}.{collect}
+		end)
+	end
+end
+
+return {table}
+"#,
+            table = self.table,
+            collect = self.collect
+        )
+    }
+
+    /// Server code that returns the server's and, when `query_clients`, every client's
+    /// data as JSON. `argument` is a Luau expression passed to the collect function.
+    pub fn collect_code(&self, argument: Option<&str>, query_clients: bool) -> String {
+        let client_argument = argument
+            .map(|argument| format!(", {argument}"))
+            .unwrap_or_default();
+        format!(
+            r#"local HttpService = game:GetService("HttpService")
+local Players = game:GetService("Players")
+local module = game:GetService("ReplicatedStorage"):FindFirstChild("{name}")
+if not module then
+	error("ReplicatedStorage.{name} is missing; {installer} installs it before the playtest")
+end
+local result = {{ server = require(module).{collect}({argument}), clients = {{}}, errors = {{}} }}
+if {query_clients} then
+	local remote = module:WaitForChild("Collect", {timeout})
+	local pending = 0
+	for _, player in Players:GetPlayers() do
+		pending += 1
+		task.spawn(function()
+			local ok, data = pcall(remote.InvokeClient, remote, player{client_argument})
+			if ok then
+				result.clients[player.Name] = data
+			else
+				table.insert(result.errors, player.Name .. ": " .. tostring(data))
+			end
+			pending -= 1
+		end)
+	end
+	local deadline = os.clock() + {timeout}
+	while pending > 0 and os.clock() < deadline do
+		task.wait(0.1)
+	end
+	if pending > 0 then
+		table.insert(result.errors, pending .. " client(s) didn't answer within {timeout} seconds; a client answers once it has run {client_trigger}")
+	end
+end
+return HttpService:JSONEncode(result)"#,
+            name = self.name,
+            installer = self.installer,
+            collect = self.collect,
+            argument = argument.unwrap_or_default(),
+            client_trigger = self.client_trigger,
+            timeout = CLIENT_TIMEOUT_SECONDS
+        )
+    }
+
+    /// Parse what collect_code returns
+    pub fn parse(&self, json: &str) -> Result<Collected, String> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid data from {} on the server: {e}", self.name))?;
+        Ok(Collected {
+            server: value.get("server").cloned(),
+            clients: value
+                .get("clients")
+                .and_then(Value::as_object)
+                .map(|clients| clients.values().cloned().collect())
+                .unwrap_or_default(),
+            errors: value
+                .get("errors")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|error| error.as_str().map(str::to_string))
+                .collect(),
+        })
+    }
+}
+
+/// A record from a place's history directory, None if there is none
+pub fn load_record<T: DeserializeOwned>(place_dir: &Path, name: &str) -> Result<Option<T>, String> {
+    let file = place_dir.join(name);
+    match std::fs::read_to_string(&file) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse {}: {e}", file.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {e}", file.display())),
+    }
+}
+
+pub fn save_record<T: Serialize>(place_dir: &Path, name: &str, record: &T) -> Result<(), String> {
+    let file = place_dir.join(name);
+    std::fs::create_dir_all(place_dir)
+        .map_err(|e| format!("Failed to create {}: {e}", place_dir.display()))?;
+    let json = serde_json::to_string_pretty(record)
+        .map_err(|e| format!("Failed to serialize {name}: {e}"))?;
+    // Written under a temporary name first so a crash can't leave a truncated record
+    let partial = place_dir.join(format!("{name}.partial"));
+    std::fs::write(&partial, json)
+        .and_then(|()| std::fs::rename(&partial, &file))
+        .map_err(|e| format!("Failed to write {}: {e}", file.display()))
+}
+
+pub fn remove_record(place_dir: &Path, name: &str) -> Result<(), String> {
+    let file = place_dir.join(name);
+    match std::fs::remove_file(&file) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {e}", file.display()))
+        }
+        _ => Ok(()),
+    }
+}
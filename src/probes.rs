@@ -0,0 +1,651 @@
+//! Temporary probes for the add_probe, read_probes and remove_probes tools.
+//!
+//! A probe is a call to the MCPProbes ModuleScript inserted into a script, in front of the
+//! first statement on a line or in a function's body, so every line keeps its number. It
+//! records the values of an expression in a buffer of the Luau VM that ran it. Each
+//! script's source without its probes is kept in a probes.json file in the place's
+//! history directory, written before a probe reaches Studio, so remove_probes can
+//! put the exact source back even after the server restarted.
+
+use crate::playtest_helper::{self, Helper, StatementVisitor};
+use full_moon::ast::{
+    Assignment, Ast, Expression, FunctionBody, FunctionDeclaration, LocalAssignment, LocalFunction,
+    Parameter, Var,
+};
+use full_moon::node::Node;
+use full_moon::tokenizer::{Symbol, Token, TokenType};
+use full_moon::visitors::Visitor;
+use full_moon::LuaVersion;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+pub const HELPER_PATH: &str = "ReplicatedStorage.MCPProbes";
+
+const BOOK_FILE: &str = "probes.json";
+
+/// Functions listed when a name doesn't match
+const MAX_LISTED_FUNCTIONS: usize = 20;
+
+const HELPER: Helper = Helper {
+    name: "MCPProbes",
+    table: "Probes",
+    collect: "take",
+    installer: "add_probe",
+    client_trigger: "a probed script",
+};
+
+const HELPER_BODY: &str = r##"-- Auto-installed by MCP Server for add_probe/read_probes
+-- Probes that add_probe inserts into scripts record their values here. Each Luau VM (the
+-- server and every client) has its own buffer; clients hand theirs to the server through
+-- the Collect RemoteFunction when read_probes asks.
+local HttpService = game:GetService("HttpService")
+local Players = game:GetService("Players")
+local RunService = game:GetService("RunService")
+
+local MAX_RECORDS = 500
+
+local Probes = {}
+local records = {}
+local dropped = 0
+
+local function render(value)
+	local kind = typeof(value)
+	if kind == "string" then
+		return string.format("%q", value)
+	elseif kind == "Instance" then
+		return value:GetFullName()
+	elseif kind == "table" then
+		local ok, json = pcall(HttpService.JSONEncode, HttpService, value)
+		if ok then
+			return json
+		end
+	end
+	return tostring(value)
+end
+
+local function context()
+	if not RunService:IsRunning() then
+		return "edit"
+	elseif RunService:IsServer() then
+		return "server"
+	end
+	local player = Players.LocalPlayer
+	return "client " .. (if player then player.Name else "?")
+end
+
+-- Called by a probe with pcall's results for its expression
+function Probes.record(id, ok, ...)
+	local values = {}
+	if ok then
+		for i = 1, select("#", ...) do
+			values[i] = render((select(i, ...)))
+		end
+	end
+	table.insert(records, {
+		id = id,
+		time = DateTime.now().UnixTimestampMillis,
+		context = context(),
+		values = values,
+		error = if ok then nil else tostring((...)),
+	})
+	if #records > MAX_RECORDS then
+		table.remove(records, 1)
+		dropped += 1
+	end
+end
+
+-- Records oldest first and how many the full buffer dropped, optionally emptying it
+function Probes.take(clear)
+	local taken = { records = records, dropped = dropped }
+	if clear then
+		records = {}
+		dropped = 0
+	end
+	return taken
+end
+"##;
+
+pub fn helper_source() -> String {
+    HELPER.source(HELPER_BODY)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Probe {
+    pub id: String,
+    pub line: usize,
+    /// The function whose body the probe starts, if it was placed by function
+    pub function: Option<String>,
+    pub expression: Option<String>,
+    /// The text inserted into the source
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbedScript {
+    pub path: String,
+    pub class_name: String,
+    /// RunContext of a Script
+    #[serde(default)]
+    pub run_context: Option<String>,
+    /// Source without the probes, as of the last add_probe
+    pub original: String,
+    /// Source with every probe, as add_probe last wrote it
+    pub probed: String,
+    pub probes: Vec<Probe>,
+}
+
+/// A place's probes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProbeBook {
+    next_id: u64,
+    pub scripts: Vec<ProbedScript>,
+}
+
+pub enum ProbeTarget {
+    Line(usize),
+    Function(String),
+}
+
+/// Where a function's body starts
+struct FunctionSite {
+    name: String,
+    offset: usize,
+    line: usize,
+    parameters: Vec<String>,
+}
+
+/// Where each named function's body starts
+#[derive(Default)]
+struct FunctionVisitor {
+    functions: Vec<FunctionSite>,
+}
+
+impl Visitor for FunctionVisitor {
+    fn visit_function_declaration(&mut self, declaration: &FunctionDeclaration) {
+        let name = declaration.name();
+        let mut text: Vec<String> = name
+            .names()
+            .iter()
+            .map(|token| token.token().to_string())
+            .collect();
+        let mut parameters = Vec::new();
+        if let Some(method) = name.method_name() {
+            let last = text.pop().unwrap_or_default();
+            text.push(format!("{last}:{method}", method = method.token()));
+            parameters.push("self".to_string());
+        }
+        self.add_function(text.join("."), declaration.body(), parameters);
+    }
+
+    fn visit_local_function(&mut self, function: &LocalFunction) {
+        self.add_function(
+            function.name().token().to_string(),
+            function.body(),
+            Vec::new(),
+        );
+    }
+
+    fn visit_local_assignment(&mut self, assignment: &LocalAssignment) {
+        for (name, value) in assignment.names().iter().zip(assignment.expressions()) {
+            if let Expression::Function(function) = value {
+                self.add_function(name.token().to_string(), function.body(), Vec::new());
+            }
+        }
+    }
+
+    fn visit_assignment(&mut self, assignment: &Assignment) {
+        for (var, value) in assignment.variables().iter().zip(assignment.expressions()) {
+            if let Expression::Function(function) = value {
+                let name = match var {
+                    Var::Name(token) => token.token().to_string(),
+                    _ => var.to_string().split_whitespace().collect(),
+                };
+                self.add_function(name, function.body(), Vec::new());
+            }
+        }
+    }
+}
+
+impl FunctionVisitor {
+    fn add_function(&mut self, name: String, body: &FunctionBody, mut parameters: Vec<String>) {
+        let block = body.block();
+        let start = block
+            .stmts()
+            .next()
+            .and_then(|stmt| stmt.start_position())
+            .or_else(|| block.last_stmt().and_then(|last| last.start_position()))
+            .unwrap_or_else(|| body.end_token().token().start_position());
+        for parameter in body.parameters() {
+            if let Parameter::Name(token) = parameter {
+                parameters.push(token.token().to_string());
+            }
+        }
+        self.functions.push(FunctionSite {
+            name,
+            offset: start.bytes(),
+            line: start.line(),
+            parameters,
+        });
+    }
+}
+
+/// Finds `...`, which a probe's closure can't capture
+#[derive(Default)]
+struct VarargVisitor {
+    found: bool,
+}
+
+impl Visitor for VarargVisitor {
+    fn visit_symbol(&mut self, token: &Token) {
+        if let TokenType::Symbol {
+            symbol: Symbol::Ellipsis,
+        } = token.token_type()
+        {
+            self.found = true;
+        }
+    }
+}
+
+fn parse(source: &str) -> Option<Ast> {
+    let result = full_moon::parse_fallible(source, LuaVersion::luau());
+    result.errors().is_empty().then(|| result.into_ast())
+}
+
+/// Check that an expression list can go in `return ...` on one line
+fn check_expression(expression: &str) -> Result<(), String> {
+    if expression.contains(['\n', '\r']) {
+        return Err("expression must be on one line".to_string());
+    }
+    let Some(ast) = parse(&format!("return {expression}")) else {
+        return Err(format!("'{expression}' isn't a valid Luau expression"));
+    };
+    if ast.nodes().stmts().next().is_some() {
+        return Err(format!("'{expression}' isn't a valid Luau expression"));
+    }
+    let mut visitor = VarargVisitor::default();
+    visitor.visit_ast(&ast);
+    if visitor.found {
+        return Err(
+            "expression can't use '...'; copy the values to a local first and probe that"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn snippet(id: &str, expression: Option<&str>) -> String {
+    let values = match expression {
+        Some(expression) => format!("pcall(function() return {expression} end)"),
+        None => "true".to_string(),
+    };
+    format!(
+        "require(game:GetService(\"ReplicatedStorage\"):WaitForChild(\"MCPProbes\")).record(\"{id}\", {values}); "
+    )
+}
+
+/// Insert a probe into a source. Without an expression, a probe at a function records its
+/// parameters and a probe at a line records only that it ran.
+pub fn insert(
+    source: &str,
+    id: String,
+    target: &ProbeTarget,
+    expression: Option<&str>,
+) -> Result<(String, Probe), String> {
+    let expression = expression.map(str::trim).filter(|e| !e.is_empty());
+    if let Some(expression) = expression {
+        check_expression(expression)?;
+    }
+    let Some(ast) = parse(source) else {
+        return Err(
+            "the script has Luau syntax errors; fix them before adding a probe".to_string(),
+        );
+    };
+    let mut statements = StatementVisitor::default();
+    statements.visit_ast(&ast);
+    let mut functions = FunctionVisitor::default();
+    functions.visit_ast(&ast);
+
+    let (offset, line, function, expression) = match target {
+        ProbeTarget::Line(line) => {
+            let offset = statements
+                .statements
+                .iter()
+                .filter(|(_, statement_line)| statement_line == line)
+                .map(|(offset, _)| *offset)
+                .min();
+            let Some(offset) = offset else {
+                return Err(no_statement(&statements.statements, *line));
+            };
+            (offset, *line, None, expression.map(str::to_string))
+        }
+        ProbeTarget::Function(name) => {
+            let site = find_function(&functions.functions, name)?;
+            let expression = expression
+                .map(str::to_string)
+                .or_else(|| (!site.parameters.is_empty()).then(|| site.parameters.join(", ")));
+            (site.offset, site.line, Some(site.name.clone()), expression)
+        }
+    };
+
+    let snippet = snippet(&id, expression.as_deref());
+    let mut probed = source.to_string();
+    probed.insert_str(offset, &snippet);
+    let probe = Probe {
+        id,
+        line,
+        function,
+        expression,
+        snippet,
+    };
+    Ok((probed, probe))
+}
+
+fn no_statement(statements: &[(usize, usize)], line: usize) -> String {
+    let before = statements
+        .iter()
+        .map(|(_, l)| *l)
+        .filter(|l| *l < line)
+        .max();
+    let after = statements
+        .iter()
+        .map(|(_, l)| *l)
+        .filter(|l| *l > line)
+        .min();
+    let nearest: Vec<String> = before
+        .into_iter()
+        .chain(after)
+        .map(|l| l.to_string())
+        .collect();
+    match nearest.as_slice() {
+        [] => format!("No statement starts on line {line}"),
+        [nearest] => {
+            format!("No statement starts on line {line}; the nearest is on line {nearest}")
+        }
+        _ => format!(
+            "No statement starts on line {line}; the nearest are on lines {}",
+            nearest.join(" and ")
+        ),
+    }
+}
+
+/// A function by its full name (`Module.method`, `Module:method`, `helper`), or by its
+/// last part if only one function has it
+fn find_function<'a>(
+    functions: &'a [FunctionSite],
+    name: &str,
+) -> Result<&'a FunctionSite, String> {
+    if let Some(site) = functions.iter().find(|site| site.name == name) {
+        return Ok(site);
+    }
+    let short: Vec<&FunctionSite> = functions
+        .iter()
+        .filter(|site| site.name.rsplit(['.', ':']).next() == Some(name))
+        .collect();
+    match short.as_slice() {
+        [site] => Ok(site),
+        [] => {
+            let mut names: Vec<&str> = functions.iter().map(|site| site.name.as_str()).collect();
+            names.sort_unstable();
+            names.dedup();
+            if names.is_empty() {
+                return Err(format!(
+                    "No function named '{name}'; the script has no named functions"
+                ));
+            }
+            let more = names.len().saturating_sub(MAX_LISTED_FUNCTIONS);
+            names.truncate(MAX_LISTED_FUNCTIONS);
+            let mut message = format!(
+                "No function named '{name}'. Functions: {}",
+                names.join(", ")
+            );
+            if more > 0 {
+                message.push_str(&format!(" and {more} more"));
+            }
+            Err(message)
+        }
+        several => Err(format!(
+            "Several functions are named '{name}': {}. Pass the full name.",
+            several
+                .iter()
+                .map(|site| site.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+impl ProbedScript {
+    /// The source without this script's probes: the original if nothing else changed,
+    /// otherwise the current source with each probe taken out. None if a probe can't be
+    /// found exactly once, so taking them out could break the script.
+    pub fn strip(&self, source: &str) -> Option<String> {
+        if source == self.probed {
+            return Some(self.original.clone());
+        }
+        let counts: Vec<usize> = self
+            .probes
+            .iter()
+            .map(|probe| source.matches(&probe.snippet).count())
+            .collect();
+        if counts.iter().all(|count| *count == 0) {
+            return Some(source.to_string());
+        }
+        if counts.iter().any(|count| *count != 1) {
+            return None;
+        }
+        let mut stripped = source.to_string();
+        for probe in &self.probes {
+            stripped = stripped.replacen(&probe.snippet, "", 1);
+        }
+        Some(stripped)
+    }
+
+    /// Whether the script can run on a client: LocalScripts, ModuleScripts and client Scripts
+    fn client(&self) -> bool {
+        self.class_name != "Script" || self.run_context.as_deref() == Some("Client")
+    }
+}
+
+impl ProbeBook {
+    /// The book in a place's history directory, empty if there is none
+    pub fn load(place_dir: &Path) -> Result<Self, String> {
+        playtest_helper::load_record(place_dir, BOOK_FILE).map(Option::unwrap_or_default)
+    }
+
+    /// Write the book. It is kept once no probes are left so ids aren't handed out twice:
+    /// a record buffered for a removed probe must not show up under a new one.
+    pub fn save(&self, place_dir: &Path) -> Result<(), String> {
+        playtest_helper::save_record(place_dir, BOOK_FILE, self)
+    }
+
+    pub fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("p{}", self.next_id)
+    }
+
+    pub fn probe(&self, id: &str) -> Option<(&ProbedScript, &Probe)> {
+        self.scripts.iter().find_map(|script| {
+            script
+                .probes
+                .iter()
+                .find(|probe| probe.id == id)
+                .map(|probe| (script, probe))
+        })
+    }
+
+    pub fn probe_count(&self) -> usize {
+        self.scripts.iter().map(|script| script.probes.len()).sum()
+    }
+
+    /// Server code that returns the server's and every client's records as JSON
+    pub fn collect_code(&self, clear: bool) -> String {
+        let query_clients = self.scripts.iter().any(ProbedScript::client);
+        HELPER.collect_code(Some(&clear.to_string()), query_clients)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProbeRecord {
+    pub id: String,
+    /// Unix time in milliseconds
+    pub time: f64,
+    pub context: String,
+    #[serde(default)]
+    pub values: Vec<String>,
+    pub error: Option<String>,
+}
+
+pub struct ProbeRecords {
+    /// Oldest first across the server and clients
+    pub records: Vec<ProbeRecord>,
+    pub dropped: u64,
+    pub clients: usize,
+    pub errors: Vec<String>,
+}
+
+/// Add one VM's `{ records, dropped }`. Empty tables come back from JSONEncode as arrays.
+fn add_taken(records: &mut ProbeRecords, taken: &Value) {
+    records.dropped += taken.get("dropped").and_then(Value::as_u64).unwrap_or(0);
+    let taken = taken.get("records").and_then(Value::as_array);
+    for record in taken.into_iter().flatten() {
+        if let Ok(record) = ProbeRecord::deserialize(record) {
+            records.records.push(record);
+        }
+    }
+}
+
+/// Parse what collect_code returns
+pub fn parse_records(json: &str) -> Result<ProbeRecords, String> {
+    let collected = HELPER.parse(json)?;
+    let mut records = ProbeRecords {
+        records: Vec::new(),
+        dropped: 0,
+        clients: collected.clients.len(),
+        errors: collected.errors,
+    };
+    for taken in collected.server.iter().chain(&collected.clients) {
+        add_taken(&mut records, taken);
+    }
+    records.records.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(records)
+}
+
+impl ProbeRecords {
+    /// One line per record, timed from the first
+    pub fn render(&self, book: &ProbeBook) -> String {
+        let start = self
+            .records
+            .first()
+            .map(|record| record.time)
+            .unwrap_or(0.0);
+        let mut text = String::new();
+        for record in &self.records {
+            let location = match book.probe(&record.id) {
+                Some((script, probe)) => match &probe.function {
+                    Some(function) => format!("{}:{} ({function})", script.path, probe.line),
+                    None => format!("{}:{}", script.path, probe.line),
+                },
+                None => "removed probe".to_string(),
+            };
+            text.push_str(&format!(
+                "\n+{:.3}s {} {location} [{}]",
+                (record.time - start) / 1000.0,
+                record.id,
+                record.context
+            ));
+            let expression = book
+                .probe(&record.id)
+                .and_then(|(_, probe)| probe.expression.as_deref());
+            match (&record.error, expression) {
+                (Some(error), _) => text.push_str(&format!(" error: {error}")),
+                (None, Some(expression)) => {
+                    text.push_str(&format!(" {expression} = {}", record.values.join(", ")))
+                }
+                (None, None) => {}
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "local Module = {}\n\nfunction Module.add(a, b)\n\tlocal sum = a + b\n\treturn sum\nend\n\nreturn Module\n";
+
+    fn probed(source: &str, probes: &[(&str, ProbeTarget)]) -> ProbedScript {
+        let mut script = ProbedScript {
+            path: "ReplicatedStorage.Module".to_string(),
+            class_name: "ModuleScript".to_string(),
+            run_context: None,
+            original: source.to_string(),
+            probed: source.to_string(),
+            probes: Vec::new(),
+        };
+        for (id, target) in probes {
+            let (text, probe) = insert(&script.probed, id.to_string(), target, None).unwrap();
+            script.probed = text;
+            script.probes.push(probe);
+        }
+        script
+    }
+
+    #[test]
+    fn insert_keeps_line_numbers() {
+        let script = probed(
+            SOURCE,
+            &[
+                ("p1", ProbeTarget::Line(5)),
+                ("p2", ProbeTarget::Function("Module.add".to_string())),
+            ],
+        );
+        let lines: Vec<&str> = script.probed.lines().collect();
+        assert_eq!(lines.len(), SOURCE.lines().count());
+        assert!(lines[3]
+            .ends_with(".record(\"p2\", pcall(function() return a, b end)); local sum = a + b"));
+        assert!(lines[4].ends_with(".record(\"p1\", true); return sum"));
+        assert_eq!(script.probes[1].line, 4);
+        assert!(
+            insert(SOURCE, "p3".to_string(), &ProbeTarget::Line(2), None)
+                .unwrap_err()
+                .contains("nearest are on lines 1 and 3")
+        );
+    }
+
+    #[test]
+    fn strip_restores_the_original_or_keeps_edits() {
+        let script = probed(
+            SOURCE,
+            &[("p1", ProbeTarget::Line(4)), ("p2", ProbeTarget::Line(5))],
+        );
+        assert_eq!(script.strip(&script.probed).unwrap(), SOURCE);
+
+        let edited = script
+            .probed
+            .replace("return Module", "return table.freeze(Module)");
+        assert_eq!(
+            script.strip(&edited).unwrap(),
+            SOURCE.replace("return Module", "return table.freeze(Module)")
+        );
+
+        // Probes the user already took out leave the source as it is
+        assert_eq!(script.strip(SOURCE).unwrap(), SOURCE);
+
+        // A probe copied elsewhere can't be taken out safely
+        let snippet = &script.probes[0].snippet;
+        let duplicated = format!("{snippet}{}", script.probed);
+        assert!(script.strip(&duplicated).is_none());
+    }
+
+    #[test]
+    fn client_scripts_include_client_run_context() {
+        let mut script = probed(SOURCE, &[]);
+        assert!(script.client());
+        script.class_name = "Script".to_string();
+        assert!(!script.client());
+        script.run_context = Some("Client".to_string());
+        assert!(script.client());
+    }
+}
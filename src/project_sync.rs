@@ -0,0 +1,215 @@
+//! Rojo project sync for the sync_project tool.
+//!
+//! Projects are built with librojo, like build.rs builds the plugin, so every Rojo file
+//! convention is honoured. Builds run in a child process of this binary (the hidden
+//! `build-project` subcommand) because librojo's build command leaks its session on
+//! purpose, which a server that rebuilds on every file change can't afford. The built
+//! tree is flattened into a build plan and the plugin diffs it against the live DataModel.
+
+use crate::model_file::{build_plan, BuildPlan, PlanMode};
+use rbx_dom_weak::WeakDom;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_PROJECT_FILE: &str = "default.project.json";
+const PROJECT_EXTENSION: &str = ".project.json";
+
+/// The parts of a .project.json file the sync needs beyond the built tree
+#[derive(Debug, Clone)]
+pub struct ProjectLayout {
+    /// Path of the .project.json file
+    pub file: PathBuf,
+    /// Whether the project root is a DataModel (a place) rather than a model
+    pub is_place: bool,
+    /// Instance paths of the project nodes, and whether instances the project doesn't
+    /// describe are kept under them (Rojo's `$ignoreUnknownInstances`)
+    ignore_unknown: HashMap<Vec<String>, bool>,
+    /// Every `$path` the project reads, for the watcher
+    pub watch_paths: Vec<PathBuf>,
+}
+
+/// A sync pass for the plugin: the build plan plus the instances that own their children
+pub struct SyncPlan {
+    pub plan: BuildPlan,
+    /// Indices of instances whose children not in the project are removed
+    pub owned: Vec<usize>,
+}
+
+impl ProjectLayout {
+    /// Load a .project.json file, or the default.project.json in a directory
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = if path.is_dir() {
+            path.join(DEFAULT_PROJECT_FILE)
+        } else {
+            path.to_path_buf()
+        };
+        if !file.to_string_lossy().ends_with(PROJECT_EXTENSION) {
+            return Err(format!(
+                "{} is not a Rojo project file (*{PROJECT_EXTENSION})",
+                file.display()
+            ));
+        }
+        let contents = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+        let project: Value = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {e}", file.display()))?;
+        let tree = project
+            .get("tree")
+            .ok_or_else(|| format!("{} has no tree", file.display()))?;
+        let name = project
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("Project");
+
+        let folder = file.parent().unwrap_or(Path::new("."));
+        let is_place = tree.get("$className").and_then(Value::as_str) == Some("DataModel");
+        let mut layout = Self {
+            file: file.clone(),
+            is_place,
+            ignore_unknown: HashMap::new(),
+            watch_paths: Vec::new(),
+        };
+        // A place's root is the DataModel itself; a model's root is an instance named after the project
+        let root_path = if is_place {
+            Vec::new()
+        } else {
+            vec![name.to_string()]
+        };
+        layout.add_node(tree, root_path, folder);
+        Ok(layout)
+    }
+
+    fn add_node(&mut self, node: &Value, path: Vec<String>, folder: &Path) {
+        let Some(node) = node.as_object() else {
+            return;
+        };
+        let source = node.get("$path").and_then(|p| match p {
+            Value::String(p) => Some(p.as_str()),
+            // Optional paths are written as { "optional": "path" }
+            Value::Object(o) => o.get("optional").and_then(Value::as_str),
+            _ => None,
+        });
+        if let Some(source) = source {
+            let source = folder.join(source);
+            if source.is_dir() {
+                self.add_meta_files(&source, &path);
+            }
+            self.watch_paths.push(source);
+        }
+        // Rojo keeps unknown instances under plain project nodes but not under $path nodes
+        let ignore_unknown = node
+            .get("$ignoreUnknownInstances")
+            .and_then(Value::as_bool)
+            .unwrap_or(source.is_none());
+        for (key, child) in node {
+            if !key.starts_with('$') {
+                let mut child_path = path.clone();
+                child_path.push(key.clone());
+                self.add_node(child, child_path, folder);
+            }
+        }
+        self.ignore_unknown.insert(path, ignore_unknown);
+    }
+
+    /// Record `ignoreUnknownInstances` from the .meta.json files under a `$path` directory.
+    /// Project nodes are recorded after this, so their own setting wins.
+    fn add_meta_files(&mut self, dir: &Path, path: &[String]) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let mut entry_path = path.to_vec();
+            if entry.path().is_dir() {
+                entry_path.push(name);
+                self.add_meta_files(&entry.path(), &entry_path);
+                continue;
+            }
+            let Some(stem) = name.strip_suffix(".meta.json") else {
+                continue;
+            };
+            // init.meta.json describes the directory itself, Name.meta.json its sibling
+            if stem != "init" {
+                entry_path.push(stem.to_string());
+            }
+            let ignore_unknown = std::fs::read_to_string(entry.path())
+                .ok()
+                .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+                .and_then(|meta| meta.get("ignoreUnknownInstances")?.as_bool());
+            if let Some(ignore_unknown) = ignore_unknown {
+                self.ignore_unknown.insert(entry_path, ignore_unknown);
+            }
+        }
+    }
+
+    /// Build the project with librojo in a child process and read the result
+    pub fn build(&self) -> Result<WeakDom, String> {
+        let extension = if self.is_place { "rbxl" } else { "rbxm" };
+        let output = std::env::temp_dir().join(format!(
+            "rbx-studio-mcp-sync-{}.{extension}",
+            uuid::Uuid::new_v4()
+        ));
+        let exe = std::env::current_exe()
+            .map_err(|e| format!("Failed to locate the server executable: {e}"))?;
+        let result = std::process::Command::new(exe)
+            .arg("build-project")
+            .arg(&self.file)
+            .arg(&output)
+            .output()
+            .map_err(|e| format!("Failed to run the project build: {e}"));
+
+        let dom = result.and_then(|result| {
+            if !result.status.success() {
+                let stderr = String::from_utf8_lossy(&result.stderr);
+                return Err(format!("Rojo build failed: {}", stderr.trim()));
+            }
+            let file = std::fs::File::open(&output)
+                .map_err(|e| format!("Failed to open the built project: {e}"))?;
+            rbx_binary::from_reader(std::io::BufReader::new(file))
+                .map_err(|e| format!("Failed to read the built project: {e}"))
+        });
+        let _ = std::fs::remove_file(&output);
+        dom
+    }
+
+    /// Flatten a built project into a sync pass
+    pub fn plan(&self, dom: &WeakDom) -> Result<SyncPlan, String> {
+        let plan = build_plan(dom, PlanMode::Sync)?;
+
+        let mut paths: Vec<Vec<String>> = Vec::with_capacity(plan.instances.len());
+        let mut owned = Vec::new();
+        for (index, instance) in plan.instances.iter().enumerate() {
+            let mut path = instance
+                .parent
+                .map(|parent| paths[parent].clone())
+                .unwrap_or_default();
+            path.push(instance.name.clone());
+            // Instances that come from files are fully owned unless a meta file says otherwise
+            if !self.ignore_unknown.get(&path).copied().unwrap_or(false) {
+                owned.push(index);
+            }
+            paths.push(path);
+        }
+        Ok(SyncPlan { plan, owned })
+    }
+}
+
+/// Entry point of the hidden `build-project` subcommand. Errors go to stderr as plain
+/// text, since `build` reports them to the tool caller as-is.
+pub fn run_build(project: PathBuf, output: PathBuf) -> ! {
+    let result = librojo::cli::BuildCommand {
+        project,
+        output: Some(output),
+        plugin: None,
+        watch: false,
+    }
+    .run();
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("{e:#}");
+            std::process::exit(1)
+        }
+    }
+}
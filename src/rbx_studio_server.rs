@@ -1,8 +1,37 @@
+use crate::attributes::{validate_attribute_name, AttributeValue};
+use crate::coverage::{self, CoverageSession};
 use crate::error::Result;
+use crate::heightmap::{
+    default_ramp, export_chunks, export_columns, ChunkSurface, ExportHeightmapChunk,
+    HeightmapChunk, Heightfield, MaterialRule, TerrainSurface, MAX_COLUMNS, MAX_HEIGHT,
+};
+use crate::instance_query::{InstanceSelector, InstanceTarget};
+use crate::module_graph;
+use crate::model_file::{
+    build_dom, build_plan, property_manifest, read_model, write_model, ModelFormat, PlanMode,
+    SerializedInstance, SerializedTree,
+};
+use crate::luau_format::{self, FormatSettings};
+use crate::luau_lint::{Linter, DEFAULT_MAX_DIAGNOSTICS, MAX_DIAGNOSTICS};
+use crate::luau_local::{self, InstanceTree, LocalTask, Outcome};
+use crate::luau_syntax;
+use crate::probes::{self, ProbeBook, ProbeTarget, ProbedScript};
+use crate::project_sync::ProjectLayout;
+use crate::script_search::{
+    ScriptSearch, DEFAULT_CONTEXT_LINES, DEFAULT_MAX_RESULTS, SCRIPT_CLASSES,
+};
+use crate::script_patch::{apply_replacements, apply_unified_diff, unified_diff, SearchReplace};
+use crate::script_refactor::{self, References};
+use crate::script_history::{place_key, HistoryStore, Revision};
+use crate::script_export::{
+    layout as script_layout, write_layout, write_sourcemap, CollectedScripts, SourcemapReport,
+    PROJECT_FILE, SOURCEMAP_FILE,
+};
+use crate::terrain::{TerrainMaterial, TerrainRegion, TerrainShape, VOXEL_SIZE};
+use crate::test_report::{TestReport, TEST_RUNNER_SOURCE};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::{extract::State, Json};
-use base64::Engine;
 use color_eyre::eyre::{Error, OptionExt};
 use rmcp::{
     handler::server::{router::tool::ToolRouter, wrapper::Parameters},
@@ -12,13 +41,15 @@ use rmcp::{
     schemars, tool, tool_handler, tool_router, ErrorData, ServerHandler,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::oneshot::Receiver;
 use tokio::sync::{mpsc, watch, Mutex};
 use tokio::time::{timeout, Duration};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use rbx_reflection::ClassTag;
 
 pub const STUDIO_PLUGIN_PORT: u16 = 44755;
 const LONG_POLL_DURATION: Duration = Duration::from_secs(15);
@@ -27,6 +58,7 @@ const LONG_POLL_DURATION: Duration = Duration::from_secs(15);
 // Max 1920px to stay under API's 2000px limit for multi-image requests
 const SCREENSHOT_MAX_DIMENSION: u32 = 1920;
 const SCREENSHOT_JPEG_QUALITY: u8 = 85;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 const SCREENSHOT_TIMEOUT_SECS: u64 = 10;
 // Tool execution timeout - must be longer than Lua-side verification timeout (10s)
 const TOOL_EXECUTION_TIMEOUT: Duration = Duration::from_secs(30);
@@ -357,6 +389,32 @@ pub struct ToolArguments {
 pub struct RunCommandResponse {
     response: String,
     id: Uuid,
+    /// ChangeHistoryService waypoint committed while handling the request, if any
+    #[serde(default)]
+    recording: Option<String>,
+}
+
+/// A change made by an MCP tool call, undoable through ChangeHistoryService
+#[derive(Debug, Serialize, Clone)]
+pub struct ChangeRecord {
+    pub id: Uuid,
+    pub tool: String,
+    pub waypoint: String,
+    pub timestamp: u64,
+    pub undone: bool,
+}
+
+/// A running sync_project watcher
+pub struct ProjectWatch {
+    project: PathBuf,
+    task: tokio::task::AbortHandle,
+    status: Arc<std::sync::Mutex<WatchStatus>>,
+}
+
+#[derive(Debug, Default)]
+struct WatchStatus {
+    syncs: usize,
+    last_result: Option<String>,
 }
 
 /// Command for input simulation - queued by MCP tools, polled by game
@@ -385,12 +443,26 @@ pub struct ServerCodeResult {
     pub error: Option<String>,
 }
 
+// find_instances result limits
+const FIND_INSTANCES_DEFAULT_LIMIT: u32 = 100;
+const FIND_INSTANCES_MAX_LIMIT: u32 = 1000;
+
+// Camera.FieldOfView is clamped to this range by Roblox
+const CAMERA_FOV_RANGE: std::ops::RangeInclusive<f64> = 1.0..=120.0;
+
+// export_instances and import_model_file refuse larger trees rather than building a huge payload
+const MODEL_FILE_MAX_INSTANCES: usize = 20_000;
+// sync_project waits for file changes to settle before syncing, since editors often write several files at once
+const PROJECT_WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+// Conversion problems listed in a tool result before the rest are summarized
+const MAX_LISTED_FAILURES: usize = 50;
+
 // Timeout for waiting for server code execution result
 const SERVER_CODE_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub struct AppState {
     process_queue: VecDeque<ToolArguments>,
-    output_map: HashMap<Uuid, mpsc::UnboundedSender<Result<String>>>,
+    output_map: HashMap<Uuid, mpsc::UnboundedSender<Result<RunCommandResponse>>>,
     waiter: watch::Receiver<()>,
     trigger: watch::Sender<()>,
     /// Queue of input commands for game to poll
@@ -399,6 +471,10 @@ pub struct AppState {
     pub server_code_queue: VecDeque<ServerCodeCommand>,
     /// Map of pending server code result channels (waiting for game to respond)
     pub server_code_results: HashMap<Uuid, mpsc::UnboundedSender<ServerCodeResult>>,
+    /// Changes recorded by tool calls, oldest first
+    change_history: Vec<ChangeRecord>,
+    /// The project sync_project is watching, if any
+    project_watch: Option<ProjectWatch>,
 }
 pub type PackedState = Arc<Mutex<AppState>>;
 
@@ -413,10 +489,22 @@ impl AppState {
             input_command_queue: VecDeque::new(),
             server_code_queue: VecDeque::new(),
             server_code_results: HashMap::new(),
+            change_history: Vec::new(),
+            project_watch: None,
         }
     }
 }
 
+impl ToolArgumentValues {
+    /// Name of the tool variant as seen by the plugin (e.g. "WriteScript")
+    fn variant_name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.as_object()?.keys().next().cloned())
+            .unwrap_or_default()
+    }
+}
+
 impl ToolArguments {
     fn new(args: ToolArgumentValues) -> (Self, Uuid) {
         Self { args, id: None }.with_id()
@@ -435,6 +523,12 @@ impl ToolArguments {
 #[derive(Clone)]
 pub struct RBXStudioServer {
     state: PackedState,
+    formatting: FormatSettings,
+    history: Arc<HistoryStore>,
+    /// Held while a tool reads and rewrites a place's probe book
+    probe_lock: Arc<Mutex<()>>,
+    /// Held while a tool reads and rewrites a place's coverage run
+    coverage_lock: Arc<Mutex<()>>,
     tool_router: ToolRouter<Self>,
 }
 
@@ -482,6 +576,22 @@ struct WriteScript {
         description = "Type of script to create: 'Script', 'LocalScript', or 'ModuleScript'. Defaults to 'Script'. Only used when creating new scripts."
     )]
     script_type: Option<String>,
+    #[schemars(
+        description = "Write the source even if it doesn't parse as Luau. Defaults to false, which rejects syntax errors with their line and column."
+    )]
+    allow_invalid: Option<bool>,
+    #[schemars(
+        description = "Format the source with StyLua before writing, using the project's stylua.toml. Defaults to the server's --format-scripts setting."
+    )]
+    format: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FormatScript {
+    #[schemars(
+        description = "Path to script in game hierarchy (e.g., 'ServerScriptService.GameManager')"
+    )]
+    path: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
@@ -490,6 +600,93 @@ struct ReadScript {
     path: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct EditScript {
+    #[schemars(description = "Path to script in game hierarchy (e.g., 'ServerScriptService.GameManager')")]
+    path: String,
+    #[schemars(description = "Unified diff to apply (e.g., from diff -u or git diff). Line numbers are hints; hunks are matched by their context and removed lines.")]
+    diff: Option<String>,
+    #[schemars(description = "Search/replace edits to apply in order, as an alternative to diff")]
+    edits: Option<Vec<SearchReplace>>,
+    #[schemars(description = "Write the result even if it doesn't parse as Luau. Defaults to false.")]
+    allow_invalid: Option<bool>,
+}
+
+/// Plugin payload for `ApplyScriptEdit`: the patched source, written only if the script
+/// still has the source the patch was applied to
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ApplyScriptEditRequest {
+    path: String,
+    expected_source: String,
+    source: String,
+}
+
+/// Plugin payload for `ApplyScriptEdits`: rename_symbol's edits, applied together so one
+/// undo reverts them
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ApplyScriptEditsRequest {
+    edits: Vec<ApplyScriptEditRequest>,
+    rename: Option<InstanceRename>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct InstanceRename {
+    path: String,
+    name: String,
+}
+
+/// Plugin payload for `RunTests`, also the options the test runner gets in a playtest
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RunTestsRequest {
+    paths: Vec<String>,
+    framework: Option<String>,
+    framework_path: Option<String>,
+    name_filter: Option<String>,
+}
+
+/// Plugin payload for `SnapshotScript`
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SnapshotScriptRequest {
+    path: Option<String>,
+}
+
+/// The open place and, if a path was given, that script's current source
+#[derive(Debug, Deserialize)]
+struct ScriptSnapshot {
+    place_id: u64,
+    place_name: String,
+    #[serde(default)]
+    class_name: String,
+    #[serde(default)]
+    source: String,
+    /// RunContext of a Script
+    #[serde(default)]
+    run_context: Option<String>,
+}
+
+impl ScriptSnapshot {
+    /// The place's directory in the history store
+    fn place(&self) -> String {
+        place_key(self.place_id, &self.place_name)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ScriptHistory {
+    #[schemars(description = "Script to list saved revisions of (e.g., 'ServerScriptService.GameManager'). Omit to list every script with saved revisions in the open place.")]
+    path: Option<String>,
+    #[schemars(description = "Return the source of this revision instead of the list. Requires path.")]
+    revision: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RevertScript {
+    #[schemars(description = "Path to script in game hierarchy (e.g., 'ServerScriptService.GameManager')")]
+    path: String,
+    #[schemars(description = "Revision number from script_history to restore")]
+    revision: usize,
+}
+
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
 struct CaptureScreenshot {
     // No parameters for v1 - just capture the Studio window
@@ -642,964 +839,4466 @@ struct PreviewAsset {
     keep: Option<bool>,
 }
 
-// ============ Asset Search Enrichment Types ============
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CreateInstance {
+    #[schemars(description = "Class of the instance to create (e.g., 'Part', 'Folder', 'RemoteEvent')")]
+    class_name: String,
+    #[schemars(description = "Path to the parent instance (e.g., 'Workspace.Map')")]
+    parent: String,
+    #[schemars(description = "Optional name for the new instance. Defaults to the class name.")]
+    name: Option<String>,
+    #[schemars(
+        description = "Optional map of property names to primitive values (string, number, boolean) to set before parenting. Enum properties accept item names (e.g., {\"Material\": \"Neon\"})."
+    )]
+    properties: Option<HashMap<String, serde_json::Value>>,
+}
 
-/// Response from the Luau plugin's SearchAssets
-#[derive(Debug, Deserialize)]
-struct PluginSearchResponse {
-    success: bool,
-    error: Option<String>,
-    assets: Vec<PluginAssetResult>,
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CloneInstance {
+    #[schemars(description = "Path to the instance to clone (e.g., 'Workspace.Map.Tree')")]
+    path: String,
+    #[schemars(description = "Optional path to parent the clone under. Defaults to the source instance's parent.")]
+    parent: Option<String>,
+    #[schemars(description = "Optional name for the clone")]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct MoveInstance {
+    #[schemars(description = "Path to the instance to move (e.g., 'Workspace.Tree')")]
+    path: String,
+    #[schemars(description = "Path to the new parent (e.g., 'Workspace.Map')")]
+    new_parent: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct DestroyInstance {
+    #[schemars(description = "Path to the instance to destroy (e.g., 'Workspace.OldMap')")]
+    path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FindInstances {
+    #[schemars(
+        description = "Selector of space-separated key:value terms that must all match. Keys: class:BasePart (IsA, comma-separate for any-of), name:Door* (glob with * and ?), tag:Interactive (CollectionService tag), attr:Team=Red (attribute = or !=), under:Workspace.Map (ancestor path), prop:Transparency>0.5 (property =, !=, <, <=, >, >=). Quote values with spaces: name:\"Spawn Point\"."
+    )]
     query: String,
+    #[schemars(description = "Maximum number of paths to return (default: 100, max: 1000). The total match count is always reported.")]
+    limit: Option<u32>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PluginAssetResult {
-    #[serde(deserialize_with = "deserialize_number_from_string")]
-    asset_id: u64,
-    name: String,
-    creator: String,
+/// Parsed form of `FindInstances` sent to the plugin
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FindInstancesQuery {
+    selector: InstanceSelector,
+    limit: u32,
 }
 
-/// Deserialize a number that may come as a string (Luau JSONEncode quirk)
-fn deserialize_number_from_string<'de, D>(deserializer: D) -> std::result::Result<u64, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    use serde::de::Error;
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ListAttributes {
+    #[schemars(description = "Path to a single instance (e.g., 'Workspace.Door'). Use either path or query.")]
+    path: Option<String>,
+    #[schemars(description = "Selector for multiple instances, same syntax as find_instances (e.g., 'tag:Door under:Workspace')")]
+    query: Option<String>,
+}
 
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum StringOrNumber {
-        String(String),
-        Number(u64),
-    }
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetAttributes {
+    #[schemars(description = "Path to a single instance (e.g., 'Workspace.Door'). Use either path or query.")]
+    path: Option<String>,
+    #[schemars(description = "Selector for multiple instances, same syntax as find_instances (e.g., 'tag:Door under:Workspace')")]
+    query: Option<String>,
+    #[schemars(
+        description = "Map of attribute names to values. Booleans, numbers and strings are plain JSON; other types use {\"type\": \"Vector3\", \"value\": [x, y, z]}. Supported types: Vector2, Vector3, Color3 (0-1 components), UDim, UDim2, NumberRange, BrickColor (name)."
+    )]
+    attributes: HashMap<String, AttributeValue>,
+}
 
-    match StringOrNumber::deserialize(deserializer)? {
-        StringOrNumber::String(s) => s.parse().map_err(D::Error::custom),
-        StringOrNumber::Number(n) => Ok(n),
-    }
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RemoveAttributes {
+    #[schemars(description = "Path to a single instance (e.g., 'Workspace.Door'). Use either path or query.")]
+    path: Option<String>,
+    #[schemars(description = "Selector for multiple instances, same syntax as find_instances (e.g., 'tag:Door under:Workspace')")]
+    query: Option<String>,
+    #[schemars(description = "Names of the attributes to remove")]
+    names: Vec<String>,
 }
 
-/// Response from economy.roblox.com/v2/assets/{id}/details
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct EconomyAssetDetails {
-    asset_id: u64,
-    name: String,
-    description: Option<String>,
-    creator: Option<EconomyCreator>,
-    created: Option<String>,
-    updated: Option<String>,
-    sales: Option<u64>,
-    is_public_domain: Option<bool>,
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct AddTags {
+    #[schemars(description = "Path to a single instance (e.g., 'Workspace.Door'). Use either path or query.")]
+    path: Option<String>,
+    #[schemars(description = "Selector for multiple instances, same syntax as find_instances (e.g., 'class:Part name:Door*')")]
+    query: Option<String>,
+    #[schemars(description = "CollectionService tags to add")]
+    tags: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
-struct EconomyCreator {
-    id: u64,
-    name: String,
-    has_verified_badge: Option<bool>,
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RemoveTags {
+    #[schemars(description = "Path to a single instance (e.g., 'Workspace.Door'). Use either path or query.")]
+    path: Option<String>,
+    #[schemars(description = "Selector for multiple instances, same syntax as find_instances (e.g., 'tag:Door')")]
+    query: Option<String>,
+    #[schemars(description = "CollectionService tags to remove")]
+    tags: Vec<String>,
 }
 
-/// Enriched asset with all metadata and quality score
-#[derive(Debug, Serialize)]
-struct EnrichedAsset {
-    asset_id: u64,
-    name: String,
-    creator: String,
-    creator_verified: bool,
-    description: Option<String>,
-    favorites: u64,
-    sales: u64,
-    created: Option<String>,
-    updated: Option<String>,
-    quality_score: f64,
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ListTags {
+    #[schemars(description = "Optional tag to list. If not specified, lists every tag in the place.")]
+    tag: Option<String>,
+    #[schemars(description = "Maximum number of paths to return per tag (default: 100, max: 1000)")]
+    limit: Option<u32>,
 }
 
+/// Attribute and tag tool payloads sent to the plugin, with path/query resolved to a target
 #[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
-enum ToolArgumentValues {
-    RunCode(RunCode),
-    InsertModel(InsertModel),
-    WriteScript(WriteScript),
-    ReadScript(ReadScript),
-    ReadOutput(ReadOutput),
-    GetStudioState(GetStudioState),
-    StartPlaytest(StartPlaytest),
-    StartSimulation(StartSimulation),
-    StopSimulation(StopSimulation),
-    StopPlaytest(StopPlaytest),
-    MoveCharacter(MoveCharacter),
-    ValidateUI(ValidateUI),
-    CreateResponsiveLayout(CreateResponsiveLayout),
-    PreviewLayout(PreviewLayout),
-    SearchAssets(SearchAssets),
-    PreviewAsset(PreviewAsset),
-    // Note: SimulateInput and ClickGui are handled directly by Rust (HTTP polling)
-    // and don't go through the Luau plugin
+struct ListAttributesRequest {
+    target: InstanceTarget,
 }
-#[tool_router]
-impl RBXStudioServer {
-    pub fn new(state: PackedState) -> Self {
-        Self {
-            state,
-            tool_router: Self::tool_router(),
-        }
-    }
 
-    #[tool(
-        description = "Runs a command in Roblox Studio and returns the printed output. Can be used to both make changes and retrieve information"
-    )]
-    async fn run_code(
-        &self,
-        Parameters(args): Parameters<RunCode>,
-    ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::RunCode(args))
-            .await
-    }
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetAttributesRequest {
+    target: InstanceTarget,
+    attributes: HashMap<String, AttributeValue>,
+}
 
-    #[tool(
-        description = "Inserts a model from the Roblox marketplace into the workspace. Returns the inserted model name."
-    )]
-    async fn insert_model(
-        &self,
-        Parameters(args): Parameters<InsertModel>,
-    ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::InsertModel(args))
-            .await
-    }
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RemoveAttributesRequest {
+    target: InstanceTarget,
+    names: Vec<String>,
+}
 
-    #[tool(
-        description = "Creates or updates a Script, LocalScript, or ModuleScript with the provided Luau source code. Uses ScriptEditorService to safely write script source."
-    )]
-    async fn write_script(
-        &self,
-        Parameters(args): Parameters<WriteScript>,
-    ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::WriteScript(args))
-            .await
-    }
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct TagsRequest {
+    target: InstanceTarget,
+    tags: Vec<String>,
+}
 
-    #[tool(
-        description = "Reads the source code of an existing Script, LocalScript, or ModuleScript. Returns the full source code as text."
-    )]
-    async fn read_script(
-        &self,
-        Parameters(args): Parameters<ReadScript>,
-    ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::ReadScript(args))
-            .await
+fn validate_tags(tags: &[String]) -> std::result::Result<(), String> {
+    if tags.is_empty() {
+        return Err("Provide at least one tag".to_string());
     }
-
-    #[tool(
-        description = "Captures a screenshot of the Roblox Studio window and returns it as a JPEG image. Useful for visual debugging, verifying UI changes, or analyzing the workspace layout."
-    )]
-    async fn capture_screenshot(
-        &self,
-        Parameters(_args): Parameters<CaptureScreenshot>,
-    ) -> Result<CallToolResult, ErrorData> {
-        // Rust-only implementation - no plugin communication needed
-        match Self::take_studio_screenshot().await {
-            Ok(base64_data) => Ok(CallToolResult::success(vec![Content::image(
-                base64_data,
-                "image/jpeg",
-            )])),
-            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
-                "Failed to capture screenshot: {}",
-                e
-            ))])),
-        }
+    if tags.iter().any(|tag| tag.trim().is_empty()) {
+        return Err("Tags cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct UndoLastChange {
+    // No parameters - undoes the most recent MCP change
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct Redo {
+    // No parameters - redoes the most recently undone MCP change
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ListMcpChanges {
+    // No parameters - lists changes recorded by MCP tool calls
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct UndoToolCall {
+    #[schemars(description = "Tool call id from list_mcp_changes")]
+    id: String,
+}
+
+/// Plugin payloads for stepping ChangeHistoryService, guarded by the expected waypoint name
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct UndoChange {
+    waypoint: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RedoChange {
+    waypoint: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetSelection {
+    // No parameters - returns the paths of the currently selected instances
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetSelection {
+    #[schemars(description = "Paths of instances to select (e.g., ['Workspace.Tree', 'Workspace.Rock']). An empty list clears the selection.")]
+    paths: Option<Vec<String>>,
+    #[schemars(description = "Selector to select instead of paths, same syntax as find_instances")]
+    query: Option<String>,
+}
+
+/// Plugin payload for `SetSelection` with the query parsed
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetSelectionRequest {
+    paths: Vec<String>,
+    selector: Option<InstanceSelector>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FocusCamera {
+    #[schemars(description = "Path to the instance to frame (e.g., 'Workspace.Castle')")]
+    path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SetCamera {
+    #[schemars(
+        description = "Full CFrame as 12 numbers: [x, y, z, r00, r01, r02, r10, r11, r12, r20, r21, r22]. Use either cframe or position."
+    )]
+    cframe: Option<[f64; 12]>,
+    #[schemars(description = "Camera position [x, y, z]. Use either cframe or position.")]
+    position: Option<[f64; 3]>,
+    #[schemars(description = "Point [x, y, z] the camera looks at when using position. Defaults to looking along -Z.")]
+    look_at: Option<[f64; 3]>,
+    #[schemars(description = "Vertical field of view in degrees (1-120). Unchanged if not specified.")]
+    fov: Option<f64>,
+}
+
+impl SetCamera {
+    fn validate(&self) -> std::result::Result<(), String> {
+        match (&self.cframe, &self.position) {
+            (Some(_), Some(_)) => return Err("Provide either cframe or position, not both".to_string()),
+            (None, None) if self.fov.is_none() => {
+                return Err("Provide a cframe, a position, or a fov".to_string())
+            }
+            _ => {}
+        }
+        if self.look_at.is_some() && self.position.is_none() {
+            return Err("look_at requires position".to_string());
+        }
+        if self.position.is_some() && self.position == self.look_at {
+            return Err("look_at must differ from position".to_string());
+        }
+        let mut numbers = self
+            .cframe
+            .iter()
+            .flatten()
+            .chain(self.position.iter().flatten())
+            .chain(self.look_at.iter().flatten());
+        if numbers.any(|n| !n.is_finite()) {
+            return Err("Camera coordinates must be finite numbers".to_string());
+        }
+        if let Some(fov) = self.fov {
+            if !CAMERA_FOV_RANGE.contains(&fov) {
+                return Err(format!(
+                    "fov must be between {} and {} degrees",
+                    CAMERA_FOV_RANGE.start(),
+                    CAMERA_FOV_RANGE.end()
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GetCamera {
+    // No parameters - returns the current camera CFrame, focus and field of view
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FillTerrain {
+    #[schemars(description = "Shape to fill: block, ball, cylinder, or wedge")]
+    shape: TerrainShape,
+    #[schemars(description = "Terrain material (e.g., Grass, Rock, Water). Air removes terrain.")]
+    material: TerrainMaterial,
+    #[schemars(description = "Center of the shape [x, y, z] in studs")]
+    position: [f64; 3],
+    #[schemars(description = "Size [x, y, z] in studs. Required for block and wedge.")]
+    size: Option<[f64; 3]>,
+    #[schemars(description = "Radius in studs. Required for ball and cylinder.")]
+    radius: Option<f64>,
+    #[schemars(description = "Height in studs. Required for cylinder.")]
+    height: Option<f64>,
+    #[schemars(description = "Orientation [x, y, z] in degrees for block, cylinder and wedge (default: [0, 0, 0])")]
+    rotation: Option<[f64; 3]>,
+}
+
+impl FillTerrain {
+    /// Check the shape has the dimensions it needs and its bounding box is within the region limit
+    fn validate(&self) -> std::result::Result<(), String> {
+        let positive = |name: &str, value: Option<f64>| match value {
+            Some(v) if v.is_finite() && v > 0.0 => Ok(v),
+            Some(_) => Err(format!("{name} must be a positive number")),
+            None => Err(format!("{name} is required for {:?} fills", self.shape).to_lowercase()),
+        };
+
+        let extents = match self.shape {
+            TerrainShape::Block | TerrainShape::Wedge => {
+                let size = self.size.ok_or_else(|| {
+                    format!("size is required for {:?} fills", self.shape).to_lowercase()
+                })?;
+                for axis in size {
+                    positive("size", Some(axis))?;
+                }
+                size
+            }
+            TerrainShape::Ball => {
+                let diameter = positive("radius", self.radius)? * 2.0;
+                [diameter; 3]
+            }
+            TerrainShape::Cylinder => {
+                let diameter = positive("radius", self.radius)? * 2.0;
+                let height = positive("height", self.height)?;
+                [diameter, height, diameter]
+            }
+        };
+
+        if self.position.iter().any(|c| !c.is_finite()) {
+            return Err("position must be finite numbers".to_string());
+        }
+        let extents = match self.rotation {
+            Some(rotation) if rotation.iter().any(|r| !r.is_finite()) => {
+                return Err("rotation must be finite numbers".to_string())
+            }
+            // A rotated shape fits inside a cube as wide as its diagonal
+            Some(rotation) if rotation.iter().any(|r| *r != 0.0) => {
+                [extents.iter().map(|e| e * e).sum::<f64>().sqrt(); 3]
+            }
+            _ => extents,
+        };
+        TerrainRegion::from_center_size(self.position, extents).validate()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ReplaceTerrainMaterial {
+    #[schemars(description = "Region to edit, expanded outward to the 4-stud voxel grid")]
+    region: TerrainRegion,
+    #[schemars(description = "Material to replace (e.g., Grass)")]
+    from_material: TerrainMaterial,
+    #[schemars(description = "Material to replace it with (e.g., Snow)")]
+    to_material: TerrainMaterial,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ClearTerrain {
+    #[schemars(description = "Region to clear, expanded outward to the 4-stud voxel grid")]
+    region: TerrainRegion,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ReadTerrainSummary {
+    #[schemars(description = "Region to inspect, expanded outward to the 4-stud voxel grid")]
+    region: TerrainRegion,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ImportHeightmap {
+    #[schemars(description = "Absolute path to a grayscale PNG on this machine. Black is the base height and white is height_scale.")]
+    file_path: String,
+    #[schemars(description = "Center of the heightmap [x, y, z]; y is the base height (default: [0, 0, 0])")]
+    position: Option<[f64; 3]>,
+    #[schemars(description = "Footprint [x, z] in studs (default: 4 studs per pixel, max 4096 per side)")]
+    size: Option<[f64; 2]>,
+    #[schemars(description = "Height of a white pixel in studs (default: 256, max: 1024)")]
+    height_scale: Option<f64>,
+    #[schemars(description = "Water surface height in studs above the base. Columns below it are flooded. No water if not specified.")]
+    water_level: Option<f64>,
+    #[schemars(description = "Rules checked in order; each column uses the first rule matching its height and slope. Defaults to rock on slopes over 45 degrees, snow on the top 20%, and sand up to 2 studs above the water.")]
+    material_ramp: Option<Vec<MaterialRule>>,
+    #[schemars(description = "Material for columns no rule matches (default: Grass)")]
+    default_material: Option<TerrainMaterial>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ExportTerrainHeightmap {
+    #[schemars(description = "Region to export, expanded outward to the 4-stud voxel grid. x and z give the image footprint (one pixel per voxel column, max 4096 studs per side); min and max y map to black and white (max 1024 studs apart).")]
+    region: TerrainRegion,
+    #[schemars(description = "Directory to write the PNG files into (default: the system temp directory)")]
+    output_dir: Option<String>,
+    #[schemars(description = "File name prefix (default: 'terrain'). Letters, digits, '-' and '_' only.")]
+    name: Option<String>,
+}
+
+impl ExportTerrainHeightmap {
+    fn validate(&self) -> std::result::Result<(), String> {
+        self.region.validate_bounds()?;
+        let [columns_x, columns_z] = export_columns(&self.region);
+        if columns_x > MAX_COLUMNS || columns_z > MAX_COLUMNS {
+            return Err(format!(
+                "Region footprint is larger than the {} stud limit per side",
+                MAX_COLUMNS as f64 * VOXEL_SIZE
+            ));
+        }
+        let aligned = self.region.aligned();
+        if aligned.max[1] - aligned.min[1] > MAX_HEIGHT {
+            return Err(format!("Region height must be at most {MAX_HEIGHT} studs"));
+        }
+        if let Some(name) = &self.name {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err("name may only contain letters, digits, '-' and '_'".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ImportHeightmap {
+    /// Validate the options and work out the column grid, reading the image header when no size is given
+    fn columns(
+        &self,
+        path: &std::path::Path,
+        height_scale: f64,
+        position: [f64; 3],
+    ) -> std::result::Result<[usize; 2], String> {
+        if !height_scale.is_finite() || height_scale <= 0.0 || height_scale > MAX_HEIGHT {
+            return Err(format!(
+                "height_scale must be greater than 0 and at most {MAX_HEIGHT}"
+            ));
+        }
+        if let Some(water_level) = self.water_level {
+            if !(0.0..=MAX_HEIGHT).contains(&water_level) {
+                return Err(format!("water_level must be between 0 and {MAX_HEIGHT}"));
+            }
+        }
+        if position.iter().any(|c| !c.is_finite()) {
+            return Err("position must be finite numbers".to_string());
+        }
+        for rule in self.material_ramp.iter().flatten() {
+            rule.validate()?;
+        }
+
+        let size = match self.size {
+            Some(size) => size,
+            None => {
+                let (width, height) = image::image_dimensions(path)
+                    .map_err(|e| format!("Failed to read heightmap {}: {e}", path.display()))?;
+                [width as f64 * VOXEL_SIZE, height as f64 * VOXEL_SIZE]
+            }
+        };
+        if size.iter().any(|s| !s.is_finite() || *s < VOXEL_SIZE) {
+            return Err(format!("size must be at least {VOXEL_SIZE} studs per side"));
+        }
+        let columns = size.map(|s| (s / VOXEL_SIZE).round() as usize);
+        if columns.iter().any(|c| *c > MAX_COLUMNS) {
+            return Err(format!(
+                "A {}x{} stud footprint is larger than the {} stud limit per side. Pass a smaller size.",
+                size[0],
+                size[1],
+                MAX_COLUMNS as f64 * VOXEL_SIZE
+            ));
+        }
+        Ok(columns)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ExportInstances {
+    #[schemars(description = "Absolute path of the file to write. The extension picks the format: .rbxm (binary) or .rbxmx (XML).")]
+    file_path: String,
+    #[schemars(description = "Paths of the instances to export (e.g., ['Workspace.Castle']). Defaults to the current Studio selection.")]
+    paths: Option<Vec<String>>,
+}
+
+/// Plugin payload for `SerializeInstances`. Without a property list the plugin only
+/// reports the classes in the subtree; with one it serializes the whole subtree.
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SerializeInstancesRequest {
+    paths: Vec<String>,
+    properties: Option<HashMap<String, Vec<String>>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ImportModelFile {
+    #[schemars(description = "Absolute path of a .rbxm, .rbxmx or Rojo .model.json file")]
+    file_path: String,
+    #[schemars(description = "Path of the instance to import into (e.g., 'ReplicatedStorage.Assets'). Defaults to 'Workspace'.")]
+    parent: Option<String>,
+}
+
+/// Plugin payload for `BuildInstances`: the instances of a model file in pre-order,
+/// with values encoded the same way `SerializeInstances` returns them
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct BuildInstancesRequest {
+    parent: String,
+    instances: Vec<SerializedInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuiltInstances {
+    roots: Vec<String>,
+    #[serde(default)]
+    failures: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SyncProject {
+    #[schemars(description = "Path to a Rojo .project.json file, or a directory containing default.project.json")]
+    project_path: String,
+    #[schemars(description = "Instance to sync a model project's root into (e.g., 'ReplicatedStorage'). Not used for place projects, whose root is the DataModel.")]
+    target: Option<String>,
+    #[schemars(description = "Keep watching the project's files and sync again whenever they change, until stop_project_sync is called. Defaults to false.")]
+    watch: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct StopProjectSync {
+    // No parameters - stops the running sync_project watcher
+}
+
+/// Plugin payload for `SyncInstances`: a built Rojo project flattened the same way as
+/// `BuildInstances`, plus the instances whose children the project fully describes
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SyncInstancesRequest {
+    parent: String,
+    instances: Vec<SerializedInstance>,
+    owned: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SyncedInstances {
+    created: usize,
+    updated: usize,
+    deleted: usize,
+    unchanged: usize,
+    #[serde(default)]
+    failures: Vec<String>,
+    /// Children of owned instances left in place: MCP helpers and non-Archivable instances
+    #[serde(default)]
+    kept: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ExportScripts {
+    #[schemars(description = "Absolute path of the directory to write the Rojo project into. Scripts go under src/, next to a generated default.project.json.")]
+    output_dir: String,
+    #[schemars(description = "Paths of instances to export the scripts under (e.g., ['ServerScriptService', 'ReplicatedStorage.Shared']). Defaults to every service that holds place content.")]
+    paths: Option<Vec<String>>,
+    #[schemars(description = "Project name written to default.project.json. Defaults to the place name.")]
+    project_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct GenerateSourcemap {
+    #[schemars(description = "Absolute path of the directory scripts are exported to with export_scripts. sourcemap.json is written there.")]
+    output_dir: String,
+    #[schemars(description = "Paths of instances to map (e.g., ['ReplicatedStorage', 'ServerScriptService']). Defaults to every service that holds place content.")]
+    paths: Option<Vec<String>>,
+    #[schemars(description = "Also map instances that aren't scripts (e.g. RemoteEvents), like Rojo's --include-non-scripts. Defaults to false.")]
+    include_non_scripts: Option<bool>,
+    #[schemars(description = "Name of the sourcemap's root. Defaults to the place name.")]
+    project_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct SearchScripts {
+    #[schemars(description = "Text to search for. Literal unless regex is true.")]
+    pattern: String,
+    #[schemars(description = "Treat pattern as a regular expression (Rust regex syntax, not Luau patterns). Defaults to false.")]
+    regex: Option<bool>,
+    #[schemars(description = "Match case exactly. Defaults to true.")]
+    case_sensitive: Option<bool>,
+    #[schemars(description = "Only search scripts under these paths (e.g., ['ServerScriptService', 'ReplicatedStorage.Shared']). Defaults to every service that holds place content.")]
+    paths: Option<Vec<String>>,
+    #[schemars(description = "Only search these script classes: 'Script', 'LocalScript' and/or 'ModuleScript'")]
+    classes: Option<Vec<String>>,
+    #[schemars(description = "Lines of context before and after each match (default: 2, max: 10)")]
+    context_lines: Option<usize>,
+    #[schemars(description = "Maximum number of matching lines to return (default: 100, max: 1000)")]
+    max_results: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ModuleGraph {
+    #[schemars(description = "Output format: 'json' (default) or 'dot' for Graphviz")]
+    format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct LintScripts {
+    #[schemars(description = "Scripts or subtrees to lint (e.g., ['ServerScriptService.Main', 'ReplicatedStorage.Shared']). Defaults to every service that holds place content.")]
+    paths: Option<Vec<String>>,
+    #[schemars(description = "Absolute path to a selene.toml, or a directory containing one, choosing the std library and rule levels. Defaults to the nearest selene.toml in the server's working directory or its parents if there is one, otherwise selene's default rules with the 'roblox' std.")]
+    config_path: Option<String>,
+    #[schemars(description = "Maximum number of diagnostics to list (default: 200, max: 2000). Counts always cover every script.")]
+    max_diagnostics: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RunTests {
+    #[schemars(description = "Instances to search for TestEZ '.spec' ModuleScripts or Jest 'jest.config' project roots (e.g., ['ReplicatedStorage.Shared']). Defaults to the services that hold place content; package '_Index' folders are skipped.")]
+    paths: Option<Vec<String>>,
+    #[schemars(description = "'testez' or 'jest'. Detected from the place when omitted.")]
+    framework: Option<String>,
+    #[schemars(description = "Path to the framework's ModuleScript (e.g., 'ReplicatedStorage.DevPackages.TestEZ'). Found by name when omitted.")]
+    framework_path: Option<String>,
+    #[schemars(description = "'edit' (default) runs in the plugin without a playtest. 'playtest' runs on the server of a running playtest through MCPServerCodeRunner, which needs LoadStringEnabled.")]
+    context: Option<String>,
+    #[schemars(description = "Only run tests whose full name matches, passed to the framework's testNamePattern (a Lua pattern for TestEZ, a regex for Jest)")]
+    name_filter: Option<String>,
+    #[schemars(description = "Absolute path to write a JUnit XML report to")]
+    junit_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RunLuauLocal {
+    #[schemars(description = "Absolute path to a Rojo project (a .project.json file, or a directory with default.project.json such as an export_scripts export) or to a .rbxl, .rbxlx, .rbxm or .rbxmx file")]
+    source: String,
+    #[schemars(description = "Luau code to run (e.g., 'local Inventory = require(game.ReplicatedStorage.Shared.Inventory) return Inventory.canStack(\"Sword\", 2)'). Its return values and print/warn output are shown.")]
+    code: Option<String>,
+    #[schemars(description = "Path of the instance code runs as `script`, so relative string requires resolve from it (e.g., 'ReplicatedStorage.Shared.Inventory')")]
+    script: Option<String>,
+    #[schemars(description = "Run the TestEZ '.spec' ModuleScripts instead of code. Defaults to false.")]
+    tests: Option<bool>,
+    #[schemars(description = "With tests: instances to search for specs. Defaults to the services that hold place content; package '_Index' folders are skipped.")]
+    paths: Option<Vec<String>>,
+    #[schemars(description = "With tests: path to the TestEZ ModuleScript. Found by name when omitted.")]
+    framework_path: Option<String>,
+    #[schemars(description = "With tests: only run tests whose full name matches this Lua pattern")]
+    name_filter: Option<String>,
+    #[schemars(description = "With tests: absolute path to write a JUnit XML report to")]
+    junit_path: Option<String>,
+    #[schemars(description = "Seconds before the run is stopped (default 10, max 300)")]
+    timeout_seconds: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CoverageStart {
+    #[schemars(description = "Scripts, or instances whose descendant scripts, to measure (e.g., ['ServerScriptService.Combat', 'ReplicatedStorage.Shared.Inventory'])")]
+    paths: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CoverageReport {
+    #[schemars(description = "Collect the counts from the running playtest. Defaults to true; pass false to only restore the original sources, e.g. when the playtest has already stopped.")]
+    collect: Option<bool>,
+    #[schemars(description = "Absolute path to write the per-file and per-line coverage to as JSON")]
+    json_path: Option<String>,
+    #[schemars(description = "Absolute path to write an LCOV tracefile to. Source files are the paths export_scripts writes the scripts to.")]
+    lcov_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct AddProbe {
+    #[schemars(description = "Path of the script to probe (e.g., 'ServerScriptService.Combat')")]
+    path: String,
+    #[schemars(description = "Line to probe. The probe runs before the first statement starting on it.")]
+    line: Option<usize>,
+    #[schemars(description = "Function to probe at the start of its body, by name (e.g., 'applyDamage', 'Combat.applyDamage' or 'Weapon:fire'), as an alternative to line")]
+    function: Option<String>,
+    #[schemars(description = "Luau expression(s) to record, evaluated where the probe runs (e.g., 'player.Name, amount'). Defaults to the function's parameters; a line probe without one only records that the line ran.")]
+    expression: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct ReadProbes {
+    #[schemars(description = "Empty the buffers after reading, so the next read only shows new records. Defaults to true.")]
+    clear: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RemoveProbes {
+    #[schemars(description = "Only remove the probes in this script. Defaults to every probe in the open place.")]
+    path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct FindReferences {
+    #[schemars(description = "Path of the ModuleScript whose export to find (e.g., 'ReplicatedStorage.Shared.Util'), or of the instance to find when export is omitted (e.g., 'ReplicatedStorage.Remotes.Damage')")]
+    path: String,
+    #[schemars(description = "Name of a function or value the ModuleScript exports (e.g., 'formatTime'). Omit to find references to the instance at path by name.")]
+    export: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct RenameSymbol {
+    #[schemars(description = "Path of the ModuleScript whose export to rename, or of the instance to rename when export is omitted")]
+    path: String,
+    #[schemars(description = "Name of the ModuleScript export to rename. Omit to rename the instance at path itself.")]
+    export: Option<String>,
+    #[schemars(description = "The new name")]
+    new_name: String,
+    #[schemars(description = "Only return the diff of every script the rename would change, without writing anything. Defaults to false.")]
+    dry_run: Option<bool>,
+}
+
+/// Plugin payload for `CollectScripts`
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+struct CollectScriptsRequest {
+    paths: Vec<String>,
+    include_non_scripts: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubtreeClasses {
+    classes: Vec<String>,
+    count: usize,
+}
+
+/// Parse the JSON a plugin tool appends to its "[SUCCESS] ...\n\n" line
+fn parse_plugin_json<T: serde::de::DeserializeOwned>(response: &str) -> std::result::Result<T, String> {
+    let (_, json) = response
+        .split_once("\n\n")
+        .ok_or_else(|| "Plugin response has no data".to_string())?;
+    serde_json::from_str(json).map_err(|e| format!("Invalid plugin response: {e}"))
+}
+
+/// Parse a script source as Luau off the async runtime, describing any syntax errors
+async fn check_syntax(path: &str, source: &str) -> std::result::Result<(), String> {
+    let source = source.to_string();
+    let checked = tokio::task::spawn_blocking(move || luau_syntax::check(&source))
+        .await
+        .map_err(|e| format!("Syntax check failed: {e}"))?;
+    checked.map_err(|errors| luau_syntax::describe(path, &errors))
+}
+
+/// Format conversion problems for a tool result, listing at most `MAX_LISTED_FAILURES`
+fn format_failures(heading: &str, failures: &[String]) -> String {
+    let mut text = format!("\n{heading} ({}):", failures.len());
+    for failure in failures.iter().take(MAX_LISTED_FAILURES) {
+        text.push_str(&format!("\n  {failure}"));
+    }
+    if failures.len() > MAX_LISTED_FAILURES {
+        text.push_str(&format!("\n  ... and {} more", failures.len() - MAX_LISTED_FAILURES));
+    }
+    text
+}
+
+/// Scripts that didn't parse and uses of the name that couldn't be traced, which a search
+/// or rename may have missed
+fn reference_notes(references: &References) -> String {
+    let mut notes = String::new();
+    if !references.syntax_errors.is_empty() {
+        notes.push_str(&format_failures(
+            "Scripts with syntax errors, where references after the error may be missing",
+            &references.syntax_errors,
+        ));
+    }
+    if !references.untraced.is_empty() {
+        notes.push_str(&format_failures(
+            &format!(
+                "Uses of {} on values that couldn't be traced, not included",
+                references.name
+            ),
+            &references.untraced_lines(),
+        ));
+    }
+    notes
+}
+
+/// Quote text as a Luau long string, with enough `=` that the text can't close it
+fn luau_long_string(text: &str) -> String {
+    let mut level = String::new();
+    while text.contains(&format!("]{level}]")) {
+        level.push('=');
+    }
+    format!("[{level}[{text}]{level}]")
+}
+
+/// Check that a class exists in the reflection database and can be created with Instance.new
+fn validate_creatable_class(class_name: &str) -> std::result::Result<(), String> {
+    let database = rbx_reflection_database::get()
+        .map_err(|e| format!("Failed to load reflection database: {e}"))?;
+
+    let Some(class) = database.classes.get(class_name) else {
+        let suggestion = database
+            .classes
+            .keys()
+            .find(|name| name.eq_ignore_ascii_case(class_name));
+        return Err(match suggestion {
+            Some(name) => format!("Unknown class '{class_name}'. Did you mean '{name}'?"),
+            None => format!("Unknown class '{class_name}'"),
+        });
+    };
+
+    if class.tags.contains(&ClassTag::Service) {
+        return Err(format!(
+            "'{class_name}' is a service and cannot be created with Instance.new"
+        ));
+    }
+    if class.tags.contains(&ClassTag::NotCreatable) {
+        return Err(format!("'{class_name}' is not creatable"));
+    }
+
+    Ok(())
+}
+
+// ============ Asset Search Enrichment Types ============
+
+/// Response from the Luau plugin's SearchAssets
+#[derive(Debug, Deserialize)]
+struct PluginSearchResponse {
+    success: bool,
+    error: Option<String>,
+    assets: Vec<PluginAssetResult>,
+    query: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginAssetResult {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    asset_id: u64,
+    name: String,
+    creator: String,
+}
+
+/// Deserialize a number that may come as a string (Luau JSONEncode quirk)
+fn deserialize_number_from_string<'de, D>(deserializer: D) -> std::result::Result<u64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber {
+        String(String),
+        Number(u64),
+    }
+
+    match StringOrNumber::deserialize(deserializer)? {
+        StringOrNumber::String(s) => s.parse().map_err(D::Error::custom),
+        StringOrNumber::Number(n) => Ok(n),
+    }
+}
+
+/// Response from economy.roblox.com/v2/assets/{id}/details
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+#[serde(rename_all = "PascalCase")]
+struct EconomyAssetDetails {
+    asset_id: u64,
+    name: String,
+    description: Option<String>,
+    creator: Option<EconomyCreator>,
+    created: Option<String>,
+    updated: Option<String>,
+    sales: Option<u64>,
+    is_public_domain: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+#[serde(rename_all = "PascalCase")]
+struct EconomyCreator {
+    id: u64,
+    name: String,
+    has_verified_badge: Option<bool>,
+}
+
+/// Enriched asset with all metadata and quality score
+#[derive(Debug, Serialize)]
+struct EnrichedAsset {
+    asset_id: u64,
+    name: String,
+    creator: String,
+    creator_verified: bool,
+    description: Option<String>,
+    favorites: u64,
+    sales: u64,
+    created: Option<String>,
+    updated: Option<String>,
+    quality_score: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+enum ToolArgumentValues {
+    RunCode(RunCode),
+    InsertModel(InsertModel),
+    WriteScript(WriteScript),
+    ReadScript(ReadScript),
+    ReadOutput(ReadOutput),
+    GetStudioState(GetStudioState),
+    StartPlaytest(StartPlaytest),
+    StartSimulation(StartSimulation),
+    StopSimulation(StopSimulation),
+    StopPlaytest(StopPlaytest),
+    MoveCharacter(MoveCharacter),
+    ValidateUI(ValidateUI),
+    CreateResponsiveLayout(CreateResponsiveLayout),
+    PreviewLayout(PreviewLayout),
+    SearchAssets(SearchAssets),
+    PreviewAsset(PreviewAsset),
+    CreateInstance(CreateInstance),
+    CloneInstance(CloneInstance),
+    MoveInstance(MoveInstance),
+    DestroyInstance(DestroyInstance),
+    FindInstances(FindInstancesQuery),
+    ListAttributes(ListAttributesRequest),
+    SetAttributes(SetAttributesRequest),
+    RemoveAttributes(RemoveAttributesRequest),
+    AddTags(TagsRequest),
+    RemoveTags(TagsRequest),
+    ListTags(ListTags),
+    UndoChange(UndoChange),
+    RedoChange(RedoChange),
+    GetSelection(GetSelection),
+    SetSelection(SetSelectionRequest),
+    FocusCamera(FocusCamera),
+    SetCamera(SetCamera),
+    GetCamera(GetCamera),
+    FillTerrain(FillTerrain),
+    ReplaceTerrainMaterial(ReplaceTerrainMaterial),
+    ClearTerrain(ClearTerrain),
+    ReadTerrainSummary(ReadTerrainSummary),
+    ImportHeightmapChunk(HeightmapChunk),
+    ExportHeightmapChunk(ExportHeightmapChunk),
+    SerializeInstances(SerializeInstancesRequest),
+    BuildInstances(BuildInstancesRequest),
+    SyncInstances(SyncInstancesRequest),
+    CollectScripts(CollectScriptsRequest),
+    ApplyScriptEdit(ApplyScriptEditRequest),
+    SnapshotScript(SnapshotScriptRequest),
+    ApplyScriptEdits(ApplyScriptEditsRequest),
+    RunTests(RunTestsRequest),
+    // Note: SimulateInput and ClickGui are handled directly by Rust (HTTP polling)
+    // and don't go through the Luau plugin
+}
+#[tool_router]
+impl RBXStudioServer {
+    pub fn new(state: PackedState, formatting: FormatSettings, history: HistoryStore) -> Self {
+        Self {
+            state,
+            formatting,
+            history: Arc::new(history),
+            probe_lock: Arc::new(Mutex::new(())),
+            coverage_lock: Arc::new(Mutex::new(())),
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(
+        description = "Runs a command in Roblox Studio and returns the printed output. Can be used to both make changes and retrieve information"
+    )]
+    async fn run_code(
+        &self,
+        Parameters(args): Parameters<RunCode>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::RunCode(args))
+            .await
+    }
+
+    #[tool(
+        description = "Inserts a model from the Roblox marketplace into the workspace. Returns the inserted model name."
+    )]
+    async fn insert_model(
+        &self,
+        Parameters(args): Parameters<InsertModel>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::InsertModel(args))
+            .await
+    }
+
+    #[tool(
+        description = "Creates or updates a Script, LocalScript, or ModuleScript with the provided Luau source code. Uses ScriptEditorService to safely write script source."
+    )]
+    async fn write_script(
+        &self,
+        Parameters(args): Parameters<WriteScript>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let mut args = args;
+        let valid = match check_syntax(&args.path, &args.source).await {
+            Ok(()) => true,
+            Err(_) if args.allow_invalid.unwrap_or(false) => false,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        // Source that doesn't parse can't be formatted, so allow_invalid writes it as given
+        let mut formatted = false;
+        if valid && args.format.unwrap_or(self.formatting.format_by_default) {
+            match self.format_source(&args.source).await {
+                Ok(source) => {
+                    formatted = source != args.source;
+                    args.source = source;
+                }
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "{e}\nNothing was written. Pass format: false to write the source as given."
+                    ))]))
+                }
+            }
+        }
+
+        // A script that doesn't exist yet has no source to keep. Any other problem reading
+        // it will fail the write too, and is reported from there.
+        if let Ok(snapshot) = self.snapshot_script(Some(&args.path)).await {
+            if snapshot.source != args.source {
+                if let Err(e) = self.save_revision(&args.path, &snapshot, "write_script").await {
+                    return Ok(CallToolResult::error(vec![Content::text(e)]));
+                }
+            }
+        }
+
+        let mut result = self
+            .generic_tool_run(ToolArgumentValues::WriteScript(args))
+            .await?;
+        if formatted && result.is_error != Some(true) {
+            result.content.push(Content::text(
+                "Formatted with StyLua before writing, so the script's source differs from what was sent. Use read_script before editing it.",
+            ));
+        }
+        Ok(result)
+    }
+
+    #[tool(
+        description = "Reads the source code of an existing Script, LocalScript, or ModuleScript. Returns the full source code as text."
+    )]
+    async fn read_script(
+        &self,
+        Parameters(args): Parameters<ReadScript>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ReadScript(args))
+            .await
+    }
+
+    #[tool(
+        description = "Edits an existing script with a unified diff or search/replace edits instead of resending the whole source. The edit is checked against the script's current source and rejected if it doesn't match, so concurrent edits aren't overwritten. Returns the applied diff."
+    )]
+    async fn edit_script(
+        &self,
+        Parameters(args): Parameters<EditScript>,
+    ) -> Result<CallToolResult, ErrorData> {
+        match (&args.diff, &args.edits) {
+            (Some(_), Some(_)) => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Provide either diff or edits, not both",
+                )]))
+            }
+            (None, None) => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Provide a diff or edits",
+                )]))
+            }
+            _ => {}
+        }
+
+        let snapshot = match self.snapshot_script(Some(&args.path)).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let source = &snapshot.source;
+
+        let patched = match (&args.diff, &args.edits) {
+            (Some(diff), _) => apply_unified_diff(source, diff),
+            (_, Some(edits)) => apply_replacements(source, edits),
+            (None, None) => unreachable!("checked above"),
+        };
+        let patched = match patched {
+            Ok(patched) => patched,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Edit rejected, nothing was written. {e}"
+                ))]))
+            }
+        };
+        if &patched == source {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "[SUCCESS] The edit leaves {} unchanged; nothing was written",
+                args.path
+            ))]));
+        }
+        if !args.allow_invalid.unwrap_or(false) {
+            if let Err(e) = check_syntax(&args.path, &patched).await {
+                return Ok(CallToolResult::error(vec![Content::text(e)]));
+            }
+        }
+
+        Ok(self
+            .replace_script_source(&args.path, &snapshot, patched, "edit_script")
+            .await)
+    }
+
+    #[tool(
+        description = "Formats an existing script in place with StyLua, using the project's stylua.toml (or the server's --stylua-config). The script is only written if its source hasn't changed since it was read. Returns the diff of the formatting changes."
+    )]
+    async fn format_script(
+        &self,
+        Parameters(args): Parameters<FormatScript>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let snapshot = match self.snapshot_script(Some(&args.path)).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let formatted = match self.format_source(&snapshot.source).await {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "{e}\nNothing was written."
+                ))]))
+            }
+        };
+        if formatted == snapshot.source {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "[SUCCESS] {} is already formatted; nothing was written",
+                args.path
+            ))]));
+        }
+
+        Ok(self
+            .replace_script_source(&args.path, &snapshot, formatted, "format_script")
+            .await)
+    }
+
+    #[tool(
+        description = "Lists the saved revisions of a script, or of every script in the open place. A revision of a script's source is saved locally whenever write_script, edit_script, format_script or revert_script replaces it, and kept across server restarts. Pass a revision number to read that revision's source."
+    )]
+    async fn script_history(
+        &self,
+        Parameters(args): Parameters<ScriptHistory>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if args.path.is_none() && args.revision.is_some() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "revision requires path",
+            )]));
+        }
+        let place = match self.snapshot_script(None).await {
+            Ok(snapshot) => snapshot.place(),
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let history = Arc::clone(&self.history);
+        let listed = tokio::task::spawn_blocking(move || {
+            let path = args.path.as_deref();
+            if let (Some(path), Some(revision)) = (path, args.revision) {
+                let (found, source) = history.load(&place, path, revision)?;
+                return Ok(format!(
+                    "[SUCCESS] Revision {revision} of {path} ({}, saved {} before {})\n\n{source}",
+                    found.class_name,
+                    found.saved_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                    found.tool
+                ));
+            }
+
+            let revisions = history.revisions(&place, path)?;
+            let store = history.root().join(&place);
+            if revisions.is_empty() {
+                return Ok(format!(
+                    "[SUCCESS] No revisions saved for {} yet. History is stored in {}",
+                    path.unwrap_or("this place"),
+                    store.display()
+                ));
+            }
+            let mut text = match path {
+                Some(path) => {
+                    let mut text = format!(
+                        "[SUCCESS] {} revision(s) of {path}, newest first:",
+                        revisions.len()
+                    );
+                    for revision in revisions.iter().rev() {
+                        text.push_str(&format!(
+                            "\n  {}: saved {} before {} ({} lines)",
+                            revision.revision,
+                            revision.saved_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                            revision.tool,
+                            revision.lines
+                        ));
+                    }
+                    text
+                }
+                None => {
+                    let mut scripts: BTreeMap<&str, (usize, &Revision)> = BTreeMap::new();
+                    for revision in &revisions {
+                        let entry = scripts.entry(&revision.path).or_insert((0, revision));
+                        *entry = (entry.0 + 1, revision);
+                    }
+                    let mut text = format!(
+                        "[SUCCESS] {} script(s) with saved revisions in this place:",
+                        scripts.len()
+                    );
+                    for (path, (count, latest)) in scripts {
+                        text.push_str(&format!(
+                            "\n  {path}: {count} revision(s), latest {} saved {}",
+                            latest.revision,
+                            latest.saved_at.format("%Y-%m-%d %H:%M:%S UTC")
+                        ));
+                    }
+                    text
+                }
+            };
+            text.push_str(&format!("\nHistory is stored in {}", store.display()));
+            Ok::<_, String>(text)
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Reading script history failed: {e}"), None))?;
+
+        match listed {
+            Ok(text) => Ok(CallToolResult::success(vec![Content::text(text)])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
+        }
+    }
+
+    #[tool(
+        description = "Restores a script to a revision listed by script_history. The current source is saved as a new revision first, so a revert can itself be reverted. A script deleted since is recreated. Returns the diff of the change."
+    )]
+    async fn revert_script(
+        &self,
+        Parameters(args): Parameters<RevertScript>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // If the script can't be read it may have been deleted, which a revert can undo
+        let (place, current) = match self.snapshot_script(Some(&args.path)).await {
+            Ok(snapshot) => (snapshot.place(), Some(snapshot)),
+            Err(_) => match self.snapshot_script(None).await {
+                Ok(snapshot) => (snapshot.place(), None),
+                Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+            },
+        };
+
+        let history = Arc::clone(&self.history);
+        let (path, revision) = (args.path.clone(), args.revision);
+        let loaded = tokio::task::spawn_blocking(move || history.load(&place, &path, revision))
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Reading script history failed: {e}"), None))?;
+        let (found, source) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let Some(current) = current else {
+            return match self
+                .run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
+                    path: args.path.clone(),
+                    source,
+                    script_type: Some(found.class_name),
+                    allow_invalid: Some(true),
+                    format: Some(false),
+                }))
+                .await
+            {
+                Ok(response) if response.starts_with("[SUCCESS]") => {
+                    Ok(CallToolResult::success(vec![Content::text(format!(
+                        "{response}\nRecreated {} from revision {}",
+                        args.path, args.revision
+                    ))]))
+                }
+                Ok(response) | Err(response) => {
+                    Ok(CallToolResult::error(vec![Content::text(response)]))
+                }
+            };
+        };
+        if current.source == source {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "[SUCCESS] {} already has the source of revision {}; nothing was written",
+                args.path, args.revision
+            ))]));
+        }
+        Ok(self
+            .replace_script_source(&args.path, &current, source, "revert_script")
+            .await)
+    }
+
+    #[tool(
+        description = "Searches the source of every Script, LocalScript and ModuleScript for literal text or a regex, e.g. to find where an event is handled. Returns script paths, line numbers and context lines."
+    )]
+    async fn search_scripts(
+        &self,
+        Parameters(args): Parameters<SearchScripts>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let classes = args.classes.unwrap_or_default();
+        if let Some(class) = classes.iter().find(|c| !SCRIPT_CLASSES.contains(&c.as_str())) {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Unknown script class '{class}'. Expected {}",
+                SCRIPT_CLASSES.join(", ")
+            ))]));
+        }
+        let search = match ScriptSearch::new(
+            &args.pattern,
+            args.regex.unwrap_or(false),
+            args.case_sensitive.unwrap_or(true),
+            args.context_lines.unwrap_or(DEFAULT_CONTEXT_LINES),
+            args.max_results.unwrap_or(DEFAULT_MAX_RESULTS),
+        ) {
+            Ok(search) => search,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let collected = match self.collect_scripts(args.paths.unwrap_or_default(), false).await {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let results = tokio::task::spawn_blocking(move || search.run(&collected, &classes))
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Script search failed: {e}"), None))?;
+
+        let mut summary = format!(
+            "[SUCCESS] {} match(es) in {} of {} script(s)",
+            results.matches, results.scripts_matched, results.scripts_searched
+        );
+        if results.truncated {
+            summary.push_str(&format!(
+                " (stopped at max_results = {}; narrow the search or raise the limit)",
+                results.matches
+            ));
+        }
+        summary.push('\n');
+        summary.push_str(&results.output);
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Builds the dependency graph of every script's require() calls, resolved statically against the place: instance paths, script.Parent chains, GetService, WaitForChild/FindFirstChild, string requires and locals holding any of these. Returns the graph as JSON or Graphviz DOT, with require cycles, requires that couldn't be resolved and ModuleScripts nothing requires."
+    )]
+    async fn module_graph(
+        &self,
+        Parameters(args): Parameters<ModuleGraph>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let dot = match args.format.as_deref() {
+            None | Some("json") => false,
+            Some("dot") => true,
+            Some(other) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Unknown format '{other}'. Expected 'json' or 'dot'"
+                ))]))
+            }
+        };
+
+        let collected = match self.collect_scripts(Vec::new(), false).await {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let graph = tokio::task::spawn_blocking(move || module_graph::build(&collected))
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Building the module graph failed: {e}"), None))?;
+
+        let mut summary = format!(
+            "[SUCCESS] {} script(s) with {} resolved require(s): {} unresolved, {} cycle(s), {} module(s) nothing requires",
+            graph.scripts.len(),
+            graph.require_count(),
+            graph.unresolved.len(),
+            graph.cycles.len(),
+            graph.unrequired_modules.len()
+        );
+        if !graph.asset_requires.is_empty() {
+            summary.push_str(&format!(
+                ", {} require(s) by asset id",
+                graph.asset_requires.len()
+            ));
+        }
+        if !graph.syntax_errors.is_empty() {
+            summary.push_str(&format!(
+                "\n{} script(s) have syntax errors; requires after the first error may be missing",
+                graph.syntax_errors.len()
+            ));
+        }
+        if !graph.unresolved.is_empty() && !graph.unrequired_modules.is_empty() {
+            summary.push_str(
+                "\nSome modules nothing requires may be loaded by the unresolved requires",
+            );
+        }
+
+        if !dot {
+            summary.push_str(&format!("\n\n{}", graph.to_json()));
+            return Ok(CallToolResult::success(vec![Content::text(summary)]));
+        }
+        if !graph.cycles.is_empty() {
+            let cycles: Vec<String> = graph.cycles.iter().map(|cycle| cycle.join(" -> ")).collect();
+            summary.push_str(&format_failures("Require cycles", &cycles));
+        }
+        if !graph.unresolved.is_empty() {
+            let unresolved: Vec<String> = graph
+                .unresolved
+                .iter()
+                .map(|require| {
+                    format!(
+                        "{}:{} {}: {}",
+                        require.script, require.line, require.expression, require.reason
+                    )
+                })
+                .collect();
+            summary.push_str(&format_failures("Unresolved requires", &unresolved));
+        }
+        if !graph.unrequired_modules.is_empty() {
+            summary.push_str(&format_failures(
+                "Modules nothing requires",
+                &graph.unrequired_modules,
+            ));
+        }
+        summary.push_str(&format!("\n\n{}", graph.to_dot()));
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Finds every reference to a ModuleScript export (e.g. formatTime of ReplicatedStorage.Shared.Util) or to an instance that scripts reach by name, across all scripts. Each script's syntax tree is resolved through requires, locals, script.Parent chains, WaitForChild/FindFirstChild and string requires, so unrelated uses of the same name aren't included. Uses on values that can't be traced are listed separately."
+    )]
+    async fn find_references(
+        &self,
+        Parameters(args): Parameters<FindReferences>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let collected = match self.collect_scripts(Vec::new(), true).await {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let found = tokio::task::spawn_blocking(move || {
+            script_refactor::find(&collected, &args.path, args.export.as_deref())
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Finding references failed: {e}"), None))?;
+        let references = match found {
+            Ok(references) => references,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let mut summary = format!(
+            "[SUCCESS] {} reference(s) to {} in {} script(s)",
+            references.found.len(),
+            references.symbol,
+            references.script_count()
+        );
+        summary.push_str(&reference_notes(&references));
+        summary.push('\n');
+        summary.push_str(&references.listing());
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Renames a ModuleScript export or an instance together with every reference find_references resolves to it, across all scripts. Returns the diff of each changed script; with dry_run nothing is written. Otherwise every script's current source is saved to its history and all edits, plus the instance rename, are applied as one change that undo_last_change reverts."
+    )]
+    async fn rename_symbol(
+        &self,
+        Parameters(args): Parameters<RenameSymbol>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let collected = match self.collect_scripts(Vec::new(), true).await {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let (path, export, new_name) = (args.path.clone(), args.export.clone(), args.new_name.clone());
+        let planned = tokio::task::spawn_blocking(move || {
+            let references = script_refactor::find(&collected, &path, export.as_deref())?;
+            let renamed =
+                script_refactor::rename(&collected, &references, &path, export.as_deref(), &new_name)?;
+            Ok::<_, String>((references, renamed))
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Planning the rename failed: {e}"), None))?;
+        let (references, renamed) = match planned {
+            Ok(planned) => planned,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        if renamed.is_empty() && references.instance.is_none() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "No references to {} found, so nothing was renamed{}",
+                references.symbol,
+                reference_notes(&references)
+            ))]));
+        }
+
+        let diff: String = renamed
+            .iter()
+            .map(|script| unified_diff(&script.path, &script.source, &script.renamed))
+            .collect();
+        let changes = format!(
+            "{} reference(s) in {} script(s){}",
+            references.found.len(),
+            renamed.len(),
+            match &references.instance {
+                Some(path) => format!(" and the instance {path}"),
+                None => String::new(),
+            }
+        );
+        let notes = reference_notes(&references);
+
+        if args.dry_run.unwrap_or(false) {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "[SUCCESS] Dry run: renaming {} to {} would change {changes}. Nothing was written.{notes}\n\n{diff}",
+                references.symbol, args.new_name
+            ))]));
+        }
+
+        let place = match self.snapshot_script(None).await {
+            Ok(place) => place,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        for script in &renamed {
+            let snapshot = ScriptSnapshot {
+                place_id: place.place_id,
+                place_name: place.place_name.clone(),
+                class_name: script.class_name.clone(),
+                source: script.source.clone(),
+                run_context: None,
+            };
+            if let Err(e) = self.save_revision(&script.path, &snapshot, "rename_symbol").await {
+                return Ok(CallToolResult::error(vec![Content::text(e)]));
+            }
+        }
+
+        let edits = renamed
+            .into_iter()
+            .map(|script| ApplyScriptEditRequest {
+                path: script.path,
+                expected_source: script.source,
+                source: script.renamed,
+            })
+            .collect();
+        let rename = references.instance.clone().map(|path| InstanceRename {
+            path,
+            name: args.new_name.clone(),
+        });
+        match self
+            .run_tool_raw(ToolArgumentValues::ApplyScriptEdits(ApplyScriptEditsRequest {
+                edits,
+                rename,
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "{response}\nRenamed {} to {}: {changes}. undo_last_change reverts all of it.{notes}\n\n{diff}",
+                    references.symbol, args.new_name
+                ))]))
+            }
+            Ok(response) | Err(response) => Ok(CallToolResult::error(vec![Content::text(response)])),
+        }
+    }
+
+    #[tool(
+        description = "Lints Luau scripts with selene's rules, run in the server: one script, a subtree or the whole place. Reports each diagnostic's severity, rule, line and column with suggested fixes. Rules and std library are configured by a project's selene.toml."
+    )]
+    async fn lint_scripts(
+        &self,
+        Parameters(args): Parameters<LintScripts>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let max_diagnostics = args.max_diagnostics.unwrap_or(DEFAULT_MAX_DIAGNOSTICS);
+        if max_diagnostics == 0 || max_diagnostics > MAX_DIAGNOSTICS {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "max_diagnostics must be between 1 and {MAX_DIAGNOSTICS}"
+            ))]));
+        }
+        let config_path = args.config_path.map(PathBuf::from);
+        if config_path.as_ref().is_some_and(|path| !path.is_absolute()) {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "config_path must be an absolute path",
+            )]));
+        }
+
+        let collected = match self.collect_scripts(args.paths.unwrap_or_default(), false).await {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let linted = tokio::task::spawn_blocking(move || {
+            let linter = Linter::load(config_path.as_deref())?;
+            let config = match &linter.config_file {
+                Some(file) => file.display().to_string(),
+                None => "selene defaults".to_string(),
+            };
+            Ok::<_, String>((
+                format!("{config} (std: {})", linter.std),
+                linter.run(&collected, max_diagnostics),
+            ))
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Linting failed: {e}"), None))?;
+        let (config, results) = match linted {
+            Ok(linted) => linted,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let mut summary = format!(
+            "[SUCCESS] {} error(s), {} warning(s) in {} of {} script(s)\nConfig: {config}",
+            results.errors, results.warnings, results.scripts_flagged, results.scripts_linted
+        );
+        if results.truncated {
+            summary.push_str(&format!(
+                "\nListing stopped at max_diagnostics = {max_diagnostics}; lint fewer scripts or raise the limit."
+            ));
+        }
+        summary.push('\n');
+        summary.push_str(&results.output);
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Runs TestEZ or Jest-Lua test suites in the place and returns pass/fail/skip counts with each failure's message and stack trace. Runs in edit mode through the plugin by default, loading scripts from their current editor source, or on the server of a running playtest. Can also write a JUnit XML report."
+    )]
+    async fn run_tests(
+        &self,
+        Parameters(args): Parameters<RunTests>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let playtest = match args.context.as_deref() {
+            None | Some("edit") => false,
+            Some("playtest") => true,
+            Some(other) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Unknown context '{other}'. Expected 'edit' or 'playtest'"
+                ))]))
+            }
+        };
+        let junit_path = args.junit_path.map(PathBuf::from);
+        if junit_path.as_ref().is_some_and(|path| !path.is_absolute()) {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "junit_path must be an absolute path",
+            )]));
+        }
+
+        let request = RunTestsRequest {
+            paths: args.paths.unwrap_or_default(),
+            framework: args.framework,
+            framework_path: args.framework_path,
+            name_filter: args.name_filter,
+        };
+        let json = if playtest {
+            let options = serde_json::to_string(&request)
+                .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+            let code = format!(
+                "local TestRunner = (function()\n{TEST_RUNNER_SOURCE}\nend)()\nlocal options = game:GetService(\"HttpService\"):JSONDecode({})\nreturn TestRunner.run(options, false)",
+                luau_long_string(&options)
+            );
+            self.run_server_code_raw(code).await
+        } else {
+            match self.run_tool_raw(ToolArgumentValues::RunTests(request)).await {
+                Ok(response) if response.starts_with("[SUCCESS]") => response
+                    .split_once("\n\n")
+                    .map(|(_, json)| json.to_string())
+                    .ok_or_else(|| "Plugin response has no data".to_string()),
+                Ok(response) | Err(response) => Err(response),
+            }
+        };
+        let report = json.and_then(|json| {
+            serde_json::from_str::<TestReport>(&json)
+                .map_err(|e| format!("Invalid test runner response: {e}"))
+        });
+        let report = match report {
+            Ok(report) => report,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let mut summary = report.summary(if playtest { "playtest" } else { "edit" });
+        if let Some(path) = junit_path {
+            let written = tokio::task::spawn_blocking(move || {
+                report.write_junit(&path).map(|()| path)
+            })
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Writing the JUnit report failed: {e}"), None))?;
+            match written {
+                Ok(path) => summary.push_str(&format!("\n\nJUnit report written to {}", path.display())),
+                Err(e) => summary.push_str(&format!("\n\n{e}")),
+            }
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Runs Luau code or TestEZ specs against ModuleScripts loaded from a Rojo project or a place file, in an embedded Luau VM. Doesn't need Studio, so it suits pure logic modules. Instances are read-only, game:GetService returns an empty stand-in for services the source doesn't have, nothing yields, and Roblox types like Vector3 aren't available."
+    )]
+    async fn run_luau_local(
+        &self,
+        Parameters(args): Parameters<RunLuauLocal>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Rust-only implementation - no plugin communication needed
+        let source = PathBuf::from(&args.source);
+        if !source.is_absolute() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "source must be an absolute path",
+            )]));
+        }
+        let junit_path = args.junit_path.map(PathBuf::from);
+        if junit_path.as_ref().is_some_and(|path| !path.is_absolute()) {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "junit_path must be an absolute path",
+            )]));
+        }
+        let timeout = args
+            .timeout_seconds
+            .unwrap_or(luau_local::DEFAULT_TIMEOUT_SECONDS);
+        if !(timeout > 0.0 && timeout <= luau_local::MAX_TIMEOUT_SECONDS) {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "timeout_seconds must be above 0 and at most {}",
+                luau_local::MAX_TIMEOUT_SECONDS
+            ))]));
+        }
+        let task = match (args.code, args.tests.unwrap_or(false)) {
+            (Some(code), false) => LocalTask::Code {
+                code,
+                script: args.script,
+            },
+            (None, true) => LocalTask::Tests {
+                paths: args.paths.unwrap_or_default(),
+                framework_path: args.framework_path,
+                name_filter: args.name_filter,
+            },
+            (Some(_), true) => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Pass either code or tests: true, not both",
+                )]))
+            }
+            (None, false) => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Pass code to run, or tests: true to run the TestEZ specs",
+                )]))
+            }
+        };
+
+        let result = tokio::task::spawn_blocking(move || {
+            let tree = InstanceTree::load(&source)?;
+            let run = luau_local::run(tree, task, Duration::from_secs_f64(timeout))?;
+            let mut summary = run.summary();
+            if let (Outcome::Tests(report), Some(path)) = (&run.outcome, junit_path) {
+                match report.write_junit(&path) {
+                    Ok(()) => summary.push_str(&format!("\n\nJUnit report written to {}", path.display())),
+                    Err(e) => summary.push_str(&format!("\n\n{e}")),
+                }
+            }
+            Ok::<_, String>((matches!(run.outcome, Outcome::Failed(_)), summary))
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Local Luau run failed: {e}"), None))?;
+        match result {
+            Ok((false, summary)) => Ok(CallToolResult::success(vec![Content::text(summary)])),
+            Ok((true, summary)) | Err(summary) => {
+                Ok(CallToolResult::error(vec![Content::text(summary)]))
+            }
+        }
+    }
+
+    #[tool(
+        description = "Starts a code coverage run: instruments the given scripts with line counters and installs the ReplicatedStorage.MCPCoverage module that counts them. Start a playtest afterwards and exercise the game, then call coverage_report while it's still running. The run is recorded on disk, so coverage_report restores the sources even after the server restarts, and the sources before instrumenting are saved to the script history for revert_script."
+    )]
+    async fn coverage_start(
+        &self,
+        Parameters(args): Parameters<CoverageStart>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if args.paths.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "paths must name at least one script or instance",
+            )]));
+        }
+        let _guard = self.coverage_lock.lock().await;
+        let place = match self.snapshot_script(None).await {
+            Ok(place) => place,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        match self.load_coverage(&place.place()).await {
+            Ok(None) => {}
+            Ok(Some(session)) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "A coverage run of {} script(s) is already active. Call coverage_report to finish it first.",
+                    session.scripts.len()
+                ))]))
+            }
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        }
+
+        let collected = match self.collect_scripts(args.paths.clone(), false).await {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let plan = tokio::task::spawn_blocking(move || coverage::plan(&collected))
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Instrumenting scripts failed: {e}"), None))?;
+        let plan = match plan {
+            Ok(plan) => plan,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        if plan.scripts.is_empty() {
+            let mut message = format!("No scripts to instrument under {}", args.paths.join(", "));
+            if !plan.skipped.is_empty() {
+                message.push('\n');
+                message.push_str(&format_failures("Skipped", &plan.skipped));
+            }
+            return Ok(CallToolResult::error(vec![Content::text(message)]));
+        }
+
+        match self
+            .run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
+                path: coverage::HELPER_PATH.to_string(),
+                source: coverage::helper_source(),
+                script_type: Some("ModuleScript".to_string()),
+                allow_invalid: None,
+                format: Some(false),
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {}
+            Ok(response) | Err(response) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to install {}: {response}",
+                    coverage::HELPER_PATH
+                ))]))
+            }
+        }
+
+        // Every planned script is recorded before any is rewritten, so coverage_report can
+        // restore them even if the server stops partway; untouched ones are skipped there
+        let mut session = CoverageSession {
+            place_id: place.place_id,
+            place_name: place.place_name.clone(),
+            scripts: plan.scripts,
+        };
+        if let Err(e) = self.save_coverage(&place.place(), &session).await {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Couldn't record the coverage run, so no scripts were instrumented: {e}"
+            ))]));
+        }
+        let mut failures = Vec::new();
+        for script in std::mem::take(&mut session.scripts) {
+            let snapshot = ScriptSnapshot {
+                place_id: place.place_id,
+                place_name: place.place_name.clone(),
+                class_name: script.class_name.clone(),
+                source: script.original.clone(),
+                run_context: None,
+            };
+            if let Err(e) = self.save_revision(&script.path, &snapshot, "coverage_start").await {
+                failures.push(e);
+                continue;
+            }
+            // Not WriteScript: ApplyScriptEdit refuses the write if the script changed since
+            // it was collected, so an edit made in between isn't lost
+            match self
+                .run_tool_raw(ToolArgumentValues::ApplyScriptEdit(ApplyScriptEditRequest {
+                    path: script.path.clone(),
+                    expected_source: script.original.clone(),
+                    source: script.instrumented.clone(),
+                }))
+                .await
+            {
+                Ok(response) if response.starts_with("[SUCCESS]") => session.scripts.push(script),
+                Ok(response) | Err(response) => failures.push(format!("{}: {response}", script.path)),
+            }
+        }
+        // Failing to narrow the record down is harmless: coverage_report skips untouched scripts
+        if let Err(e) = self.save_coverage(&place.place(), &session).await {
+            failures.push(e);
+        }
+        if session.scripts.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "No scripts were instrumented\n{}",
+                format_failures("Failed", &failures)
+            ))]));
+        }
+
+        let lines: usize = session.scripts.iter().map(|script| script.lines.len()).sum();
+        let mut summary = format!(
+            "[SUCCESS] Instrumented {lines} line(s) in {} script(s). Start a playtest and exercise the game, then call coverage_report before stopping it.\n",
+            session.scripts.len()
+        );
+        for script in &session.scripts {
+            summary.push_str(&format!("\n  {} ({} lines)", script.path, script.lines.len()));
+        }
+        if !plan.skipped.is_empty() {
+            summary.push('\n');
+            summary.push_str(&format_failures("Skipped", &plan.skipped));
+        }
+        if !failures.is_empty() {
+            summary.push('\n');
+            summary.push_str(&format_failures("Not instrumented", &failures));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Finishes the coverage run coverage_start began: collects the line counts from the playtest's server and clients, reports per-script coverage with the lines that never ran, optionally writes JSON and LCOV files, and restores the scripts' original sources. Call it while the playtest is still running."
+    )]
+    async fn coverage_report(
+        &self,
+        Parameters(args): Parameters<CoverageReport>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let collect = args.collect.unwrap_or(true);
+        let json_path = args.json_path.map(PathBuf::from);
+        let lcov_path = args.lcov_path.map(PathBuf::from);
+        if [&json_path, &lcov_path]
+            .iter()
+            .any(|path| path.as_ref().is_some_and(|path| !path.is_absolute()))
+        {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "json_path and lcov_path must be absolute paths",
+            )]));
+        }
+        if !collect && (json_path.is_some() || lcov_path.is_some()) {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "json_path and lcov_path need collect: true",
+            )]));
+        }
+        let _guard = self.coverage_lock.lock().await;
+        let place = match self.snapshot_script(None).await {
+            Ok(place) => place,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let session = match self.load_coverage(&place.place()).await {
+            Ok(Some(session)) => session,
+            Ok(None) => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "No coverage run is active in the open place. Call coverage_start first.",
+                )]))
+            }
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let mut summary = if collect {
+            let hits = self
+                .run_server_code_raw(session.collect_code())
+                .await
+                .and_then(|json| coverage::parse_hits(&json));
+            let hits = match hits {
+                Ok(hits) => hits,
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "{e}\n\nThe scripts are still instrumented. Call coverage_report again while the playtest is running, or pass collect: false to only restore them."
+                    ))]))
+                }
+            };
+            let report = coverage::CoverageReport::new(&session, &hits);
+            let mut summary = report.summary();
+            let notes = tokio::task::spawn_blocking(move || {
+                let mut notes = Vec::new();
+                if let Some(path) = json_path {
+                    notes.push(match report.write_json(&path) {
+                        Ok(()) => format!("JSON report written to {}", path.display()),
+                        Err(e) => e,
+                    });
+                }
+                if let Some(path) = lcov_path {
+                    notes.push(match report.write_lcov(&path) {
+                        Ok(()) => format!("LCOV report written to {}", path.display()),
+                        Err(e) => e,
+                    });
+                }
+                notes
+            })
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Writing the coverage report failed: {e}"), None))?;
+            if !notes.is_empty() {
+                summary.push_str(&format!("\n\n{}", notes.join("\n")));
+            }
+            summary
+        } else {
+            format!(
+                "[SUCCESS] Finished the coverage run of {} script(s) without collecting counts",
+                session.scripts.len()
+            )
+        };
+
+        // Scripts edited since coverage_start are left for revert_script; ones that couldn't be
+        // read or written stay in the run so another coverage_report can retry them
+        let CoverageSession {
+            place_id,
+            place_name,
+            scripts,
+        } = session;
+        let mut changed = Vec::new();
+        let mut failed = Vec::new();
+        let mut pending = Vec::new();
+        for script in scripts {
+            let current = match self.snapshot_script(Some(&script.path)).await {
+                Ok(current) => current,
+                Err(e) => {
+                    failed.push(format!("{}: {e}", script.path));
+                    pending.push(script);
+                    continue;
+                }
+            };
+            if current.source == script.original {
+                continue;
+            }
+            if current.source != script.instrumented {
+                changed.push(format!(
+                    "{}: edited since coverage_start; revert_script restores the revision it saved",
+                    script.path
+                ));
+                continue;
+            }
+            match self
+                .run_tool_raw(ToolArgumentValues::ApplyScriptEdit(ApplyScriptEditRequest {
+                    path: script.path.clone(),
+                    expected_source: script.instrumented.clone(),
+                    source: script.original.clone(),
+                }))
+                .await
+            {
+                Ok(response) if response.starts_with("[SUCCESS]") => {}
+                Ok(response) | Err(response) => {
+                    failed.push(format!("{}: {response}", script.path));
+                    pending.push(script);
+                }
+            }
+        }
+        if !changed.is_empty() {
+            summary.push('\n');
+            summary.push_str(&format_failures("Not restored", &changed));
+        }
+        if !failed.is_empty() {
+            summary.push('\n');
+            summary.push_str(&format_failures(
+                "Couldn't be restored; call coverage_report with collect: false to retry",
+                &failed,
+            ));
+        }
+        let remaining = CoverageSession {
+            place_id,
+            place_name,
+            scripts: pending,
+        };
+        if let Err(e) = self.save_coverage(&place.place(), &remaining).await {
+            summary.push_str(&format!("\n\n{e}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Adds a temporary probe to a script for live debugging: a one-line call that records the values of an expression each time a line or the start of a function runs, without shifting any line numbers. Probes take effect in the next playtest; read_probes shows what they recorded and remove_probes restores the exact original sources. Probes are tracked on disk, so remove_probes finds them even after the server restarts."
+    )]
+    async fn add_probe(
+        &self,
+        Parameters(args): Parameters<AddProbe>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let target = match (args.line, args.function) {
+            (Some(line), None) => ProbeTarget::Line(line),
+            (None, Some(function)) => ProbeTarget::Function(function),
+            _ => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Pass either line or function",
+                )]))
+            }
+        };
+        if args.path == probes::HELPER_PATH {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "MCPProbes can't probe itself",
+            )]));
+        }
+
+        let _guard = self.probe_lock.lock().await;
+        let snapshot = match self.snapshot_script(Some(&args.path)).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let mut book = match self.load_probes(&snapshot.place()).await {
+            Ok(book) => book,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let previous = book.clone();
+        // Probes edited out of the script are forgotten; once none are left it starts over
+        if let Some(index) = book.scripts.iter().position(|script| script.path == args.path) {
+            let script = &mut book.scripts[index];
+            script.probes.retain(|probe| snapshot.source.contains(&probe.snippet));
+            if script.probes.is_empty() {
+                book.scripts.remove(index);
+            }
+        }
+        let id = book.next_id();
+        let (probed, probe) =
+            match probes::insert(&snapshot.source, id, &target, args.expression.as_deref()) {
+                Ok(inserted) => inserted,
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "{}: {e}",
+                        args.path
+                    ))]))
+                }
+            };
+        match book.scripts.iter_mut().find(|script| script.path == args.path) {
+            Some(script) => {
+                // Edits made since the last probe become part of the source remove_probes restores
+                let Some(original) = script.strip(&snapshot.source) else {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "{}: the script was edited and a probe now appears more than once, so its source without probes can't be worked out. Call remove_probes first.",
+                        args.path
+                    ))]));
+                };
+                script.original = original;
+                script.run_context = snapshot.run_context.clone();
+                script.probed = probed.clone();
+                script.probes.push(probe.clone());
+            }
+            None => {
+                if let Err(e) = self.save_revision(&args.path, &snapshot, "add_probe").await {
+                    return Ok(CallToolResult::error(vec![Content::text(e)]));
+                }
+                book.scripts.push(ProbedScript {
+                    path: args.path.clone(),
+                    class_name: snapshot.class_name.clone(),
+                    run_context: snapshot.run_context.clone(),
+                    original: snapshot.source.clone(),
+                    probed: probed.clone(),
+                    probes: vec![probe.clone()],
+                });
+            }
+        }
+
+        match self
+            .run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
+                path: probes::HELPER_PATH.to_string(),
+                source: probes::helper_source(),
+                script_type: Some("ModuleScript".to_string()),
+                allow_invalid: None,
+                format: Some(false),
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {}
+            Ok(response) | Err(response) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to install {}: {response}",
+                    probes::HELPER_PATH
+                ))]))
+            }
+        }
+        // Recorded before the probe is written, so a crash can't leave an untracked probe
+        if let Err(e) = self.save_probes(&snapshot.place(), &book).await {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "{e}\nThe probe wasn't added."
+            ))]));
+        }
+        match self
+            .run_tool_raw(ToolArgumentValues::ApplyScriptEdit(ApplyScriptEditRequest {
+                path: args.path.clone(),
+                expected_source: snapshot.source.clone(),
+                source: probed,
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {}
+            Ok(response) | Err(response) => {
+                let _ = self.save_probes(&snapshot.place(), &previous).await;
+                return Ok(CallToolResult::error(vec![Content::text(response)]));
+            }
+        }
+
+        let location = match &probe.function {
+            Some(function) => format!("{}:{} (start of {function})", args.path, probe.line),
+            None => format!("{}:{}", args.path, probe.line),
+        };
+        let recording = match &probe.expression {
+            Some(expression) => format!("recording `{expression}`"),
+            None => "recording each time it runs".to_string(),
+        };
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "[SUCCESS] Added probe {} at {location}, {recording}. Probes take effect in the next playtest; read_probes shows what they record and remove_probes restores the original sources.\n{} probe(s) active in {} script(s)",
+            probe.id,
+            book.probe_count(),
+            book.scripts.len()
+        ))]))
+    }
+
+    #[tool(
+        description = "Reads what the probes add_probe inserted have recorded in the running playtest, from the server and every client, oldest first. Each record shows the probe's script and line, where it ran, and the values of its expression or the error evaluating it."
+    )]
+    async fn read_probes(
+        &self,
+        Parameters(args): Parameters<ReadProbes>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let place = match self.snapshot_script(None).await {
+            Ok(place) => place,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let book = match self.load_probes(&place.place()).await {
+            Ok(book) => book,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let records = self
+            .run_server_code_raw(book.collect_code(args.clear.unwrap_or(true)))
+            .await
+            .and_then(|json| probes::parse_records(&json));
+        let records = match records {
+            Ok(records) => records,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let mut summary = format!(
+            "[SUCCESS] {} record(s) from the server and {} client(s); {} probe(s) active in {} script(s)",
+            records.records.len(),
+            records.clients,
+            book.probe_count(),
+            book.scripts.len()
+        );
+        if records.dropped > 0 {
+            summary.push_str(&format!(
+                "\n{} older record(s) were dropped from full buffers",
+                records.dropped
+            ));
+        }
+        if !records.errors.is_empty() {
+            summary.push('\n');
+            summary.push_str(&format_failures("Collection problems", &records.errors));
+        }
+        if records.records.is_empty() {
+            summary.push_str("\n\nNothing recorded yet. Probes record in playtests started after add_probe, when their line runs.");
+        } else {
+            summary.push('\n');
+            summary.push_str(&records.render(&book));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(
+        description = "Removes the probes add_probe inserted and restores each script's exact source from before its first probe. A script edited since keeps the edits and loses only the probes."
+    )]
+    async fn remove_probes(
+        &self,
+        Parameters(args): Parameters<RemoveProbes>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let _guard = self.probe_lock.lock().await;
+        let place = match self.snapshot_script(None).await {
+            Ok(place) => place,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let mut book = match self.load_probes(&place.place()).await {
+            Ok(book) => book,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        let (targets, mut kept): (Vec<ProbedScript>, Vec<ProbedScript>) = book
+            .scripts
+            .drain(..)
+            .partition(|script| args.path.as_ref().is_none_or(|path| &script.path == path));
+        if targets.is_empty() {
+            return Ok(match &args.path {
+                Some(path) => CallToolResult::error(vec![Content::text(format!("{path} has no probes"))]),
+                None => CallToolResult::success(vec![Content::text("[SUCCESS] No probes to remove")]),
+            });
+        }
+
+        let mut removed = 0;
+        let mut restored = Vec::new();
+        let mut failed = Vec::new();
+        for script in targets {
+            let current = match self.snapshot_script(Some(&script.path)).await {
+                Ok(current) => current,
+                Err(e) => {
+                    failed.push(format!("{}: {e}", script.path));
+                    kept.push(script);
+                    continue;
+                }
+            };
+            let Some(source) = script.strip(&current.source) else {
+                failed.push(format!(
+                    "{}: edited so its probes can't be taken out safely; revert_script restores the revision add_probe saved before the first probe",
+                    script.path
+                ));
+                kept.push(script);
+                continue;
+            };
+            if source != current.source {
+                match self
+                    .run_tool_raw(ToolArgumentValues::ApplyScriptEdit(ApplyScriptEditRequest {
+                        path: script.path.clone(),
+                        expected_source: current.source.clone(),
+                        source,
+                    }))
+                    .await
+                {
+                    Ok(response) if response.starts_with("[SUCCESS]") => {}
+                    Ok(response) | Err(response) => {
+                        failed.push(format!("{}: {response}", script.path));
+                        kept.push(script);
+                        continue;
+                    }
+                }
+            }
+            removed += script.probes.len();
+            restored.push(script.path);
+        }
+        book.scripts = kept;
+        let saved = self.save_probes(&place.place(), &book).await;
+
+        let mut summary = format!(
+            "Removed {removed} probe(s) from {} script(s)",
+            restored.len()
+        );
+        for path in &restored {
+            summary.push_str(&format!("\n  {path}"));
+        }
+        if !failed.is_empty() {
+            summary.push('\n');
+            summary.push_str(&format_failures("Not removed", &failed));
+        }
+        if let Err(e) = saved {
+            summary.push_str(&format!("\n\n{e}"));
+        }
+        if restored.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(summary)]));
+        }
+        Ok(CallToolResult::success(vec![Content::text(format!("[SUCCESS] {summary}"))]))
+    }
+
+    #[tool(
+        description = "Captures a screenshot of the Roblox Studio window and returns it as a JPEG image. Useful for visual debugging, verifying UI changes, or analyzing the workspace layout."
+    )]
+    async fn capture_screenshot(
+        &self,
+        Parameters(_args): Parameters<CaptureScreenshot>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Rust-only implementation - no plugin communication needed
+        match Self::take_studio_screenshot().await {
+            Ok(base64_data) => Ok(CallToolResult::success(vec![Content::image(
+                base64_data,
+                "image/jpeg",
+            )])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to capture screenshot: {}",
+                e
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Reads captured output from Roblox Studio's Output window. Captures print(), warn(), and error() messages during both Edit and Play modes. Useful for debugging scripts by checking for errors or reviewing print statements. The buffer holds up to 10,000 messages with FIFO eviction. Will warn if messages were dropped due to buffer overflow."
+    )]
+    async fn read_output(
+        &self,
+        Parameters(args): Parameters<ReadOutput>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ReadOutput(args))
+            .await
+    }
+
+    #[tool(
+        description = "Gets the current Studio mode (edit/play/run) to determine if workspace modifications are safe"
+    )]
+    async fn get_studio_state(
+        &self,
+        Parameters(_args): Parameters<GetStudioState>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::GetStudioState(GetStudioState {}))
+            .await
+    }
+
+    #[tool(
+        description = "Starts playtest mode with a player character. Use this to test gameplay with player controls."
+    )]
+    async fn start_playtest(
+        &self,
+        Parameters(_args): Parameters<StartPlaytest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::StartPlaytest(StartPlaytest {}))
+            .await
+    }
+
+    #[tool(
+        description = "Starts simulation mode (run) without a player character. Use this to test physics and scripts without player interaction."
+    )]
+    async fn start_simulation(
+        &self,
+        Parameters(_args): Parameters<StartSimulation>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::StartSimulation(StartSimulation {}))
+            .await
+    }
+
+    #[tool(
+        description = "Stops playtest or simulation mode and returns to edit mode."
+    )]
+    async fn stop_simulation(
+        &self,
+        Parameters(_args): Parameters<StopSimulation>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::StopSimulation(StopSimulation {}))
+            .await
+    }
+
+    #[tool(
+        description = "Stops playtest mode and returns to edit mode. Alias for stop_simulation."
+    )]
+    async fn stop_playtest(
+        &self,
+        Parameters(_args): Parameters<StopPlaytest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::StopPlaytest(StopPlaytest {}))
+            .await
+    }
+
+    #[tool(
+        description = "Moves or teleports a character in the workspace. Works in simulation mode where the plugin has direct access to the game. Use instant=true for teleporting or instant=false to walk to the position."
+    )]
+    async fn move_character(
+        &self,
+        Parameters(args): Parameters<MoveCharacter>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::MoveCharacter(args))
+            .await
+    }
+
+    /// Identify the open place and read a script's current source, including unsaved
+    /// editor changes. Without a path, only the place is identified.
+    async fn snapshot_script(&self, path: Option<&str>) -> std::result::Result<ScriptSnapshot, String> {
+        match self
+            .run_tool_raw(ToolArgumentValues::SnapshotScript(SnapshotScriptRequest {
+                path: path.map(str::to_string),
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => parse_plugin_json(&response),
+            Ok(response) | Err(response) => Err(response),
+        }
+    }
+
+    /// The scripts under `paths` (every service with place content if empty) and their
+    /// ancestors, plus every other instance there if `include_non_scripts`
+    async fn collect_scripts(
+        &self,
+        paths: Vec<String>,
+        include_non_scripts: bool,
+    ) -> std::result::Result<CollectedScripts, String> {
+        match self
+            .run_tool_raw(ToolArgumentValues::CollectScripts(CollectScriptsRequest {
+                paths,
+                include_non_scripts,
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => parse_plugin_json(&response),
+            Ok(response) | Err(response) => Err(response),
+        }
+    }
+
+    /// Save a script's current source to its history before a tool replaces it
+    async fn save_revision(
+        &self,
+        path: &str,
+        snapshot: &ScriptSnapshot,
+        tool: &str,
+    ) -> std::result::Result<(), String> {
+        let history = Arc::clone(&self.history);
+        let (place, path_owned, tool) = (snapshot.place(), path.to_string(), tool.to_string());
+        let (class_name, source) = (snapshot.class_name.clone(), snapshot.source.clone());
+        tokio::task::spawn_blocking(move || {
+            history.record(&place, &path_owned, &class_name, &source, &tool)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|recorded| recorded)
+        .map(|_| ())
+        .map_err(|e| format!("Couldn't save the current source of {path} to its history, so nothing was written: {e}"))
+    }
+
+    /// A place's active coverage run from its history directory
+    async fn load_coverage(
+        &self,
+        place: &str,
+    ) -> std::result::Result<Option<CoverageSession>, String> {
+        let place_dir = self.history.root().join(place);
+        tokio::task::spawn_blocking(move || CoverageSession::load(&place_dir))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|loaded| loaded)
+    }
+
+    async fn save_coverage(
+        &self,
+        place: &str,
+        session: &CoverageSession,
+    ) -> std::result::Result<(), String> {
+        let (place_dir, session) = (self.history.root().join(place), session.clone());
+        tokio::task::spawn_blocking(move || session.save(&place_dir))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|saved| saved)
+    }
+
+    /// A place's probe book from its history directory
+    async fn load_probes(&self, place: &str) -> std::result::Result<ProbeBook, String> {
+        let place_dir = self.history.root().join(place);
+        tokio::task::spawn_blocking(move || ProbeBook::load(&place_dir))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|loaded| loaded)
+    }
+
+    async fn save_probes(&self, place: &str, book: &ProbeBook) -> std::result::Result<(), String> {
+        let (place_dir, book) = (self.history.root().join(place), book.clone());
+        tokio::task::spawn_blocking(move || book.save(&place_dir))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|saved| saved)
+    }
+
+    /// Replace a script's source after saving the old one to its history, but only if it
+    /// still has the source in the snapshot. Returns the tool result with the diff.
+    async fn replace_script_source(
+        &self,
+        path: &str,
+        snapshot: &ScriptSnapshot,
+        source: String,
+        tool: &str,
+    ) -> CallToolResult {
+        if let Err(e) = self.save_revision(path, snapshot, tool).await {
+            return CallToolResult::error(vec![Content::text(e)]);
+        }
+        let diff = unified_diff(path, &snapshot.source, &source);
+        match self
+            .run_tool_raw(ToolArgumentValues::ApplyScriptEdit(ApplyScriptEditRequest {
+                path: path.to_string(),
+                expected_source: snapshot.source.clone(),
+                source,
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {
+                CallToolResult::success(vec![Content::text(format!("{response}\n\n{diff}"))])
+            }
+            Ok(response) | Err(response) => CallToolResult::error(vec![Content::text(response)]),
+        }
+    }
+
+    /// Format a script source with StyLua off the async runtime
+    async fn format_source(&self, source: &str) -> std::result::Result<String, String> {
+        let settings = self.formatting.clone();
+        let source = source.to_string();
+        tokio::task::spawn_blocking(move || {
+            luau_format::format(&source, settings.load_config()?)
+        })
+        .await
+        .map_err(|e| format!("Formatting failed: {e}"))?
+    }
+
+    /// Internal helper to run a tool and get the raw string result
+    async fn run_tool_raw(&self, args: ToolArgumentValues) -> Result<String, String> {
+        let tool = args.variant_name();
+        let (command, id) = ToolArguments::new(args);
+        let (tx, mut rx) = mpsc::unbounded_channel::<Result<RunCommandResponse>>();
+        let trigger = {
+            let mut state = self.state.lock().await;
+            state.process_queue.push_back(command);
+            state.output_map.insert(id, tx);
+            state.trigger.clone()
+        };
+
+        if trigger.send(()).is_err() {
+            return Err("Failed to trigger command".to_string());
+        }
+
+        let result = match timeout(TOOL_EXECUTION_TIMEOUT, rx.recv()).await {
+            Ok(Some(result)) => result,
+            Ok(None) => {
+                let mut state = self.state.lock().await;
+                state.output_map.remove_entry(&id);
+                return Err("Channel closed".to_string());
+            }
+            Err(_) => {
+                let mut state = self.state.lock().await;
+                state.output_map.remove_entry(&id);
+                return Err("Timeout".to_string());
+            }
+        };
+
+        {
+            let mut state = self.state.lock().await;
+            state.output_map.remove_entry(&id);
+        }
+
+        let response = result.map_err(|e| e.to_string())?;
+        self.record_change(id, tool, response.recording).await;
+        Ok(response.response)
+    }
+
+    /// Remember the waypoint a tool call committed so it can be undone later
+    async fn record_change(&self, id: Uuid, tool: String, waypoint: Option<String>) {
+        let Some(waypoint) = waypoint else {
+            return;
+        };
+        let mut state = self.state.lock().await;
+        // A new waypoint clears Studio's redo stack
+        state.change_history.retain(|record| !record.undone);
+        state.change_history.push(ChangeRecord {
+            id,
+            tool,
+            waypoint,
+            timestamp: current_timestamp_ms(),
+            undone: false,
+        });
+    }
+
+    /// Check if MCP input scripts are installed, and install them if not.
+    /// Returns (scripts_were_installed, error_message_if_any)
+    async fn ensure_input_scripts_installed(&self) -> (bool, Option<String>) {
+        // Check if all MCP scripts exist
+        let check_code = r#"
+            local poller = game:GetService("ServerScriptService"):FindFirstChild("MCPInputPoller")
+            local sps = game:GetService("StarterPlayer"):FindFirstChild("StarterPlayerScripts")
+            local handler = sps and sps:FindFirstChild("MCPInputHandler")
+            local movement = sps and sps:FindFirstChild("MCPMovementHandler")
+            local clickSupport = game:GetService("ReplicatedStorage"):FindFirstChild("MCPClickSupport")
+            return tostring(poller ~= nil) .. "," .. tostring(handler ~= nil) .. "," .. tostring(movement ~= nil) .. "," .. tostring(clickSupport ~= nil)
+        "#;
+
+        let scripts_status = match self.run_tool_raw(ToolArgumentValues::RunCode(RunCode {
+            command: check_code.to_string(),
+        })).await {
+            Ok(status) => status,
+            Err(e) => {
+                return (false, Some(format!("Failed to check scripts: {}", e)));
+            }
+        };
+
+        let parts: Vec<&str> = scripts_status.trim().split(',').collect();
+        let poller_exists = parts.first().is_some_and(|s| s.contains("true"));
+        let handler_exists = parts.get(1).is_some_and(|s| s.contains("true"));
+        let movement_exists = parts.get(2).is_some_and(|s| s.contains("true"));
+        let click_support_exists = parts.get(3).is_some_and(|s| s.contains("true"));
+
+        if poller_exists && handler_exists && movement_exists && click_support_exists {
+            return (false, None); // Scripts already installed
+        }
+
+        // Install missing scripts
+        let mut installed = Vec::new();
+
+        if !poller_exists {
+            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
+                path: "ServerScriptService.MCPInputPoller".to_string(),
+                source: MCP_INPUT_POLLER_SOURCE.to_string(),
+                script_type: Some("Script".to_string()),
+                allow_invalid: None,
+                format: Some(false),
+            })).await.is_err() {
+                return (false, Some("Failed to install MCPInputPoller script".to_string()));
+            }
+            installed.push("MCPInputPoller (ServerScriptService)");
+        }
+
+        if !handler_exists {
+            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
+                path: "StarterPlayer.StarterPlayerScripts.MCPInputHandler".to_string(),
+                source: MCP_INPUT_HANDLER_SOURCE.to_string(),
+                script_type: Some("LocalScript".to_string()),
+                allow_invalid: None,
+                format: Some(false),
+            })).await.is_err() {
+                return (false, Some("Failed to install MCPInputHandler script".to_string()));
+            }
+            installed.push("MCPInputHandler (StarterPlayerScripts)");
+        }
+
+        if !movement_exists {
+            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
+                path: "StarterPlayer.StarterPlayerScripts.MCPMovementHandler".to_string(),
+                source: MCP_MOVEMENT_HANDLER_SOURCE.to_string(),
+                script_type: Some("LocalScript".to_string()),
+                allow_invalid: None,
+                format: Some(false),
+            })).await.is_err() {
+                return (false, Some("Failed to install MCPMovementHandler script".to_string()));
+            }
+            installed.push("MCPMovementHandler (StarterPlayerScripts)");
+        }
+
+        if !click_support_exists {
+            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
+                path: "ReplicatedStorage.MCPClickSupport".to_string(),
+                source: MCP_CLICK_SUPPORT_SOURCE.to_string(),
+                script_type: Some("ModuleScript".to_string()),
+                allow_invalid: None,
+                format: Some(false),
+            })).await.is_err() {
+                return (false, Some("Failed to install MCPClickSupport module".to_string()));
+            }
+            installed.push("MCPClickSupport (ReplicatedStorage)");
+        }
+
+        (true, if installed.is_empty() { None } else { Some(installed.join(", ")) })
+    }
+
+    #[tool(
+        description = "Simulates keyboard or mouse input during playtest. Required scripts (MCPInputPoller, MCPInputHandler) will be auto-installed if missing. Supports keyboard keys (W, A, S, D, Space, E, etc.) and mouse buttons (Left, Right, Middle)."
+    )]
+    async fn simulate_input(
+        &self,
+        Parameters(args): Parameters<SimulateInput>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Check and install scripts if needed
+        let (scripts_installed, installed_names) = self.ensure_input_scripts_installed().await;
+
+        let command = InputCommand {
+            command_type: "input".to_string(),
+            data: serde_json::json!({
+                "inputType": args.input_type,
+                "key": args.key,
+                "action": args.action,
+                "mouseX": args.mouse_x,
+                "mouseY": args.mouse_y,
+            }),
+            id: Uuid::new_v4(),
+            timestamp: current_timestamp_ms(),
+        };
+
+        // POST to the HTTP server to ensure command reaches the right instance
+        let client = reqwest::Client::new();
+        let result = client
+            .post(format!("http://127.0.0.1:{STUDIO_PLUGIN_PORT}/mcp/input"))
+            .json(&serde_json::json!({ "command": command }))
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                let mut message = format!(
+                    "Queued {} input: {} {} (id: {}).",
+                    args.input_type, args.key, args.action, command.id
+                );
+                if scripts_installed {
+                    if let Some(names) = installed_names {
+                        message.push_str(&format!(
+                            "\n\n✅ Auto-installed required scripts: {}.\nNote: You must restart playtest (stop and F5 again) for the scripts to take effect.",
+                            names
+                        ));
+                    }
+                }
+                Ok(CallToolResult::success(vec![Content::text(message)]))
+            }
+            Ok(response) => {
+                Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to queue input command: HTTP {} - Is Roblox Studio running?",
+                    response.status()
+                ))]))
+            }
+            Err(e) => {
+                Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to queue input command: {} - Is the MCP server running?",
+                    e
+                ))]))
+            }
+        }
+    }
+
+    #[tool(
+        description = "Simulates clicking a GUI element during playtest. Required scripts (MCPInputPoller, MCPInputHandler) will be auto-installed if missing. Provide the path to the GUI element (e.g., 'ScreenGui.PlayButton')."
+    )]
+    async fn click_gui(
+        &self,
+        Parameters(args): Parameters<ClickGui>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Check and install scripts if needed
+        let (scripts_installed, installed_names) = self.ensure_input_scripts_installed().await;
+
+        let command = InputCommand {
+            command_type: "gui_click".to_string(),
+            data: serde_json::json!({
+                "path": args.path,
+            }),
+            id: Uuid::new_v4(),
+            timestamp: current_timestamp_ms(),
+        };
+
+        // POST to the HTTP server to ensure command reaches the right instance
+        let client = reqwest::Client::new();
+        let result = client
+            .post(format!("http://127.0.0.1:{STUDIO_PLUGIN_PORT}/mcp/input"))
+            .json(&serde_json::json!({ "command": command }))
+            .send()
+            .await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                let mut message = format!(
+                    "Queued GUI click: {} (id: {}).",
+                    args.path, command.id
+                );
+                if scripts_installed {
+                    if let Some(names) = installed_names {
+                        message.push_str(&format!(
+                            "\n\n✅ Auto-installed required scripts: {}.\nNote: You must restart playtest (stop and F5 again) for the scripts to take effect.",
+                            names
+                        ));
+                    }
+                }
+                Ok(CallToolResult::success(vec![Content::text(message)]))
+            }
+            Ok(response) => {
+                Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to queue GUI click: HTTP {} - Is Roblox Studio running?",
+                    response.status()
+                ))]))
+            }
+            Err(e) => {
+                Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to queue GUI click: {} - Is the MCP server running?",
+                    e
+                ))]))
+            }
+        }
+    }
+
+    #[tool(
+        description = "Executes Luau code in the server context during playtest. Unlike run_code which executes in the plugin context, this runs in the actual game server where ServerScriptService scripts execute. Requires MCPServerCodeRunner script in ServerScriptService. Use this to: verify server-side state, test game logic, check _G values set by server scripts, or invoke server functions."
+    )]
+    async fn run_server_code(
+        &self,
+        Parameters(args): Parameters<RunServerCode>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let command_id = Uuid::new_v4();
+        let command = ServerCodeCommand {
+            id: command_id,
+            code: args.code.clone(),
+            timestamp: current_timestamp_ms(),
+        };
+
+        // Create channel to receive result
+        let (tx, mut rx) = mpsc::unbounded_channel::<ServerCodeResult>();
+
+        // Queue the command and register for result
+        {
+            let mut state = self.state.lock().await;
+            state.server_code_queue.push_back(command);
+            state.server_code_results.insert(command_id, tx);
+        }
+
+        // Wait for result with timeout
+        let result = match timeout(SERVER_CODE_TIMEOUT, rx.recv()).await {
+            Ok(Some(result)) => result,
+            Ok(None) => {
+                // Channel closed without response
+                let mut state = self.state.lock().await;
+                state.server_code_results.remove(&command_id);
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Server code execution channel closed unexpectedly. Is the MCPServerCodeRunner script running?",
+                )]));
+            }
+            Err(_) => {
+                // Timeout elapsed
+                let mut state = self.state.lock().await;
+                state.server_code_results.remove(&command_id);
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Server code execution timed out after {}s. Ensure:\n\
+                    1. Studio is in playtest mode (F5)\n\
+                    2. MCPServerCodeRunner script is in ServerScriptService\n\
+                    3. The script is polling http://localhost:{}/mcp/server_code",
+                    SERVER_CODE_TIMEOUT.as_secs(),
+                    STUDIO_PLUGIN_PORT
+                ))]));
+            }
+        };
+
+        // Clean up
+        {
+            let mut state = self.state.lock().await;
+            state.server_code_results.remove(&command_id);
+        }
+
+        // Return result
+        if result.success {
+            Ok(CallToolResult::success(vec![Content::text(
+                result.result.unwrap_or_else(|| "nil".to_string()),
+            )]))
+        } else {
+            Ok(CallToolResult::error(vec![Content::text(format!(
+                "Server code error: {}",
+                result.error.unwrap_or_else(|| "Unknown error".to_string())
+            ))]))
+        }
+    }
+
+    /// Helper to run generated code on server and wait for result
+    async fn run_generated_server_code(&self, code: String) -> Result<CallToolResult, ErrorData> {
+        match self.run_server_code_raw(code).await {
+            Ok(result) => Ok(CallToolResult::success(vec![Content::text(result)])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(e)])),
+        }
+    }
+
+    /// Run generated code on the server and get what it returned as a string
+    async fn run_server_code_raw(&self, code: String) -> std::result::Result<String, String> {
+        let command_id = Uuid::new_v4();
+        let command = ServerCodeCommand {
+            id: command_id,
+            code,
+            timestamp: current_timestamp_ms(),
+        };
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<ServerCodeResult>();
+
+        {
+            let mut state = self.state.lock().await;
+            state.server_code_queue.push_back(command);
+            state.server_code_results.insert(command_id, tx);
+        }
+
+        let result = match timeout(SERVER_CODE_TIMEOUT, rx.recv()).await {
+            Ok(Some(result)) => result,
+            Ok(None) => {
+                let mut state = self.state.lock().await;
+                state.server_code_results.remove(&command_id);
+                return Err("Server execution channel closed. Is MCPServerCodeRunner running?".to_string());
+            }
+            Err(_) => {
+                let mut state = self.state.lock().await;
+                state.server_code_results.remove(&command_id);
+                return Err(format!(
+                    "Timed out after {}s. Ensure playtest is running with MCPServerCodeRunner.",
+                    SERVER_CODE_TIMEOUT.as_secs()
+                ));
+            }
+        };
+
+        {
+            let mut state = self.state.lock().await;
+            state.server_code_results.remove(&command_id);
+        }
+
+        if result.success {
+            Ok(result.result.unwrap_or_else(|| "nil".to_string()))
+        } else {
+            Err(format!(
+                "Error: {}",
+                result.error.unwrap_or_else(|| "Unknown error".to_string())
+            ))
+        }
+    }
+
+    #[tool(
+        description = "Fires a RemoteEvent to clients. Supports 'ToClient' (to specific player) and 'ToAllClients' directions. Note: 'ToServer' is not supported because MCP runs on the server and RemoteEvent.OnServerEvent cannot be manually triggered. Requires MCPServerCodeRunner script in ServerScriptService."
+    )]
+    async fn fire_remote(
+        &self,
+        Parameters(args): Parameters<FireRemote>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let fire_code = match args.direction.as_str() {
+            "ToServer" => {
+                // ToServer doesn't work from server context because:
+                // 1. FireServer() is client-only
+                // 2. OnServerEvent can't be manually triggered (it's an RBXScriptSignal, not BindableEvent)
+                // For testing server event handlers, use run_server_code to call the handler function directly
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "ToServer direction not supported: MCP runs on the server, and RemoteEvent.OnServerEvent \
+                    cannot be manually triggered. To test server event handlers, use run_server_code to call \
+                    the handler function directly, or use simulate_input/click_gui to trigger client actions \
+                    that fire the remote."
+                )]));
+            }
+            "ToClient" => {
+                let player_name = args.player_name.as_deref().unwrap_or("Unknown");
+                match &args.args {
+                    Some(json_args) => format!(
+                        "local HttpService = game:GetService('HttpService')\n\
+                        local Players = game:GetService('Players')\n\
+                        local player = Players:FindFirstChild('{}')\n\
+                        if not player then error('Player not found: {}') end\n\
+                        local args = HttpService:JSONDecode('{}')\n\
+                        remote:FireClient(player, table.unpack(args))\n\
+                        return 'Fired to client: {}'",
+                        player_name, player_name, json_args.replace('\'', "\\'"), player_name
+                    ),
+                    None => format!(
+                        "local Players = game:GetService('Players')\n\
+                        local player = Players:FindFirstChild('{}')\n\
+                        if not player then error('Player not found: {}') end\n\
+                        remote:FireClient(player)\n\
+                        return 'Fired to client: {}'",
+                        player_name, player_name, player_name
+                    ),
+                }
+            }
+            "ToAllClients" => {
+                match &args.args {
+                    Some(json_args) => format!(
+                        "local HttpService = game:GetService('HttpService')\n\
+                        local args = HttpService:JSONDecode('{}')\n\
+                        remote:FireAllClients(table.unpack(args))\n\
+                        return 'Fired to all clients'",
+                        json_args.replace('\'', "\\'")
+                    ),
+                    None => "remote:FireAllClients()\nreturn 'Fired to all clients'".to_string(),
+                }
+            }
+            _ => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Invalid direction. Use 'ToServer', 'ToClient', or 'ToAllClients'",
+                )]));
+            }
+        };
+
+        let code = format!(
+            r#"local path = "{}"
+local parts = string.split(path, ".")
+local current = game
+for _, part in ipairs(parts) do
+    current = current:FindFirstChild(part)
+    if not current then
+        error("Remote not found: " .. path .. " (failed at: " .. part .. ")")
+    end
+end
+local remote = current
+if not remote:IsA("RemoteEvent") then
+    error("Object at " .. path .. " is not a RemoteEvent, it's a " .. remote.ClassName)
+end
+{}"#,
+            args.path, fire_code
+        );
+
+        self.run_generated_server_code(code).await
+    }
+
+    #[tool(
+        description = "Validates UI elements for common layout issues. Checks for: overlapping elements, offscreen elements, pixel positioning (Offset without Scale), missing UISizeConstraint, and AnchorPoint/Position mismatches. Returns a JSON report of issues found."
+    )]
+    async fn validate_ui(
+        &self,
+        Parameters(args): Parameters<ValidateUI>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::ValidateUI(args))
+            .await
+    }
+
+    #[tool(
+        description = "Creates a responsive UI layout with best-practice container structure. Creates a ScreenGui with positioned containers that include UISizeConstraint and UIListLayout for proper responsive behavior."
+    )]
+    async fn create_responsive_layout(
+        &self,
+        Parameters(args): Parameters<CreateResponsiveLayout>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CreateResponsiveLayout(args))
+            .await
+    }
+
+    #[tool(
+        description = "Calculates what UI layout would look like at a specific viewport size (e.g., mobile device). Returns JSON with element positions and sizes, identifying elements that would be offscreen or overlapping. Useful for checking mobile layouts without the Device Emulator."
+    )]
+    async fn preview_layout(
+        &self,
+        Parameters(args): Parameters<PreviewLayout>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::PreviewLayout(args))
+            .await
+    }
+
+    #[tool(
+        description = "Searches the Roblox marketplace for assets matching a query. Returns a ranked list of assets with quality scores based on favorites, creator verification, and recency. Assets are sorted by quality score to help you choose the best option."
+    )]
+    async fn search_assets(
+        &self,
+        Parameters(args): Parameters<SearchAssets>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Step 1: Get basic search results from plugin
+        let plugin_result = self
+            .run_tool_raw(ToolArgumentValues::SearchAssets(args.clone()))
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Plugin search failed: {}", e), None))?;
+
+        // Step 2: Parse the JSON response from plugin
+        let plugin_response: PluginSearchResponse = serde_json::from_str(&plugin_result)
+            .map_err(|e| {
+                ErrorData::internal_error(format!("Failed to parse plugin response: {}", e), None)
+            })?;
+
+        if !plugin_response.success {
+            return Ok(CallToolResult::error(vec![Content::text(
+                plugin_response
+                    .error
+                    .unwrap_or_else(|| "Unknown search error".to_string()),
+            )]));
+        }
+
+        if plugin_response.assets.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No assets found for query: '{}'",
+                plugin_response.query
+            ))]));
+        }
+
+        // Step 3: Enrich assets with web API data
+        let enriched = self.enrich_assets(plugin_response.assets).await;
+
+        // Step 4: Format results
+        let mut lines = vec![
+            format!(
+                "Found {} assets for '{}', ranked by quality:\n",
+                enriched.len(),
+                plugin_response.query
+            ),
+        ];
+
+        for (i, asset) in enriched.iter().enumerate() {
+            let verified = if asset.creator_verified { " ✓" } else { "" };
+            let desc_preview = asset
+                .description
+                .as_ref()
+                .map(|d| {
+                    let clean = d.lines().next().unwrap_or("").trim();
+                    if clean.len() > 60 {
+                        format!("{}...", &clean[..57])
+                    } else {
+                        clean.to_string()
+                    }
+                })
+                .unwrap_or_default();
+
+            lines.push(format!(
+                "{}. **{}** (ID: {})\n   Creator: {}{} | ⭐ {} favorites | Score: {:.1}\n   {}",
+                i + 1,
+                asset.name,
+                asset.asset_id,
+                asset.creator,
+                verified,
+                asset.favorites,
+                asset.quality_score,
+                desc_preview
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push("Use `preview_asset` with an asset_id to see what it looks like.".to_string());
+
+        Ok(CallToolResult::success(vec![Content::text(lines.join("\n"))]))
+    }
+
+    /// Enrich assets with metadata from Roblox web APIs
+    async fn enrich_assets(&self, assets: Vec<PluginAssetResult>) -> Vec<EnrichedAsset> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+
+        let mut enriched: Vec<EnrichedAsset> = Vec::with_capacity(assets.len());
+
+        // Fetch metadata for each asset concurrently
+        let futures: Vec<_> = assets
+            .into_iter()
+            .map(|asset| {
+                let client = client.clone();
+                async move {
+                    let (economy_data, favorites) = tokio::join!(
+                        Self::fetch_economy_details(&client, asset.asset_id),
+                        Self::fetch_favorites_count(&client, asset.asset_id)
+                    );
+
+                    let (description, creator_verified, sales, created, updated) =
+                        match economy_data {
+                            Ok(details) => (
+                                details.description,
+                                details
+                                    .creator
+                                    .as_ref()
+                                    .and_then(|c| c.has_verified_badge)
+                                    .unwrap_or(false),
+                                details.sales.unwrap_or(0),
+                                details.created,
+                                details.updated,
+                            ),
+                            Err(_) => (None, false, 0, None, None),
+                        };
+
+                    let favorites = favorites.unwrap_or(0);
+
+                    // Calculate quality score
+                    let quality_score =
+                        Self::calculate_quality_score(favorites, creator_verified, &updated, &description);
+
+                    EnrichedAsset {
+                        asset_id: asset.asset_id,
+                        name: asset.name,
+                        creator: asset.creator,
+                        creator_verified,
+                        description,
+                        favorites,
+                        sales,
+                        created,
+                        updated,
+                        quality_score,
+                    }
+                }
+            })
+            .collect();
+
+        let results = futures::future::join_all(futures).await;
+        enriched.extend(results);
+
+        // Sort by quality score (highest first)
+        enriched.sort_by(|a, b| {
+            b.quality_score
+                .partial_cmp(&a.quality_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        enriched
+    }
+
+    /// Fetch asset details from economy.roblox.com
+    async fn fetch_economy_details(
+        client: &reqwest::Client,
+        asset_id: u64,
+    ) -> std::result::Result<EconomyAssetDetails, reqwest::Error> {
+        let url = format!("https://economy.roblox.com/v2/assets/{}/details", asset_id);
+        client.get(&url).send().await?.json().await
+    }
+
+    /// Fetch favorites count from catalog.roblox.com
+    async fn fetch_favorites_count(
+        client: &reqwest::Client,
+        asset_id: u64,
+    ) -> std::result::Result<u64, reqwest::Error> {
+        let url = format!(
+            "https://catalog.roblox.com/v1/favorites/assets/{}/count",
+            asset_id
+        );
+        client.get(&url).send().await?.json().await
+    }
+
+    /// Calculate quality score based on various factors
+    fn calculate_quality_score(
+        favorites: u64,
+        creator_verified: bool,
+        updated: &Option<String>,
+        description: &Option<String>,
+    ) -> f64 {
+        let mut score = 0.0;
+
+        // Favorites: logarithmic scale (diminishing returns)
+        // 100 favorites = ~46 points, 1000 = ~69, 10000 = ~92
+        if favorites > 0 {
+            score += (favorites as f64).ln() * 10.0;
+        }
+
+        // Creator verified badge: +20 points
+        if creator_verified {
+            score += 20.0;
+        }
+
+        // Recency bonus: up to 15 points for recently updated assets
+        if let Some(updated_str) = updated {
+            if let Ok(updated_date) = DateTime::parse_from_rfc3339(updated_str) {
+                let now = Utc::now();
+                let days_ago = (now - updated_date.with_timezone(&Utc)).num_days();
+                // Full 15 points if updated within 30 days, decreasing over 2 years
+                let recency_score = 15.0 * (1.0 - (days_ago as f64 / 730.0).min(1.0));
+                score += recency_score.max(0.0);
+            }
+        }
+
+        // Description quality: up to 10 points
+        if let Some(desc) = description {
+            let desc_len = desc.len();
+            // 5 points for having a description, up to 5 more for length
+            if desc_len > 10 {
+                score += 5.0 + (desc_len as f64 / 200.0).min(1.0) * 5.0;
+            }
+        }
+
+        score
+    }
+
+    #[tool(
+        description = "Previews an asset by temporarily inserting it and taking a screenshot. Use this to see what an asset looks like before committing to it. Set keep=true to keep the asset in workspace, or leave as default to remove after screenshot."
+    )]
+    async fn preview_asset(
+        &self,
+        Parameters(args): Parameters<PreviewAsset>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::PreviewAsset(args))
+            .await
+    }
+
+    #[tool(
+        description = "Creates a new instance under a parent path. The class name is validated against the Roblox reflection database before anything is sent to Studio. Returns the path of the created instance."
+    )]
+    async fn create_instance(
+        &self,
+        Parameters(args): Parameters<CreateInstance>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if let Err(e) = validate_creatable_class(&args.class_name) {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
+        }
+        self.generic_tool_run(ToolArgumentValues::CreateInstance(args))
+            .await
+    }
+
+    #[tool(
+        description = "Clones an instance and its descendants. The clone is placed under the given parent, or next to the original if no parent is given. Returns the path of the clone."
+    )]
+    async fn clone_instance(
+        &self,
+        Parameters(args): Parameters<CloneInstance>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::CloneInstance(args))
+            .await
+    }
+
+    #[tool(
+        description = "Reparents an instance under a new parent. Services cannot be moved, and an instance cannot be moved into its own subtree. Returns the new path."
+    )]
+    async fn move_instance(
+        &self,
+        Parameters(args): Parameters<MoveInstance>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::MoveInstance(args))
+            .await
+    }
+
+    #[tool(
+        description = "Destroys an instance and its descendants. Refuses to destroy services or MCP helper objects (MCPInputPoller, MCPServerCodeRunner, etc.)."
+    )]
+    async fn destroy_instance(
+        &self,
+        Parameters(args): Parameters<DestroyInstance>,
+    ) -> Result<CallToolResult, ErrorData> {
+        self.generic_tool_run(ToolArgumentValues::DestroyInstance(args))
+            .await
+    }
+
+    #[tool(
+        description = "Finds instances matching a selector, e.g. 'class:BasePart under:Workspace.Map tag:Interactive prop:Anchored=false'. The selector is validated before it is sent to Studio. Returns matching paths (up to the limit) and the total match count."
+    )]
+    async fn find_instances(
+        &self,
+        Parameters(args): Parameters<FindInstances>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let selector = match InstanceSelector::parse(&args.query) {
+            Ok(selector) => selector,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Invalid selector: {e}"
+                ))]))
+            }
+        };
+        let limit = args
+            .limit
+            .unwrap_or(FIND_INSTANCES_DEFAULT_LIMIT)
+            .clamp(1, FIND_INSTANCES_MAX_LIMIT);
+        self.generic_tool_run(ToolArgumentValues::FindInstances(FindInstancesQuery {
+            selector,
+            limit,
+        }))
+        .await
+    }
+
+    #[tool(
+        description = "Lists the attributes of an instance (path) or of every instance matching a selector (query), with typed values."
+    )]
+    async fn list_attributes(
+        &self,
+        Parameters(args): Parameters<ListAttributes>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let target = match InstanceTarget::from_args(args.path, args.query) {
+            Ok(target) => target,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        self.generic_tool_run(ToolArgumentValues::ListAttributes(ListAttributesRequest {
+            target,
+        }))
+        .await
+    }
+
+    #[tool(
+        description = "Sets typed attributes on an instance (path) or on every instance matching a selector (query). Bulk changes are recorded as one undo waypoint."
+    )]
+    async fn set_attributes(
+        &self,
+        Parameters(args): Parameters<SetAttributes>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if args.attributes.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Provide at least one attribute",
+            )]));
+        }
+        for (name, value) in &args.attributes {
+            if let Err(e) = validate_attribute_name(name)
+                .and_then(|_| value.validate().map_err(|e| format!("{name}: {e}")))
+            {
+                return Ok(CallToolResult::error(vec![Content::text(e)]));
+            }
+        }
+        let target = match InstanceTarget::from_args(args.path, args.query) {
+            Ok(target) => target,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        self.generic_tool_run(ToolArgumentValues::SetAttributes(SetAttributesRequest {
+            target,
+            attributes: args.attributes,
+        }))
+        .await
+    }
+
+    #[tool(
+        description = "Removes attributes from an instance (path) or from every instance matching a selector (query). Bulk changes are recorded as one undo waypoint."
+    )]
+    async fn remove_attributes(
+        &self,
+        Parameters(args): Parameters<RemoveAttributes>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if args.names.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Provide at least one attribute name",
+            )]));
+        }
+        if let Some(e) = args.names.iter().find_map(|name| validate_attribute_name(name).err()) {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
+        }
+        let target = match InstanceTarget::from_args(args.path, args.query) {
+            Ok(target) => target,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        self.generic_tool_run(ToolArgumentValues::RemoveAttributes(RemoveAttributesRequest {
+            target,
+            names: args.names,
+        }))
+        .await
+    }
+
+    #[tool(
+        description = "Adds CollectionService tags to an instance (path) or to every instance matching a selector (query). Bulk changes are recorded as one undo waypoint."
+    )]
+    async fn add_tags(
+        &self,
+        Parameters(args): Parameters<AddTags>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if let Err(e) = validate_tags(&args.tags) {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
+        }
+        let target = match InstanceTarget::from_args(args.path, args.query) {
+            Ok(target) => target,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        self.generic_tool_run(ToolArgumentValues::AddTags(TagsRequest {
+            target,
+            tags: args.tags,
+        }))
+        .await
+    }
+
+    #[tool(
+        description = "Removes CollectionService tags from an instance (path) or from every instance matching a selector (query). Bulk changes are recorded as one undo waypoint."
+    )]
+    async fn remove_tags(
+        &self,
+        Parameters(args): Parameters<RemoveTags>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if let Err(e) = validate_tags(&args.tags) {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
+        }
+        let target = match InstanceTarget::from_args(args.path, args.query) {
+            Ok(target) => target,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        self.generic_tool_run(ToolArgumentValues::RemoveTags(TagsRequest {
+            target,
+            tags: args.tags,
+        }))
+        .await
+    }
+
+    #[tool(
+        description = "Lists CollectionService tags and the paths of the instances carrying each one. Pass a tag to list just that tag."
+    )]
+    async fn list_tags(
+        &self,
+        Parameters(args): Parameters<ListTags>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let limit = args
+            .limit
+            .unwrap_or(FIND_INSTANCES_DEFAULT_LIMIT)
+            .clamp(1, FIND_INSTANCES_MAX_LIMIT);
+        self.generic_tool_run(ToolArgumentValues::ListTags(ListTags {
+            tag: args.tag,
+            limit: Some(limit),
+        }))
+        .await
+    }
+
+    #[tool(
+        description = "Undoes the most recent change made by an MCP tool call. Refuses if the user has made Studio changes since then, so their work is never undone by mistake."
+    )]
+    async fn undo_last_change(
+        &self,
+        Parameters(_args): Parameters<UndoLastChange>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let record = {
+            let state = self.state.lock().await;
+            state.change_history.iter().rev().find(|r| !r.undone).cloned()
+        };
+        match record {
+            Some(record) => self.undo_record(record).await,
+            None => Ok(CallToolResult::error(vec![Content::text(
+                "No MCP changes to undo",
+            )])),
+        }
+    }
+
+    #[tool(
+        description = "Redoes the most recently undone MCP change. Refuses if Studio's redo stack no longer starts with that change."
+    )]
+    async fn redo(
+        &self,
+        Parameters(_args): Parameters<Redo>,
+    ) -> Result<CallToolResult, ErrorData> {
+        // Undone records always form a suffix of the history; the first of them is next to redo
+        let record = {
+            let state = self.state.lock().await;
+            state.change_history.iter().find(|r| r.undone).cloned()
+        };
+        let Some(record) = record else {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "No undone MCP changes to redo",
+            )]));
+        };
+
+        let response = self
+            .run_tool_raw(ToolArgumentValues::RedoChange(RedoChange {
+                waypoint: record.waypoint.clone(),
+            }))
+            .await;
+        match response {
+            Ok(message) if message.starts_with("[SUCCESS]") => {
+                self.set_undone(record.id, false).await;
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "{message} (tool call {} - {})",
+                    record.id, record.tool
+                ))]))
+            }
+            Ok(message) => Ok(CallToolResult::error(vec![Content::text(message)])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to redo: {e}"
+            ))])),
+        }
+    }
+
+    #[tool(
+        description = "Lists changes made by MCP tool calls in this session, newest first, with their tool call ids and whether they have been undone."
+    )]
+    async fn list_mcp_changes(
+        &self,
+        Parameters(_args): Parameters<ListMcpChanges>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let records: Vec<ChangeRecord> = {
+            let state = self.state.lock().await;
+            state.change_history.iter().rev().cloned().collect()
+        };
+        if records.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No MCP changes recorded in this session",
+            )]));
+        }
+        let json = serde_json::to_string_pretty(&records)
+            .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Undoes the change made by a specific tool call (id from list_mcp_changes). Refuses if later MCP changes are still applied, since they may depend on it; undo those first."
+    )]
+    async fn undo_tool_call(
+        &self,
+        Parameters(args): Parameters<UndoToolCall>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let Ok(id) = Uuid::parse_str(args.id.trim()) else {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Invalid tool call id: {}",
+                args.id
+            ))]));
+        };
+
+        let (record, later) = {
+            let state = self.state.lock().await;
+            let Some(index) = state.change_history.iter().position(|r| r.id == id) else {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "No recorded change for tool call {id}"
+                ))]));
+            };
+            let later: Vec<String> = state.change_history[index + 1..]
+                .iter()
+                .filter(|r| !r.undone)
+                .map(|r| format!("{} ({})", r.id, r.tool))
+                .collect();
+            (state.change_history[index].clone(), later)
+        };
+
+        if record.undone {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Tool call {id} has already been undone"
+            ))]));
+        }
+        if !later.is_empty() {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Cannot undo tool call {id}: later changes depend on it. Undo these first (newest last): {}",
+                later.join(", ")
+            ))]));
+        }
+
+        self.undo_record(record).await
+    }
+
+    async fn undo_record(&self, record: ChangeRecord) -> Result<CallToolResult, ErrorData> {
+        let response = self
+            .run_tool_raw(ToolArgumentValues::UndoChange(UndoChange {
+                waypoint: record.waypoint.clone(),
+            }))
+            .await;
+        match response {
+            Ok(message) if message.starts_with("[SUCCESS]") => {
+                self.set_undone(record.id, true).await;
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "{message} (tool call {} - {})",
+                    record.id, record.tool
+                ))]))
+            }
+            Ok(message) => Ok(CallToolResult::error(vec![Content::text(message)])),
+            Err(e) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "Failed to undo: {e}"
+            ))])),
+        }
+    }
+
+    async fn set_undone(&self, id: Uuid, undone: bool) {
+        let mut state = self.state.lock().await;
+        if let Some(record) = state.change_history.iter_mut().find(|r| r.id == id) {
+            record.undone = undone;
+        }
     }
 
     #[tool(
-        description = "Reads captured output from Roblox Studio's Output window. Captures print(), warn(), and error() messages during both Edit and Play modes. Useful for debugging scripts by checking for errors or reviewing print statements. The buffer holds up to 10,000 messages with FIFO eviction. Will warn if messages were dropped due to buffer overflow."
+        description = "Gets the instances currently selected in Studio. Returns their paths and classes."
     )]
-    async fn read_output(
+    async fn get_selection(
         &self,
-        Parameters(args): Parameters<ReadOutput>,
+        Parameters(_args): Parameters<GetSelection>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::ReadOutput(args))
+        self.generic_tool_run(ToolArgumentValues::GetSelection(GetSelection {}))
             .await
     }
 
     #[tool(
-        description = "Gets the current Studio mode (edit/play/run) to determine if workspace modifications are safe"
+        description = "Sets the Studio selection to the given paths or to every instance matching a selector. Use this to hand the result of your work back to the user. An empty paths list clears the selection."
     )]
-    async fn get_studio_state(
+    async fn set_selection(
         &self,
-        Parameters(_args): Parameters<GetStudioState>,
+        Parameters(args): Parameters<SetSelection>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::GetStudioState(GetStudioState {}))
-            .await
+        let selector = match (&args.paths, args.query) {
+            (Some(_), Some(_)) => {
+                return Ok(CallToolResult::error(vec![Content::text(
+                    "Provide either paths or query, not both",
+                )]))
+            }
+            (_, Some(query)) => match InstanceSelector::parse(&query) {
+                Ok(selector) => Some(selector),
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Invalid selector: {e}"
+                    ))]))
+                }
+            },
+            (_, None) => None,
+        };
+        self.generic_tool_run(ToolArgumentValues::SetSelection(SetSelectionRequest {
+            paths: args.paths.unwrap_or_default(),
+            selector,
+        }))
+        .await
     }
 
     #[tool(
-        description = "Starts playtest mode with a player character. Use this to test gameplay with player controls."
+        description = "Points the Studio camera at an instance so its whole bounding box is in view. Useful before capture_screenshot."
     )]
-    async fn start_playtest(
+    async fn focus_camera(
         &self,
-        Parameters(_args): Parameters<StartPlaytest>,
+        Parameters(args): Parameters<FocusCamera>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::StartPlaytest(StartPlaytest {}))
+        self.generic_tool_run(ToolArgumentValues::FocusCamera(args))
             .await
     }
 
     #[tool(
-        description = "Starts simulation mode (run) without a player character. Use this to test physics and scripts without player interaction."
+        description = "Sets the Studio camera from a full CFrame or from a position and look_at point, and optionally its field of view."
     )]
-    async fn start_simulation(
+    async fn set_camera(
         &self,
-        Parameters(_args): Parameters<StartSimulation>,
+        Parameters(args): Parameters<SetCamera>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::StartSimulation(StartSimulation {}))
+        if let Err(e) = args.validate() {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
+        }
+        self.generic_tool_run(ToolArgumentValues::SetCamera(args))
             .await
     }
 
     #[tool(
-        description = "Stops playtest or simulation mode and returns to edit mode."
+        description = "Gets the Studio camera's CFrame, position, look direction, focus point, field of view and viewport size as JSON."
     )]
-    async fn stop_simulation(
+    async fn get_camera(
         &self,
-        Parameters(_args): Parameters<StopSimulation>,
+        Parameters(_args): Parameters<GetCamera>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::StopSimulation(StopSimulation {}))
+        self.generic_tool_run(ToolArgumentValues::GetCamera(GetCamera {}))
             .await
     }
 
     #[tool(
-        description = "Stops playtest mode and returns to edit mode. Alias for stop_simulation."
+        description = "Fills a block, ball, cylinder, or wedge of terrain with a material. Use material Air to carve shapes out of existing terrain."
     )]
-    async fn stop_playtest(
+    async fn fill_terrain(
         &self,
-        Parameters(_args): Parameters<StopPlaytest>,
+        Parameters(args): Parameters<FillTerrain>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::StopPlaytest(StopPlaytest {}))
+        if let Err(e) = args.validate() {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
+        }
+        self.generic_tool_run(ToolArgumentValues::FillTerrain(args))
             .await
     }
 
     #[tool(
-        description = "Moves or teleports a character in the workspace. Works in simulation mode where the plugin has direct access to the game. Use instant=true for teleporting or instant=false to walk to the position."
+        description = "Replaces one terrain material with another inside a region, leaving the terrain shape unchanged."
     )]
-    async fn move_character(
+    async fn replace_terrain_material(
         &self,
-        Parameters(args): Parameters<MoveCharacter>,
+        Parameters(args): Parameters<ReplaceTerrainMaterial>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::MoveCharacter(args))
-            .await
-    }
-
-    /// Internal helper to run a tool and get the raw string result
-    async fn run_tool_raw(&self, args: ToolArgumentValues) -> Result<String, String> {
-        let (command, id) = ToolArguments::new(args);
-        let (tx, mut rx) = mpsc::unbounded_channel::<Result<String>>();
-        let trigger = {
-            let mut state = self.state.lock().await;
-            state.process_queue.push_back(command);
-            state.output_map.insert(id, tx);
-            state.trigger.clone()
-        };
-
-        if trigger.send(()).is_err() {
-            return Err("Failed to trigger command".to_string());
-        }
-
-        let result = match timeout(TOOL_EXECUTION_TIMEOUT, rx.recv()).await {
-            Ok(Some(result)) => result,
-            Ok(None) => {
-                let mut state = self.state.lock().await;
-                state.output_map.remove_entry(&id);
-                return Err("Channel closed".to_string());
-            }
-            Err(_) => {
-                let mut state = self.state.lock().await;
-                state.output_map.remove_entry(&id);
-                return Err("Timeout".to_string());
-            }
-        };
-
-        {
-            let mut state = self.state.lock().await;
-            state.output_map.remove_entry(&id);
-        }
-
-        result.map_err(|e| e.to_string())
-    }
-
-    /// Check if MCP input scripts are installed, and install them if not.
-    /// Returns (scripts_were_installed, error_message_if_any)
-    async fn ensure_input_scripts_installed(&self) -> (bool, Option<String>) {
-        // Check if all MCP scripts exist
-        let check_code = r#"
-            local poller = game:GetService("ServerScriptService"):FindFirstChild("MCPInputPoller")
-            local sps = game:GetService("StarterPlayer"):FindFirstChild("StarterPlayerScripts")
-            local handler = sps and sps:FindFirstChild("MCPInputHandler")
-            local movement = sps and sps:FindFirstChild("MCPMovementHandler")
-            local clickSupport = game:GetService("ReplicatedStorage"):FindFirstChild("MCPClickSupport")
-            return tostring(poller ~= nil) .. "," .. tostring(handler ~= nil) .. "," .. tostring(movement ~= nil) .. "," .. tostring(clickSupport ~= nil)
-        "#;
-
-        let scripts_status = match self.run_tool_raw(ToolArgumentValues::RunCode(RunCode {
-            command: check_code.to_string(),
-        })).await {
-            Ok(status) => status,
-            Err(e) => {
-                return (false, Some(format!("Failed to check scripts: {}", e)));
-            }
-        };
-
-        let parts: Vec<&str> = scripts_status.trim().split(',').collect();
-        let poller_exists = parts.first().map_or(false, |s| s.contains("true"));
-        let handler_exists = parts.get(1).map_or(false, |s| s.contains("true"));
-        let movement_exists = parts.get(2).map_or(false, |s| s.contains("true"));
-        let click_support_exists = parts.get(3).map_or(false, |s| s.contains("true"));
-
-        if poller_exists && handler_exists && movement_exists && click_support_exists {
-            return (false, None); // Scripts already installed
-        }
-
-        // Install missing scripts
-        let mut installed = Vec::new();
-
-        if !poller_exists {
-            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
-                path: "ServerScriptService.MCPInputPoller".to_string(),
-                source: MCP_INPUT_POLLER_SOURCE.to_string(),
-                script_type: Some("Script".to_string()),
-            })).await.is_err() {
-                return (false, Some("Failed to install MCPInputPoller script".to_string()));
-            }
-            installed.push("MCPInputPoller (ServerScriptService)");
+        if let Err(e) = args.region.validate() {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
         }
-
-        if !handler_exists {
-            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
-                path: "StarterPlayer.StarterPlayerScripts.MCPInputHandler".to_string(),
-                source: MCP_INPUT_HANDLER_SOURCE.to_string(),
-                script_type: Some("LocalScript".to_string()),
-            })).await.is_err() {
-                return (false, Some("Failed to install MCPInputHandler script".to_string()));
-            }
-            installed.push("MCPInputHandler (StarterPlayerScripts)");
+        if args.from_material == args.to_material {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "from_material and to_material are the same",
+            )]));
         }
-
-        if !movement_exists {
-            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
-                path: "StarterPlayer.StarterPlayerScripts.MCPMovementHandler".to_string(),
-                source: MCP_MOVEMENT_HANDLER_SOURCE.to_string(),
-                script_type: Some("LocalScript".to_string()),
-            })).await.is_err() {
-                return (false, Some("Failed to install MCPMovementHandler script".to_string()));
-            }
-            installed.push("MCPMovementHandler (StarterPlayerScripts)");
+        if args.from_material == TerrainMaterial::Air || args.to_material == TerrainMaterial::Air {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "Air cannot be replaced or used as a replacement. Use fill_terrain to add terrain or clear_terrain to remove it.",
+            )]));
         }
+        self.generic_tool_run(ToolArgumentValues::ReplaceTerrainMaterial(args))
+            .await
+    }
 
-        if !click_support_exists {
-            if self.run_tool_raw(ToolArgumentValues::WriteScript(WriteScript {
-                path: "ReplicatedStorage.MCPClickSupport".to_string(),
-                source: MCP_CLICK_SUPPORT_SOURCE.to_string(),
-                script_type: Some("ModuleScript".to_string()),
-            })).await.is_err() {
-                return (false, Some("Failed to install MCPClickSupport module".to_string()));
-            }
-            installed.push("MCPClickSupport (ReplicatedStorage)");
+    #[tool(description = "Removes all terrain, including water, inside a region.")]
+    async fn clear_terrain(
+        &self,
+        Parameters(args): Parameters<ClearTerrain>,
+    ) -> Result<CallToolResult, ErrorData> {
+        if let Err(e) = args.region.validate() {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
         }
-
-        (true, if installed.is_empty() { None } else { Some(installed.join(", ")) })
+        self.generic_tool_run(ToolArgumentValues::ClearTerrain(args))
+            .await
     }
 
     #[tool(
-        description = "Simulates keyboard or mouse input during playtest. Required scripts (MCPInputPoller, MCPInputHandler) will be auto-installed if missing. Supports keyboard keys (W, A, S, D, Space, E, etc.) and mouse buttons (Left, Right, Middle)."
+        description = "Summarizes the terrain inside a region: voxel counts and filled volume per material, and the bounds of the filled voxels. Large regions are estimated from a sample of voxels. Use this to inspect terrain before and after editing it."
     )]
-    async fn simulate_input(
+    async fn read_terrain_summary(
         &self,
-        Parameters(args): Parameters<SimulateInput>,
+        Parameters(args): Parameters<ReadTerrainSummary>,
     ) -> Result<CallToolResult, ErrorData> {
-        // Check and install scripts if needed
-        let (scripts_installed, installed_names) = self.ensure_input_scripts_installed().await;
-
-        let command = InputCommand {
-            command_type: "input".to_string(),
-            data: serde_json::json!({
-                "inputType": args.input_type,
-                "key": args.key,
-                "action": args.action,
-                "mouseX": args.mouse_x,
-                "mouseY": args.mouse_y,
-            }),
-            id: Uuid::new_v4(),
-            timestamp: current_timestamp_ms(),
-        };
-
-        // POST to the HTTP server to ensure command reaches the right instance
-        let client = reqwest::Client::new();
-        let result = client
-            .post(format!("http://127.0.0.1:{STUDIO_PLUGIN_PORT}/mcp/input"))
-            .json(&serde_json::json!({ "command": command }))
-            .send()
-            .await;
-
-        match result {
-            Ok(response) if response.status().is_success() => {
-                let mut message = format!(
-                    "Queued {} input: {} {} (id: {}).",
-                    args.input_type, args.key, args.action, command.id
-                );
-                if scripts_installed {
-                    if let Some(names) = installed_names {
-                        message.push_str(&format!(
-                            "\n\n✅ Auto-installed required scripts: {}.\nNote: You must restart playtest (stop and F5 again) for the scripts to take effect.",
-                            names
-                        ));
-                    }
-                }
-                Ok(CallToolResult::success(vec![Content::text(message)]))
-            }
-            Ok(response) => {
-                Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Failed to queue input command: HTTP {} - Is Roblox Studio running?",
-                    response.status()
-                ))]))
-            }
-            Err(e) => {
-                Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Failed to queue input command: {} - Is the MCP server running?",
-                    e
-                ))]))
-            }
+        if let Err(e) = args.region.validate() {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
         }
+        self.generic_tool_run(ToolArgumentValues::ReadTerrainSummary(args))
+            .await
     }
 
     #[tool(
-        description = "Simulates clicking a GUI element during playtest. Required scripts (MCPInputPoller, MCPInputHandler) will be auto-installed if missing. Provide the path to the GUI element (e.g., 'ScreenGui.PlayButton')."
+        description = "Builds terrain from a local grayscale PNG heightmap. The image is resampled to the 4-stud voxel grid and written in 64x64-column chunks, replacing existing terrain in the footprint up to the height scale. Supports a water level and a material ramp by height and slope."
     )]
-    async fn click_gui(
+    async fn import_heightmap(
         &self,
-        Parameters(args): Parameters<ClickGui>,
+        Parameters(args): Parameters<ImportHeightmap>,
     ) -> Result<CallToolResult, ErrorData> {
-        // Check and install scripts if needed
-        let (scripts_installed, installed_names) = self.ensure_input_scripts_installed().await;
-
-        let command = InputCommand {
-            command_type: "gui_click".to_string(),
-            data: serde_json::json!({
-                "path": args.path,
-            }),
-            id: Uuid::new_v4(),
-            timestamp: current_timestamp_ms(),
+        let path = std::path::PathBuf::from(&args.file_path);
+        let height_scale = args.height_scale.unwrap_or(256.0);
+        let position = args.position.unwrap_or([0.0; 3]);
+        let [columns_x, columns_z] = match args.columns(&path, height_scale, position) {
+            Ok(columns) => columns,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
         };
 
-        // POST to the HTTP server to ensure command reaches the right instance
-        let client = reqwest::Client::new();
-        let result = client
-            .post(format!("http://127.0.0.1:{STUDIO_PLUGIN_PORT}/mcp/input"))
-            .json(&serde_json::json!({ "command": command }))
-            .send()
-            .await;
-
-        match result {
-            Ok(response) if response.status().is_success() => {
-                let mut message = format!(
-                    "Queued GUI click: {} (id: {}).",
-                    args.path, command.id
-                );
-                if scripts_installed {
-                    if let Some(names) = installed_names {
-                        message.push_str(&format!(
-                            "\n\n✅ Auto-installed required scripts: {}.\nNote: You must restart playtest (stop and F5 again) for the scripts to take effect.",
-                            names
-                        ));
-                    }
-                }
-                Ok(CallToolResult::success(vec![Content::text(message)]))
-            }
-            Ok(response) => {
-                Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Failed to queue GUI click: HTTP {} - Is Roblox Studio running?",
-                    response.status()
-                ))]))
-            }
+        let load_path = path.clone();
+        let heightfield = match tokio::task::spawn_blocking(move || {
+            Heightfield::load(&load_path, columns_x, columns_z, height_scale)
+        })
+        .await
+        {
+            Ok(Ok(heightfield)) => heightfield,
+            Ok(Err(e)) => return Ok(CallToolResult::error(vec![Content::text(e)])),
             Err(e) => {
-                Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Failed to queue GUI click: {} - Is the MCP server running?",
-                    e
-                ))]))
+                return Err(ErrorData::internal_error(
+                    format!("Heightmap decoding failed: {e}"),
+                    None,
+                ))
+            }
+        };
+
+        let ramp = args
+            .material_ramp
+            .unwrap_or_else(|| default_ramp(height_scale, args.water_level));
+        let materials =
+            heightfield.materials(&ramp, args.default_material.unwrap_or(TerrainMaterial::Grass));
+
+        // Align the minimum corner to the voxel grid so chunk edges meet exactly
+        let align = |c: f64| (c / VOXEL_SIZE).floor() * VOXEL_SIZE;
+        let origin = [
+            align(position[0] - columns_x as f64 * VOXEL_SIZE / 2.0),
+            align(position[1]),
+            align(position[2] - columns_z as f64 * VOXEL_SIZE / 2.0),
+        ];
+        let top = height_scale.max(args.water_level.unwrap_or(0.0));
+        let voxels_y = (top / VOXEL_SIZE).ceil() as u32;
+        let chunks = heightfield.chunks(&materials, origin, voxels_y, args.water_level);
+
+        let chunk_count = chunks.len();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            match self
+                .run_tool_raw(ToolArgumentValues::ImportHeightmapChunk(chunk))
+                .await
+            {
+                Ok(response) if response.starts_with("[SUCCESS]") => {}
+                Ok(response) | Err(response) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Chunk {} of {chunk_count} failed: {response}\n{index} chunk(s) were already written. Each chunk is its own undo waypoint (see list_mcp_changes).",
+                        index + 1
+                    ))]))
+                }
             }
         }
+
+        let mut material_columns = std::collections::BTreeMap::new();
+        for material in &materials {
+            *material_columns.entry(format!("{material:?}")).or_insert(0usize) += 1;
+        }
+        let (min_height, max_height) = heightfield
+            .heights
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(min, max), h| (min.min(*h), max.max(*h)));
+
+        let mut summary = format!(
+            "[SUCCESS] Imported {} as {columns_x}x{columns_z} columns ({}x{} studs) in {chunk_count} chunk(s)\n\
+             Minimum corner: ({}, {}, {})\n\
+             Heights: {min_height:.1} to {max_height:.1} studs above the base\n\
+             Materials (columns): {}",
+            path.display(),
+            columns_x as f64 * VOXEL_SIZE,
+            columns_z as f64 * VOXEL_SIZE,
+            origin[0],
+            origin[1],
+            origin[2],
+            material_columns
+                .iter()
+                .map(|(material, count)| format!("{material} {count}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if let Some(water_level) = args.water_level {
+            summary.push_str(&format!("\nWater level: {water_level} studs above the base"));
+        }
+        if heightfield.converted_from_color {
+            summary.push_str("\nNote: the image has color channels; its luminance was used as height");
+        }
+        if chunk_count > 1 {
+            summary.push_str(&format!(
+                "\nEach chunk is its own undo waypoint; undo_last_change {chunk_count} times to remove the import"
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
     }
 
     #[tool(
-        description = "Executes Luau code in the server context during playtest. Unlike run_code which executes in the plugin context, this runs in the actual game server where ServerScriptService scripts execute. Requires MCPServerCodeRunner script in ServerScriptService. Use this to: verify server-side state, test game logic, check _G values set by server scripts, or invoke server functions."
+        description = "Exports terrain to a 16-bit grayscale heightmap PNG and a material map PNG on disk, and returns previews of both. Heights map the region's min y to black and max y to white, so the heightmap can be re-imported with import_heightmap."
     )]
-    async fn run_server_code(
+    async fn export_terrain_heightmap(
         &self,
-        Parameters(args): Parameters<RunServerCode>,
+        Parameters(args): Parameters<ExportTerrainHeightmap>,
     ) -> Result<CallToolResult, ErrorData> {
-        let command_id = Uuid::new_v4();
-        let command = ServerCodeCommand {
-            id: command_id,
-            code: args.code.clone(),
-            timestamp: current_timestamp_ms(),
-        };
-
-        // Create channel to receive result
-        let (tx, mut rx) = mpsc::unbounded_channel::<ServerCodeResult>();
-
-        // Queue the command and register for result
-        {
-            let mut state = self.state.lock().await;
-            state.server_code_queue.push_back(command);
-            state.server_code_results.insert(command_id, tx);
+        if let Err(e) = args.validate() {
+            return Ok(CallToolResult::error(vec![Content::text(e)]));
         }
 
-        // Wait for result with timeout
-        let result = match timeout(SERVER_CODE_TIMEOUT, rx.recv()).await {
-            Ok(Some(result)) => result,
-            Ok(None) => {
-                // Channel closed without response
-                let mut state = self.state.lock().await;
-                state.server_code_results.remove(&command_id);
-                return Ok(CallToolResult::error(vec![Content::text(
-                    "Server code execution channel closed unexpectedly. Is the MCPServerCodeRunner script running?",
-                )]));
-            }
-            Err(_) => {
-                // Timeout elapsed
-                let mut state = self.state.lock().await;
-                state.server_code_results.remove(&command_id);
+        let [columns_x, columns_z] = export_columns(&args.region);
+        let mut surface = TerrainSurface::new(columns_x, columns_z);
+        let chunks = export_chunks(&args.region);
+        let chunk_count = chunks.len();
+        for (index, (offset_x, offset_z, region)) in chunks.into_iter().enumerate() {
+            let response = match self
+                .run_tool_raw(ToolArgumentValues::ExportHeightmapChunk(
+                    ExportHeightmapChunk { region },
+                ))
+                .await
+            {
+                Ok(response) if response.starts_with("[SUCCESS]") => response,
+                Ok(response) | Err(response) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!(
+                        "Reading chunk {} of {chunk_count} failed: {response}",
+                        index + 1
+                    ))]))
+                }
+            };
+            let parsed = parse_plugin_json::<ChunkSurface>(&response)
+                .and_then(|chunk| surface.insert(offset_x, offset_z, &chunk));
+            if let Err(e) = parsed {
                 return Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Server code execution timed out after {}s. Ensure:\n\
-                    1. Studio is in playtest mode (F5)\n\
-                    2. MCPServerCodeRunner script is in ServerScriptService\n\
-                    3. The script is polling http://localhost:{}/mcp/server_code",
-                    SERVER_CODE_TIMEOUT.as_secs(),
-                    STUDIO_PLUGIN_PORT
+                    "Invalid data for chunk {} of {chunk_count}: {e}",
+                    index + 1
                 ))]));
             }
+        }
+
+        let aligned = args.region.aligned();
+        let region_height = aligned.max[1] - aligned.min[1];
+        let output_dir = args
+            .output_dir
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        let name = args.name.unwrap_or_else(|| "terrain".to_string());
+        let height_path = output_dir.join(format!("{name}_height.png"));
+        let material_path = output_dir.join(format!("{name}_materials.png"));
+
+        let height_image = surface.height_image(region_height);
+        let material_image = surface.material_image();
+        let (save_height_path, save_material_path) = (height_path.clone(), material_path.clone());
+        let previews = tokio::task::spawn_blocking(move || -> Result<(String, String), Error> {
+            std::fs::create_dir_all(&output_dir)?;
+            height_image.save(&save_height_path)?;
+            material_image.save(&save_material_path)?;
+            Ok((
+                Self::process_screenshot(image::DynamicImage::ImageLuma16(height_image))?,
+                Self::process_screenshot(image::DynamicImage::ImageRgb8(material_image))?,
+            ))
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Image export failed: {e}"), None))?;
+        let (height_preview, material_preview) = match previews {
+            Ok(previews) => previews,
+            Err(e) => {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "Failed to write terrain images: {e}"
+                ))]))
+            }
         };
 
-        // Clean up
-        {
-            let mut state = self.state.lock().await;
-            state.server_code_results.remove(&command_id);
+        let mut summary = format!(
+            "[SUCCESS] Exported {columns_x}x{columns_z} columns from ({}, {}, {}) to ({}, {}, {})\n\
+             Heightmap: {} (black = y {}, white = y {})\n\
+             Material map: {}",
+            aligned.min[0],
+            aligned.min[1],
+            aligned.min[2],
+            aligned.max[0],
+            aligned.max[1],
+            aligned.max[2],
+            height_path.display(),
+            aligned.min[1],
+            aligned.max[1],
+            material_path.display(),
+        );
+        match surface.height_range() {
+            Some((min, max)) => summary.push_str(&format!(
+                "\nGround: {min:.1} to {max:.1} studs above the region's min y"
+            )),
+            None => summary.push_str("\nNo terrain in this region"),
         }
-
-        // Return result
-        if result.success {
-            Ok(CallToolResult::success(vec![Content::text(
-                result.result.unwrap_or_else(|| "nil".to_string()),
-            )]))
-        } else {
-            Ok(CallToolResult::error(vec![Content::text(format!(
-                "Server code error: {}",
-                result.error.unwrap_or_else(|| "Unknown error".to_string())
-            ))]))
+        let legend = surface
+            .material_counts()
+            .into_iter()
+            .map(|(material, count)| {
+                let [r, g, b] = material.map_color();
+                format!("  {material:?} #{r:02x}{g:02x}{b:02x}: {count} columns")
+            })
+            .collect::<Vec<_>>();
+        if !legend.is_empty() {
+            summary.push_str(&format!("\nSurface materials:\n{}", legend.join("\n")));
         }
+        summary.push_str(&format!(
+            "\nTo re-import: import_heightmap with size [{}, {}], height_scale {region_height}, position [{}, {}, {}]",
+            columns_x as f64 * VOXEL_SIZE,
+            columns_z as f64 * VOXEL_SIZE,
+            (aligned.min[0] + aligned.max[0]) / 2.0,
+            aligned.min[1],
+            (aligned.min[2] + aligned.max[2]) / 2.0,
+        ));
+
+        Ok(CallToolResult::success(vec![
+            Content::text(summary),
+            Content::image(height_preview, "image/jpeg"),
+            Content::image(material_preview, "image/jpeg"),
+        ]))
     }
 
-    /// Helper to run generated code on server and wait for result
-    async fn run_generated_server_code(&self, code: String) -> Result<CallToolResult, ErrorData> {
-        let command_id = Uuid::new_v4();
-        let command = ServerCodeCommand {
-            id: command_id,
-            code,
-            timestamp: current_timestamp_ms(),
+    #[tool(
+        description = "Exports instances and their descendants from Studio to a .rbxm or .rbxmx model file on disk, e.g. to check a model into a repository. Properties that can't be exported are listed in the result."
+    )]
+    async fn export_instances(
+        &self,
+        Parameters(args): Parameters<ExportInstances>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let path = std::path::PathBuf::from(&args.file_path);
+        if !path.is_absolute() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "file_path must be an absolute path",
+            )]));
+        }
+        if ModelFormat::from_path(&path).is_none() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "file_path must end in .rbxm or .rbxmx",
+            )]));
+        }
+        let paths = args.paths.unwrap_or_default();
+
+        // First pass: find out which classes are in the subtree
+        let subtree = match self
+            .run_tool_raw(ToolArgumentValues::SerializeInstances(
+                SerializeInstancesRequest {
+                    paths: paths.clone(),
+                    properties: None,
+                },
+            ))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {
+                parse_plugin_json::<SubtreeClasses>(&response)
+            }
+            Ok(response) | Err(response) => Err(response),
+        };
+        let subtree = match subtree {
+            Ok(subtree) => subtree,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
         };
+        if subtree.count > MODEL_FILE_MAX_INSTANCES {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Refusing to export {} instances; the limit is {MODEL_FILE_MAX_INSTANCES}. Export smaller subtrees.",
+                subtree.count
+            ))]));
+        }
 
-        let (tx, mut rx) = mpsc::unbounded_channel::<ServerCodeResult>();
+        let manifest = match property_manifest(&subtree.classes) {
+            Ok(manifest) => manifest,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
 
+        // Second pass: read the manifest's properties for every instance
+        let tree = match self
+            .run_tool_raw(ToolArgumentValues::SerializeInstances(
+                SerializeInstancesRequest {
+                    paths,
+                    properties: Some(manifest.properties),
+                },
+            ))
+            .await
         {
-            let mut state = self.state.lock().await;
-            state.server_code_queue.push_back(command);
-            state.server_code_results.insert(command_id, tx);
-        }
-
-        let result = match timeout(SERVER_CODE_TIMEOUT, rx.recv()).await {
-            Ok(Some(result)) => result,
-            Ok(None) => {
-                let mut state = self.state.lock().await;
-                state.server_code_results.remove(&command_id);
-                return Ok(CallToolResult::error(vec![Content::text(
-                    "Server execution channel closed. Is MCPServerCodeRunner running?",
-                )]));
-            }
-            Err(_) => {
-                let mut state = self.state.lock().await;
-                state.server_code_results.remove(&command_id);
-                return Ok(CallToolResult::error(vec![Content::text(format!(
-                    "Timed out after {}s. Ensure playtest is running with MCPServerCodeRunner.",
-                    SERVER_CODE_TIMEOUT.as_secs()
-                ))]));
+            Ok(response) if response.starts_with("[SUCCESS]") => {
+                parse_plugin_json::<SerializedTree>(&response)
             }
+            Ok(response) | Err(response) => Err(response),
+        };
+        let tree = match tree {
+            Ok(tree) => tree,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
         };
 
-        {
-            let mut state = self.state.lock().await;
-            state.server_code_results.remove(&command_id);
-        }
+        let instance_count = tree.instances.len();
+        let write_path = path.clone();
+        let written = tokio::task::spawn_blocking(move || {
+            let (dom, roots, failures) = build_dom(tree)?;
+            write_model(&write_path, &dom, &roots)?;
+            let size = std::fs::metadata(&write_path).map(|m| m.len()).unwrap_or(0);
+            Ok::<_, String>((roots.len(), failures, size))
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Model export failed: {e}"), None))?;
+        let (root_count, failures, size) = match written {
+            Ok(result) => result,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
 
-        if result.success {
-            Ok(CallToolResult::success(vec![Content::text(
-                result.result.unwrap_or_else(|| "nil".to_string()),
-            )]))
-        } else {
-            Ok(CallToolResult::error(vec![Content::text(format!(
-                "Error: {}",
-                result.error.unwrap_or_else(|| "Unknown error".to_string())
-            ))]))
+        let mut summary = format!(
+            "[SUCCESS] Exported {instance_count} instance(s) under {root_count} root(s) to {} ({size} bytes)",
+            path.display()
+        );
+        if !manifest.unsupported.is_empty() {
+            let unsupported: Vec<String> = manifest.unsupported.into_iter().collect();
+            summary.push_str(&format_failures("Properties not exported", &unsupported));
         }
+        if !failures.is_empty() {
+            summary.push_str(&format_failures("Values that failed to convert", &failures));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
     }
 
     #[tool(
-        description = "Fires a RemoteEvent to clients. Supports 'ToClient' (to specific player) and 'ToAllClients' directions. Note: 'ToServer' is not supported because MCP runs on the server and RemoteEvent.OnServerEvent cannot be manually triggered. Requires MCPServerCodeRunner script in ServerScriptService."
+        description = "Imports a .rbxm, .rbxmx or Rojo .model.json file from disk into Studio under the given parent. The file is parsed by the server; properties and classes that can't be recreated from a plugin are listed in the result."
     )]
-    async fn fire_remote(
+    async fn import_model_file(
         &self,
-        Parameters(args): Parameters<FireRemote>,
+        Parameters(args): Parameters<ImportModelFile>,
     ) -> Result<CallToolResult, ErrorData> {
-        let fire_code = match args.direction.as_str() {
-            "ToServer" => {
-                // ToServer doesn't work from server context because:
-                // 1. FireServer() is client-only
-                // 2. OnServerEvent can't be manually triggered (it's an RBXScriptSignal, not BindableEvent)
-                // For testing server event handlers, use run_server_code to call the handler function directly
-                return Ok(CallToolResult::error(vec![Content::text(
-                    "ToServer direction not supported: MCP runs on the server, and RemoteEvent.OnServerEvent \
-                    cannot be manually triggered. To test server event handlers, use run_server_code to call \
-                    the handler function directly, or use simulate_input/click_gui to trigger client actions \
-                    that fire the remote."
-                )]));
-            }
-            "ToClient" => {
-                let player_name = args.player_name.as_deref().unwrap_or("Unknown");
-                match &args.args {
-                    Some(json_args) => format!(
-                        "local HttpService = game:GetService('HttpService')\n\
-                        local Players = game:GetService('Players')\n\
-                        local player = Players:FindFirstChild('{}')\n\
-                        if not player then error('Player not found: {}') end\n\
-                        local args = HttpService:JSONDecode('{}')\n\
-                        remote:FireClient(player, table.unpack(args))\n\
-                        return 'Fired to client: {}'",
-                        player_name, player_name, json_args.replace('\'', "\\'"), player_name
-                    ),
-                    None => format!(
-                        "local Players = game:GetService('Players')\n\
-                        local player = Players:FindFirstChild('{}')\n\
-                        if not player then error('Player not found: {}') end\n\
-                        remote:FireClient(player)\n\
-                        return 'Fired to client: {}'",
-                        player_name, player_name, player_name
-                    ),
-                }
-            }
-            "ToAllClients" => {
-                match &args.args {
-                    Some(json_args) => format!(
-                        "local HttpService = game:GetService('HttpService')\n\
-                        local args = HttpService:JSONDecode('{}')\n\
-                        remote:FireAllClients(table.unpack(args))\n\
-                        return 'Fired to all clients'",
-                        json_args.replace('\'', "\\'")
-                    ),
-                    None => "remote:FireAllClients()\nreturn 'Fired to all clients'".to_string(),
-                }
+        let path = std::path::PathBuf::from(&args.file_path);
+        let read_path = path.clone();
+        let plan = tokio::task::spawn_blocking(move || {
+            let (dom, resolve_failures) = read_model(&read_path)?;
+            let mut plan = build_plan(&dom, PlanMode::Import)?;
+            plan.failures.splice(0..0, resolve_failures);
+            Ok::<_, String>(plan)
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Model import failed: {e}"), None))?;
+        let plan = match plan {
+            Ok(plan) => plan,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        if plan.instances.is_empty() {
+            let mut message = format!("{} contains no instances that can be created", path.display());
+            if !plan.skipped.is_empty() {
+                message.push_str(&format_failures("Instances skipped", &plan.skipped));
             }
-            _ => {
-                return Ok(CallToolResult::error(vec![Content::text(
-                    "Invalid direction. Use 'ToServer', 'ToClient', or 'ToAllClients'",
-                )]));
+            return Ok(CallToolResult::error(vec![Content::text(message)]));
+        }
+        if plan.instances.len() > MODEL_FILE_MAX_INSTANCES {
+            return Ok(CallToolResult::error(vec![Content::text(format!(
+                "Refusing to import {} instances; the limit is {MODEL_FILE_MAX_INSTANCES}.",
+                plan.instances.len()
+            ))]));
+        }
+
+        let parent = args.parent.unwrap_or_else(|| "Workspace".to_string());
+        let instance_count = plan.instances.len();
+        let built = match self
+            .run_tool_raw(ToolArgumentValues::BuildInstances(BuildInstancesRequest {
+                parent: parent.clone(),
+                instances: plan.instances,
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {
+                parse_plugin_json::<BuiltInstances>(&response)
             }
+            Ok(response) | Err(response) => Err(response),
+        };
+        let built = match built {
+            Ok(built) => built,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
         };
 
-        let code = format!(
-            r#"local path = "{}"
-local parts = string.split(path, ".")
-local current = game
-for _, part in ipairs(parts) do
-    current = current:FindFirstChild(part)
-    if not current then
-        error("Remote not found: " .. path .. " (failed at: " .. part .. ")")
-    end
-end
-local remote = current
-if not remote:IsA("RemoteEvent") then
-    error("Object at " .. path .. " is not a RemoteEvent, it's a " .. remote.ClassName)
-end
-{}"#,
-            args.path, fire_code
+        let mut summary = format!(
+            "[SUCCESS] Imported {instance_count} instance(s) from {} into {parent}: {}",
+            path.display(),
+            built.roots.join(", ")
         );
-
-        self.run_generated_server_code(code).await
+        if !plan.skipped.is_empty() {
+            summary.push_str(&format_failures("Instances skipped", &plan.skipped));
+        }
+        if !plan.unsupported.is_empty() {
+            let unsupported: Vec<String> = plan.unsupported.into_iter().collect();
+            summary.push_str(&format_failures("Properties not applied", &unsupported));
+        }
+        let failures: Vec<String> = plan.failures.into_iter().chain(built.failures).collect();
+        if !failures.is_empty() {
+            summary.push_str(&format_failures("Values that failed to apply", &failures));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
     }
 
     #[tool(
-        description = "Validates UI elements for common layout issues. Checks for: overlapping elements, offscreen elements, pixel positioning (Offset without Scale), missing UISizeConstraint, and AnchorPoint/Position mismatches. Returns a JSON report of issues found."
+        description = "Syncs a Rojo project (default.project.json) into Studio. The project is built with Rojo and diffed against the live DataModel, and only changed scripts, instances and properties are written. With watch, keeps syncing one-way whenever the project's files change."
     )]
-    async fn validate_ui(
+    async fn sync_project(
         &self,
-        Parameters(args): Parameters<ValidateUI>,
+        Parameters(args): Parameters<SyncProject>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::ValidateUI(args))
-            .await
+        let project = PathBuf::from(&args.project_path);
+        let summary = match self.sync_project_once(&project, args.target.as_deref()).await {
+            Ok(summary) => summary,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+        if !args.watch.unwrap_or(false) {
+            return Ok(CallToolResult::success(vec![Content::text(summary)]));
+        }
+
+        let status = Arc::new(std::sync::Mutex::new(WatchStatus::default()));
+        let task = tokio::spawn(self.clone().watch_project(
+            project.clone(),
+            args.target,
+            Arc::clone(&status),
+        ));
+        let previous = self.state.lock().await.project_watch.replace(ProjectWatch {
+            project: project.clone(),
+            task: task.abort_handle(),
+            status,
+        });
+        if let Some(previous) = previous {
+            previous.task.abort();
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{summary}\nWatching {} for changes. Call stop_project_sync to stop.",
+            project.display()
+        ))]))
     }
 
-    #[tool(
-        description = "Creates a responsive UI layout with best-practice container structure. Creates a ScreenGui with positioned containers that include UISizeConstraint and UIListLayout for proper responsive behavior."
-    )]
-    async fn create_responsive_layout(
+    #[tool(description = "Stops the file watcher started by sync_project with watch.")]
+    async fn stop_project_sync(
         &self,
-        Parameters(args): Parameters<CreateResponsiveLayout>,
+        Parameters(_args): Parameters<StopProjectSync>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::CreateResponsiveLayout(args))
-            .await
+        let Some(watch) = self.state.lock().await.project_watch.take() else {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "No project sync is running",
+            )]));
+        };
+        watch.task.abort();
+
+        let status = watch.status.lock().unwrap();
+        let mut summary = format!(
+            "[SUCCESS] Stopped watching {} after {} sync(s)",
+            watch.project.display(),
+            status.syncs
+        );
+        if let Some(last) = &status.last_result {
+            summary.push_str(&format!("\nLast sync: {last}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
     }
 
     #[tool(
-        description = "Calculates what UI layout would look like at a specific viewport size (e.g., mobile device). Returns JSON with element positions and sizes, identifying elements that would be offscreen or overlapping. Useful for checking mobile layouts without the Device Emulator."
+        description = "Exports every Script, LocalScript and ModuleScript in the place to a Rojo directory layout (.server.luau, .client.luau, .luau and init files) with a matching default.project.json, e.g. for code review. Re-running only rewrites files whose contents changed and removes the files an earlier export wrote for scripts that no longer exist; other files are never removed."
     )]
-    async fn preview_layout(
+    async fn export_scripts(
         &self,
-        Parameters(args): Parameters<PreviewLayout>,
+        Parameters(args): Parameters<ExportScripts>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::PreviewLayout(args))
-            .await
+        let output_dir = PathBuf::from(&args.output_dir);
+        if !output_dir.is_absolute() {
+            return Ok(CallToolResult::error(vec![Content::text(
+                "output_dir must be an absolute path",
+            )]));
+        }
+
+        let collected = match self.collect_scripts(args.paths.unwrap_or_default(), false).await {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let project_name = args
+            .project_name
+            .unwrap_or_else(|| collected.place_name.clone());
+        let write_dir = output_dir.clone();
+        let exported = tokio::task::spawn_blocking(move || {
+            let layout = script_layout(&collected, &project_name)?;
+            let report = write_layout(&write_dir, &layout)?;
+            Ok::<_, String>((layout, report))
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Script export failed: {e}"), None))?;
+        let (layout, report) = match exported {
+            Ok(result) => result,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
+
+        let mut summary = format!(
+            "[SUCCESS] Exported {} script(s) to {}: {} file(s) written, {} unchanged, {} removed\nProject file: {}",
+            layout.script_count,
+            output_dir.display(),
+            report.written.len(),
+            report.unchanged,
+            report.removed.len(),
+            output_dir.join(PROJECT_FILE).display()
+        );
+        let list = |paths: &[PathBuf]| -> Vec<String> {
+            paths.iter().map(|p| p.display().to_string()).collect()
+        };
+        if !report.written.is_empty() {
+            summary.push_str(&format_failures("Written", &list(&report.written)));
+        }
+        if !report.removed.is_empty() {
+            summary.push_str(&format_failures("Removed", &list(&report.removed)));
+        }
+        if !layout.skipped.is_empty() {
+            summary.push_str(&format_failures("Instances skipped", &layout.skipped));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
     }
 
     #[tool(
-        description = "Searches the Roblox marketplace for assets matching a query. Returns a ranked list of assets with quality scores based on favorites, creator verification, and recency. Assets are sorted by quality score to help you choose the best option."
+        description = "Writes a Rojo sourcemap.json built from the live DataModel, so luau-lsp can resolve require paths and autocomplete instances in places that aren't managed by Rojo. Script file paths match the layout export_scripts writes to the same directory."
     )]
-    async fn search_assets(
+    async fn generate_sourcemap(
         &self,
-        Parameters(args): Parameters<SearchAssets>,
+        Parameters(args): Parameters<GenerateSourcemap>,
     ) -> Result<CallToolResult, ErrorData> {
-        // Step 1: Get basic search results from plugin
-        let plugin_result = self
-            .run_tool_raw(ToolArgumentValues::SearchAssets(args.clone()))
-            .await
-            .map_err(|e| ErrorData::internal_error(format!("Plugin search failed: {}", e), None))?;
-
-        // Step 2: Parse the JSON response from plugin
-        let plugin_response: PluginSearchResponse = serde_json::from_str(&plugin_result)
-            .map_err(|e| {
-                ErrorData::internal_error(format!("Failed to parse plugin response: {}", e), None)
-            })?;
-
-        if !plugin_response.success {
+        let output_dir = PathBuf::from(&args.output_dir);
+        if !output_dir.is_absolute() {
             return Ok(CallToolResult::error(vec![Content::text(
-                plugin_response
-                    .error
-                    .unwrap_or_else(|| "Unknown search error".to_string()),
+                "output_dir must be an absolute path",
             )]));
         }
 
-        if plugin_response.assets.is_empty() {
-            return Ok(CallToolResult::success(vec![Content::text(format!(
-                "No assets found for query: '{}'",
-                plugin_response.query
-            ))]));
-        }
-
-        // Step 3: Enrich assets with web API data
-        let enriched = self.enrich_assets(plugin_response.assets).await;
-
-        // Step 4: Format results
-        let mut lines = vec![
-            format!(
-                "Found {} assets for '{}', ranked by quality:\n",
-                enriched.len(),
-                plugin_response.query
-            ),
-        ];
+        let collected = match self
+            .collect_scripts(
+                args.paths.unwrap_or_default(),
+                args.include_non_scripts.unwrap_or(false),
+            )
+            .await
+        {
+            Ok(collected) => collected,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
 
-        for (i, asset) in enriched.iter().enumerate() {
-            let verified = if asset.creator_verified { " ✓" } else { "" };
-            let desc_preview = asset
-                .description
-                .as_ref()
-                .map(|d| {
-                    let clean = d.lines().next().unwrap_or("").trim();
-                    if clean.len() > 60 {
-                        format!("{}...", &clean[..57])
-                    } else {
-                        clean.to_string()
-                    }
-                })
-                .unwrap_or_default();
+        let project_name = args
+            .project_name
+            .unwrap_or_else(|| collected.place_name.clone());
+        let write_dir = output_dir.clone();
+        let generated = tokio::task::spawn_blocking(move || {
+            write_sourcemap(&write_dir, &collected, &project_name)
+                .map(|report| (collected.nodes.len(), report))
+        })
+        .await
+        .map_err(|e| ErrorData::internal_error(format!("Sourcemap generation failed: {e}"), None))?;
+        let (instance_count, SourcemapReport { layout, written, missing }) = match generated {
+            Ok(result) => result,
+            Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
+        };
 
-            lines.push(format!(
-                "{}. **{}** (ID: {})\n   Creator: {}{} | ⭐ {} favorites | Score: {:.1}\n   {}",
-                i + 1,
-                asset.name,
-                asset.asset_id,
-                asset.creator,
-                verified,
-                asset.favorites,
-                asset.quality_score,
-                desc_preview
+        let mut summary = format!(
+            "[SUCCESS] {} {} with {instance_count} instance(s) and {} script(s)",
+            if written { "Wrote" } else { "Unchanged:" },
+            output_dir.join(SOURCEMAP_FILE).display(),
+            layout.script_count
+        );
+        if missing > 0 {
+            summary.push_str(&format!(
+                "\n{missing} mapped file(s) don't exist yet. Run export_scripts with the same output_dir to write them."
             ));
         }
-
-        lines.push(String::new());
-        lines.push("Use `preview_asset` with an asset_id to see what it looks like.".to_string());
-
-        Ok(CallToolResult::success(vec![Content::text(lines.join("\n"))]))
+        if !layout.skipped.is_empty() {
+            summary.push_str(&format_failures("Scripts without files", &layout.skipped));
+        }
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
     }
 
-    /// Enrich assets with metadata from Roblox web APIs
-    async fn enrich_assets(&self, assets: Vec<PluginAssetResult>) -> Vec<EnrichedAsset> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .unwrap_or_default();
-
-        let mut enriched: Vec<EnrichedAsset> = Vec::with_capacity(assets.len());
-
-        // Fetch metadata for each asset concurrently
-        let futures: Vec<_> = assets
-            .into_iter()
-            .map(|asset| {
-                let client = client.clone();
-                async move {
-                    let (economy_data, favorites) = tokio::join!(
-                        Self::fetch_economy_details(&client, asset.asset_id),
-                        Self::fetch_favorites_count(&client, asset.asset_id)
-                    );
-
-                    let (description, creator_verified, sales, created, updated) =
-                        match economy_data {
-                            Ok(details) => (
-                                details.description,
-                                details
-                                    .creator
-                                    .as_ref()
-                                    .and_then(|c| c.has_verified_badge)
-                                    .unwrap_or(false),
-                                details.sales.unwrap_or(0),
-                                details.created,
-                                details.updated,
-                            ),
-                            Err(_) => (None, false, 0, None, None),
-                        };
-
-                    let favorites = favorites.unwrap_or(0);
-
-                    // Calculate quality score
-                    let quality_score =
-                        Self::calculate_quality_score(favorites, creator_verified, &updated, &description);
-
-                    EnrichedAsset {
-                        asset_id: asset.asset_id,
-                        name: asset.name,
-                        creator: asset.creator,
-                        creator_verified,
-                        description,
-                        favorites,
-                        sales,
-                        created,
-                        updated,
-                        quality_score,
-                    }
-                }
-            })
-            .collect();
-
-        let results = futures::future::join_all(futures).await;
-        enriched.extend(results);
-
-        // Sort by quality score (highest first)
-        enriched.sort_by(|a, b| {
-            b.quality_score
-                .partial_cmp(&a.quality_score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        enriched
-    }
+    /// Build the project, send it to the plugin and summarize what changed
+    async fn sync_project_once(
+        &self,
+        project: &Path,
+        target: Option<&str>,
+    ) -> std::result::Result<String, String> {
+        let project_path = project.to_path_buf();
+        let (layout, sync) = tokio::task::spawn_blocking(move || {
+            let layout = ProjectLayout::load(&project_path)?;
+            let dom = layout.build()?;
+            let sync = layout.plan(&dom)?;
+            Ok::<_, String>((layout, sync))
+        })
+        .await
+        .map_err(|e| format!("Project build failed: {e}"))??;
+
+        let parent = match (layout.is_place, target) {
+            (true, _) => "game".to_string(),
+            (false, Some(target)) => target.to_string(),
+            (false, None) => {
+                return Err(format!(
+                    "{} is a model project; pass target to choose where its root goes",
+                    layout.file.display()
+                ))
+            }
+        };
+        let plan = sync.plan;
+        if plan.instances.len() > MODEL_FILE_MAX_INSTANCES {
+            return Err(format!(
+                "Refusing to sync {} instances; the limit is {MODEL_FILE_MAX_INSTANCES}.",
+                plan.instances.len()
+            ));
+        }
 
-    /// Fetch asset details from economy.roblox.com
-    async fn fetch_economy_details(
-        client: &reqwest::Client,
-        asset_id: u64,
-    ) -> std::result::Result<EconomyAssetDetails, reqwest::Error> {
-        let url = format!("https://economy.roblox.com/v2/assets/{}/details", asset_id);
-        client.get(&url).send().await?.json().await
-    }
+        let synced = match self
+            .run_tool_raw(ToolArgumentValues::SyncInstances(SyncInstancesRequest {
+                parent: parent.clone(),
+                instances: plan.instances,
+                owned: sync.owned,
+            }))
+            .await
+        {
+            Ok(response) if response.starts_with("[SUCCESS]") => {
+                parse_plugin_json::<SyncedInstances>(&response)?
+            }
+            Ok(response) | Err(response) => return Err(response),
+        };
 
-    /// Fetch favorites count from catalog.roblox.com
-    async fn fetch_favorites_count(
-        client: &reqwest::Client,
-        asset_id: u64,
-    ) -> std::result::Result<u64, reqwest::Error> {
-        let url = format!(
-            "https://catalog.roblox.com/v1/favorites/assets/{}/count",
-            asset_id
+        let mut summary = format!(
+            "[SUCCESS] Synced {} into {parent}: {} created, {} updated, {} deleted, {} unchanged",
+            layout.file.display(),
+            synced.created,
+            synced.updated,
+            synced.deleted,
+            synced.unchanged
         );
-        client.get(&url).send().await?.json().await
-    }
-
-    /// Calculate quality score based on various factors
-    fn calculate_quality_score(
-        favorites: u64,
-        creator_verified: bool,
-        updated: &Option<String>,
-        description: &Option<String>,
-    ) -> f64 {
-        let mut score = 0.0;
-
-        // Favorites: logarithmic scale (diminishing returns)
-        // 100 favorites = ~46 points, 1000 = ~69, 10000 = ~92
-        if favorites > 0 {
-            score += (favorites as f64).ln() * 10.0;
+        if !plan.skipped.is_empty() {
+            summary.push_str(&format_failures("Instances skipped", &plan.skipped));
         }
-
-        // Creator verified badge: +20 points
-        if creator_verified {
-            score += 20.0;
+        if !synced.kept.is_empty() {
+            summary.push_str(&format_failures(
+                "Kept though not in the project (MCP helpers and non-Archivable instances)",
+                &synced.kept,
+            ));
+        }
+        if !plan.unsupported.is_empty() {
+            let unsupported: Vec<String> = plan.unsupported.into_iter().collect();
+            summary.push_str(&format_failures("Properties not synced", &unsupported));
         }
+        let failures: Vec<String> = plan.failures.into_iter().chain(synced.failures).collect();
+        if !failures.is_empty() {
+            summary.push_str(&format_failures("Values that failed to apply", &failures));
+        }
+        Ok(summary)
+    }
 
-        // Recency bonus: up to 15 points for recently updated assets
-        if let Some(updated_str) = updated {
-            if let Ok(updated_date) = DateTime::parse_from_rfc3339(updated_str) {
-                let now = Utc::now();
-                let days_ago = (now - updated_date.with_timezone(&Utc)).num_days();
-                // Full 15 points if updated within 30 days, decreasing over 2 years
-                let recency_score = 15.0 * (1.0 - (days_ago as f64 / 730.0).min(1.0));
-                score += recency_score.max(0.0);
+    /// Sync again whenever the project's files change, until the task is aborted
+    async fn watch_project(
+        self,
+        project: PathBuf,
+        target: Option<String>,
+        status: Arc<std::sync::Mutex<WatchStatus>>,
+    ) {
+        use notify::Watcher;
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok_and(|event| !event.kind.is_access()) {
+                let _ = tx.send(());
             }
-        }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                tracing::error!("Failed to start the project watcher: {e}");
+                return;
+            }
+        };
 
-        // Description quality: up to 10 points
-        if let Some(desc) = description {
-            let desc_len = desc.len();
-            // 5 points for having a description, up to 5 more for length
-            if desc_len > 10 {
-                score += 5.0 + (desc_len as f64 / 200.0).min(1.0) * 5.0;
+        let mut watched: Vec<(PathBuf, notify::RecursiveMode)> = Vec::new();
+        loop {
+            // The project file can add or remove $path entries, so refresh what's watched
+            if let Ok(layout) = ProjectLayout::load(&project) {
+                let folder = layout.file.parent().unwrap_or(Path::new(".")).to_path_buf();
+                let wanted: Vec<(PathBuf, notify::RecursiveMode)> =
+                    std::iter::once((folder, notify::RecursiveMode::NonRecursive))
+                        .chain(
+                            layout
+                                .watch_paths
+                                .into_iter()
+                                .map(|path| (path, notify::RecursiveMode::Recursive)),
+                        )
+                        .collect();
+                if wanted != watched {
+                    for (path, _) in &watched {
+                        let _ = watcher.unwatch(path);
+                    }
+                    for (path, mode) in &wanted {
+                        if let Err(e) = watcher.watch(path, *mode) {
+                            tracing::warn!("Failed to watch {}: {e}", path.display());
+                        }
+                    }
+                    watched = wanted;
+                }
             }
-        }
 
-        score
-    }
+            if rx.recv().await.is_none() {
+                return;
+            }
+            while let Ok(Some(())) = timeout(PROJECT_WATCH_DEBOUNCE, rx.recv()).await {}
 
-    #[tool(
-        description = "Previews an asset by temporarily inserting it and taking a screenshot. Use this to see what an asset looks like before committing to it. Set keep=true to keep the asset in workspace, or leave as default to remove after screenshot."
-    )]
-    async fn preview_asset(
-        &self,
-        Parameters(args): Parameters<PreviewAsset>,
-    ) -> Result<CallToolResult, ErrorData> {
-        self.generic_tool_run(ToolArgumentValues::PreviewAsset(args))
-            .await
+            let result = match self.sync_project_once(&project, target.as_deref()).await {
+                Ok(summary) => summary,
+                Err(e) => format!("[ERROR] {e}"),
+            };
+            tracing::info!("Project sync: {result}");
+            let mut status = status.lock().unwrap();
+            status.syncs += 1;
+            status.last_result = Some(result);
+        }
     }
 
     async fn generic_tool_run(
         &self,
         args: ToolArgumentValues,
     ) -> Result<CallToolResult, ErrorData> {
+        let tool = args.variant_name();
         let (command, id) = ToolArguments::new(args);
         tracing::debug!("Running command: {:?}", command);
-        let (tx, mut rx) = mpsc::unbounded_channel::<Result<String>>();
+        let (tx, mut rx) = mpsc::unbounded_channel::<Result<RunCommandResponse>>();
         let trigger = {
             let mut state = self.state.lock().await;
             state.process_queue.push_back(command);
@@ -1642,13 +5341,18 @@ end
         }
         tracing::debug!("Sending to MCP: {result:?}");
         match result {
-            Ok(result) => Ok(CallToolResult::success(vec![Content::text(result)])),
+            Ok(result) => {
+                self.record_change(id, tool, result.recording).await;
+                Ok(CallToolResult::success(vec![Content::text(result.response)]))
+            }
             Err(err) => Ok(CallToolResult::error(vec![Content::text(err.to_string())])),
         }
     }
 
     /// Process an image: resize to fit within max dimensions and encode as JPEG base64
     fn process_screenshot(img: image::DynamicImage) -> Result<String, Error> {
+        use base64::Engine;
+
         // Resize to max dimensions while maintaining aspect ratio
         let resized = img.resize(
             SCREENSHOT_MAX_DIMENSION,
@@ -1883,7 +5587,7 @@ pub async fn response_handler(
         .output_map
         .remove(&payload.id)
         .ok_or_eyre("Unknown ID")?;
-    Ok(tx.send(Ok(payload.response))?)
+    Ok(tx.send(Ok(payload))?)
 }
 
 pub async fn proxy_handler(
@@ -1904,7 +5608,7 @@ pub async fn proxy_handler(
         state.output_map.remove_entry(&id);
     }
     tracing::debug!("Sending back to dud: {response:?}");
-    Ok(Json(RunCommandResponse { response, id }))
+    Ok(Json(response))
 }
 
 pub async fn dud_proxy_loop(state: PackedState, exit: Receiver<()>) {
@@ -1928,11 +5632,7 @@ pub async fn dud_proxy_loop(state: PackedState, exit: Receiver<()>) {
                         .remove(&entry.id.unwrap())
                         .unwrap()
                 };
-                let res = res
-                    .json::<RunCommandResponse>()
-                    .await
-                    .map(|r| r.response)
-                    .map_err(Into::into);
+                let res = res.json::<RunCommandResponse>().await.map_err(Into::into);
                 tx.send(res).unwrap();
             } else {
                 tracing::error!("Failed to proxy: {res:?}");
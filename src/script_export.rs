@@ -0,0 +1,831 @@
+//! Script export to a Rojo directory layout for the export_scripts tool.
+//!
+//! The plugin sends every script along with its ancestors up to the service. Each service
+//! becomes a `$path` node in default.project.json, and instances below it become files
+//! and directories following Rojo's conventions: `.server.luau`, `.client.luau` and
+//! `.luau` files, `init` files for scripts with children, and `.meta.json` files for what
+//! the file name can't say (class names, RunContext, disabled scripts). Instances that
+//! have children which aren't exported are marked `ignoreUnknownInstances` so syncing
+//! the layout back never deletes them.
+//!
+//! The same layout maps instances to files for the generate_sourcemap tool, which writes a
+//! Rojo `sourcemap.json` for luau-lsp without the place being managed by Rojo.
+//!
+//! The `export-scripts` and `sourcemap` subcommands do the same without Studio, collecting
+//! the scripts from a place file or Rojo place project the way the plugin collects them
+//! from the live DataModel.
+
+use crate::luau_local::load_dom;
+use rbx_dom_weak::types::{Ref, Variant};
+use rbx_dom_weak::{ustr, Instance, WeakDom};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_FILE: &str = "default.project.json";
+pub const SOURCEMAP_FILE: &str = "sourcemap.json";
+const SOURCE_DIR: &str = "src";
+
+/// Lists the files an export wrote, so the next export only removes its own files
+const MANIFEST_FILE: &str = ".export-manifest.json";
+
+/// Characters that aren't allowed in file names on at least one platform
+const RESERVED_CHARACTERS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// File names Windows refuses regardless of extension
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Services that hold place content, as the plugin's `CollectScripts` exports them
+const EXPORTED_SERVICES: &[&str] = &[
+    "Workspace",
+    "Lighting",
+    "MaterialService",
+    "ReplicatedFirst",
+    "ReplicatedStorage",
+    "ServerScriptService",
+    "ServerStorage",
+    "StarterGui",
+    "StarterPack",
+    "StarterPlayer",
+    "SoundService",
+    "Teams",
+    "TextChatService",
+    "Chat",
+    "LocalizationService",
+];
+
+const SCRIPT_CLASSES: &[&str] = &["Script", "LocalScript", "ModuleScript"];
+
+/// Helpers the MCP server installs, which aren't place code even when a place was saved
+/// with them (the plugin's HelperNames)
+const HELPER_NAMES: &[&str] = &[
+    "MCPInputPoller",
+    "MCPInputHandler",
+    "MCPMovementHandler",
+    "MCPClickSupport",
+    "MCPInputCommand",
+    "MCPInputReceived",
+    "MCPGuiClicked",
+    "MCPServerCodeRunner",
+    "MCPCoverage",
+    "MCPProbes",
+];
+
+/// An exported instance as the plugin's `CollectScripts` returns it, in pre-order
+#[derive(Debug, Deserialize)]
+pub struct ScriptNode {
+    pub name: String,
+    pub class_name: String,
+    /// Zero-based index of the parent; None for services
+    pub parent: Option<usize>,
+    /// Source of scripts; None for their ancestors
+    pub source: Option<String>,
+    pub run_context: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+    /// Whether the instance has children that aren't exported
+    #[serde(default)]
+    pub partial: bool,
+    /// Listed for the sourcemap only: neither a script nor an ancestor of one
+    #[serde(default)]
+    pub unexported: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CollectedScripts {
+    pub place_name: String,
+    pub nodes: Vec<ScriptNode>,
+}
+
+/// The files of an export, relative to the output directory
+#[derive(Debug, Default)]
+pub struct ScriptLayout {
+    pub files: BTreeMap<PathBuf, String>,
+    /// The files each node was written to, by node index
+    pub node_files: Vec<Vec<PathBuf>>,
+    pub script_count: usize,
+    /// Instances that can't be written under their name, with the reason
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub written: Vec<PathBuf>,
+    pub unchanged: usize,
+    pub removed: Vec<PathBuf>,
+}
+
+/// Children of each node and the service nodes, checking that parents come first
+fn child_lists(nodes: &[ScriptNode]) -> Result<(Vec<Vec<usize>>, Vec<usize>), String> {
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut services = Vec::new();
+    for (index, node) in nodes.iter().enumerate() {
+        match node.parent {
+            Some(parent) if parent < index => children[parent].push(index),
+            Some(parent) => {
+                return Err(format!(
+                    "Invalid plugin response: {} is listed before its parent {parent}",
+                    node.name
+                ))
+            }
+            None => services.push(index),
+        }
+    }
+    Ok((children, services))
+}
+
+/// Collect the scripts of a place file or built Rojo project, and their ancestors, as
+/// `CollectScripts` does in Studio. With `include_non_scripts`, every other instance in
+/// the exported services is listed too, marked unexported.
+pub fn collect_dom(dom: &WeakDom, place_name: &str, include_non_scripts: bool) -> CollectedScripts {
+    let mut included = HashSet::new();
+    let mut listed = HashSet::new();
+    let mut pending: Vec<Ref> = dom
+        .root()
+        .children()
+        .iter()
+        .copied()
+        .filter(|&referent| {
+            dom.get_by_ref(referent)
+                .is_some_and(|service| EXPORTED_SERVICES.contains(&service.class.as_str()))
+        })
+        .collect();
+    while let Some(referent) = pending.pop() {
+        let Some(instance) = dom.get_by_ref(referent) else {
+            continue;
+        };
+        // Helpers are left out with everything below them
+        if HELPER_NAMES.contains(&instance.name.as_str()) {
+            continue;
+        }
+        if SCRIPT_CLASSES.contains(&instance.class.as_str()) {
+            mark_ancestors(dom, referent, &mut included);
+        }
+        if include_non_scripts {
+            mark_ancestors(dom, referent, &mut listed);
+        }
+        pending.extend(instance.children().iter().copied());
+    }
+
+    fn visit(
+        dom: &WeakDom,
+        instance: &Instance,
+        parent: Option<usize>,
+        included: &HashSet<Ref>,
+        listed: &HashSet<Ref>,
+        nodes: &mut Vec<ScriptNode>,
+    ) {
+        let index = nodes.len();
+        let is_script = included.contains(&instance.referent())
+            && SCRIPT_CLASSES.contains(&instance.class.as_str());
+        let property = |name: &str| instance.properties.get(&ustr(name));
+        nodes.push(ScriptNode {
+            name: instance.name.to_string(),
+            class_name: instance.class.to_string(),
+            parent,
+            source: match property("Source") {
+                Some(Variant::String(source)) if is_script => Some(source.clone()),
+                _ if is_script => Some(String::new()),
+                _ => None,
+            },
+            run_context: match property("RunContext") {
+                Some(Variant::Enum(item)) if is_script && instance.class == "Script" => {
+                    run_context_name(item.to_u32())
+                }
+                _ if is_script && instance.class == "Script" => Some("Legacy".to_string()),
+                _ => None,
+            },
+            // Older files only store Disabled
+            disabled: is_script
+                && instance.class != "ModuleScript"
+                && (matches!(property("Enabled"), Some(Variant::Bool(false)))
+                    || matches!(property("Disabled"), Some(Variant::Bool(true)))),
+            partial: false,
+            unexported: !included.contains(&instance.referent()),
+        });
+        for &child in instance.children() {
+            if !included.contains(&child) {
+                nodes[index].partial = true;
+            }
+            if included.contains(&child) || listed.contains(&child) {
+                if let Some(child) = dom.get_by_ref(child) {
+                    visit(dom, child, Some(index), included, listed, nodes);
+                }
+            }
+        }
+    }
+
+    let mut nodes = Vec::new();
+    for &service in dom.root().children() {
+        if included.contains(&service) || listed.contains(&service) {
+            if let Some(service) = dom.get_by_ref(service) {
+                visit(dom, service, None, &included, &listed, &mut nodes);
+            }
+        }
+    }
+    CollectedScripts {
+        place_name: place_name.to_string(),
+        nodes,
+    }
+}
+
+/// Mark an instance and its ancestors below the DataModel
+fn mark_ancestors(dom: &WeakDom, referent: Ref, set: &mut HashSet<Ref>) {
+    let mut current = referent;
+    while current != dom.root_ref() && set.insert(current) {
+        match dom.get_by_ref(current) {
+            Some(instance) => current = instance.parent(),
+            None => break,
+        }
+    }
+}
+
+fn run_context_name(value: u32) -> Option<String> {
+    let database = rbx_reflection_database::get().ok()?;
+    database
+        .enums
+        .get("RunContext")?
+        .items
+        .iter()
+        .find(|(_, item)| **item == value)
+        .map(|(name, _)| name.to_string())
+}
+
+/// Lay the collected scripts out as Rojo files plus a default.project.json
+pub fn layout(collected: &CollectedScripts, project_name: &str) -> Result<ScriptLayout, String> {
+    let nodes = &collected.nodes;
+    let (mut children, services) = child_lists(nodes)?;
+    // Nodes listed only for the sourcemap have no files
+    for list in &mut children {
+        list.retain(|&child| !nodes[child].unexported);
+    }
+
+    let mut layout = ScriptLayout {
+        node_files: vec![Vec::new(); nodes.len()],
+        ..Default::default()
+    };
+    let mut tree = Map::new();
+    tree.insert("$className".to_string(), json!("DataModel"));
+    for service in services {
+        let node = &nodes[service];
+        if node.unexported {
+            continue;
+        }
+        let dir = Path::new(SOURCE_DIR).join(&node.name);
+        tree.insert(
+            node.name.clone(),
+            json!({
+                "$className": node.class_name,
+                "$path": path_string(&dir),
+                // Services always hold more than scripts
+                "$ignoreUnknownInstances": true,
+            }),
+        );
+        let mut builder = LayoutBuilder {
+            nodes,
+            children: &children,
+            layout: &mut layout,
+        };
+        builder.add_children(service, &dir, &node.name);
+    }
+
+    let project = json!({ "name": project_name, "tree": tree });
+    let mut contents = serde_json::to_string_pretty(&project)
+        .map_err(|e| format!("Failed to write {PROJECT_FILE}: {e}"))?;
+    contents.push('\n');
+    layout.files.insert(PathBuf::from(PROJECT_FILE), contents);
+    Ok(layout)
+}
+
+struct LayoutBuilder<'a> {
+    nodes: &'a [ScriptNode],
+    children: &'a [Vec<usize>],
+    layout: &'a mut ScriptLayout,
+}
+
+impl LayoutBuilder<'_> {
+    fn add_children(&mut self, parent: usize, dir: &Path, parent_path: &str) {
+        // File names are compared case-insensitively, as macOS and Windows do
+        let mut taken = HashSet::new();
+        for &child in &self.children[parent] {
+            let node = &self.nodes[child];
+            let path = format!("{parent_path}.{}", node.name);
+            if let Err(reason) = check_name(node) {
+                self.skip(child, &path, &reason);
+                continue;
+            }
+            if !taken.insert(node.name.to_lowercase()) {
+                self.skip(
+                    child,
+                    &path,
+                    "another child with the same name (ignoring case) is already exported",
+                );
+                continue;
+            }
+            self.add_node(child, dir, &path);
+        }
+    }
+
+    fn add_node(&mut self, index: usize, dir: &Path, path: &str) {
+        let node = &self.nodes[index];
+        let has_children = !self.children[index].is_empty();
+        let mut meta = Map::new();
+        if node.partial {
+            meta.insert("ignoreUnknownInstances".to_string(), json!(true));
+        }
+
+        let Some(source) = &node.source else {
+            // Directories are Folders unless their init.meta.json says otherwise
+            let child_dir = dir.join(&node.name);
+            if node.class_name != "Folder" {
+                meta.insert("className".to_string(), json!(node.class_name));
+            }
+            self.add_meta(index, child_dir.join("init.meta.json"), meta);
+            self.add_children(index, &child_dir, path);
+            return;
+        };
+
+        let suffix = script_suffix(&node.class_name);
+        let mut properties = Map::new();
+        if let Some(run_context) = &node.run_context {
+            if run_context != "Legacy" {
+                properties.insert("RunContext".to_string(), json!(run_context));
+            }
+        }
+        if node.disabled {
+            // Enabled isn't serialized, so Rojo only keeps Disabled
+            properties.insert("Disabled".to_string(), json!(true));
+        }
+        if !properties.is_empty() {
+            meta.insert("properties".to_string(), Value::Object(properties));
+        }
+
+        self.layout.script_count += 1;
+        if has_children {
+            let child_dir = dir.join(&node.name);
+            self.add_file(
+                index,
+                child_dir.join(format!("init{suffix}")),
+                source.clone(),
+            );
+            self.add_meta(index, child_dir.join("init.meta.json"), meta);
+            self.add_children(index, &child_dir, path);
+        } else {
+            self.add_file(
+                index,
+                dir.join(format!("{}{suffix}", node.name)),
+                source.clone(),
+            );
+            self.add_meta(index, dir.join(format!("{}.meta.json", node.name)), meta);
+        }
+    }
+
+    fn add_file(&mut self, index: usize, file: PathBuf, contents: String) {
+        self.layout.node_files[index].push(file.clone());
+        self.layout.files.insert(file, contents);
+    }
+
+    fn add_meta(&mut self, index: usize, file: PathBuf, meta: Map<String, Value>) {
+        if meta.is_empty() {
+            return;
+        }
+        if let Ok(mut contents) = serde_json::to_string_pretty(&Value::Object(meta)) {
+            contents.push('\n');
+            self.add_file(index, file, contents);
+        }
+    }
+
+    fn skip(&mut self, index: usize, path: &str, reason: &str) {
+        let scripts = self.count_scripts(index);
+        self.layout.skipped.push(format!(
+            "{path}: {reason} (skipped with {scripts} script(s))"
+        ));
+    }
+
+    fn count_scripts(&self, index: usize) -> usize {
+        let own = usize::from(self.nodes[index].source.is_some());
+        own + self.children[index]
+            .iter()
+            .map(|&child| self.count_scripts(child))
+            .sum::<usize>()
+    }
+}
+
+fn script_suffix(class_name: &str) -> &'static str {
+    match class_name {
+        "Script" => ".server.luau",
+        "LocalScript" => ".client.luau",
+        _ => ".luau",
+    }
+}
+
+/// Check that an instance name survives the trip to a file name and back through Rojo
+fn check_name(node: &ScriptNode) -> Result<(), String> {
+    let name = node.name.as_str();
+    if name.is_empty() || name == "." || name == ".." {
+        return Err("name can't be used as a file name".to_string());
+    }
+    if name
+        .chars()
+        .any(|c| c.is_control() || RESERVED_CHARACTERS.contains(&c))
+    {
+        return Err("name contains characters that aren't allowed in file names".to_string());
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Err("name ends with a dot or space, which Windows strips".to_string());
+    }
+    let lower = name.to_lowercase();
+    let stem = lower.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.contains(&stem) {
+        return Err("name is reserved on Windows".to_string());
+    }
+    if node.source.is_some() {
+        // Rojo would read these back as a different script, or as the parent's init file
+        if lower == "init" || lower.starts_with("init.") {
+            return Err("scripts named init are read back as their parent".to_string());
+        }
+        if lower.ends_with(".server") || lower.ends_with(".client") || lower.ends_with(".meta") {
+            return Err("name ends in a suffix Rojo reads as a script type".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Forward slashes on every platform, as Rojo projects are usually written
+fn path_string(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Build a Rojo sourcemap of the collected tree, pointing at the files `layout` places
+/// each script in. Paths use forward slashes and are relative to the export directory.
+pub fn sourcemap(
+    collected: &CollectedScripts,
+    layout: &ScriptLayout,
+    project_name: &str,
+) -> Result<Value, String> {
+    let nodes = &collected.nodes;
+    let (children, services) = child_lists(nodes)?;
+
+    fn node_json(
+        index: usize,
+        nodes: &[ScriptNode],
+        children: &[Vec<usize>],
+        layout: &ScriptLayout,
+    ) -> Value {
+        let node = &nodes[index];
+        let mut object = Map::new();
+        object.insert("name".to_string(), json!(node.name));
+        object.insert("className".to_string(), json!(node.class_name));
+        let files = &layout.node_files[index];
+        if !files.is_empty() {
+            let files: Vec<String> = files.iter().map(|f| path_string(f)).collect();
+            object.insert("filePaths".to_string(), json!(files));
+        }
+        let child_nodes: Vec<Value> = children[index]
+            .iter()
+            .map(|&child| node_json(child, nodes, children, layout))
+            .collect();
+        if !child_nodes.is_empty() {
+            object.insert("children".to_string(), Value::Array(child_nodes));
+        }
+        Value::Object(object)
+    }
+
+    let services: Vec<Value> = services
+        .into_iter()
+        .map(|service| node_json(service, nodes, &children, layout))
+        .collect();
+    Ok(json!({
+        "name": project_name,
+        "className": "DataModel",
+        "filePaths": [PROJECT_FILE],
+        "children": services,
+    }))
+}
+
+/// What write_sourcemap did
+pub struct SourcemapReport {
+    pub layout: ScriptLayout,
+    pub written: bool,
+    /// Mapped files that don't exist in the directory yet
+    pub missing: usize,
+}
+
+/// Write `sourcemap.json` to `dir`, unless it already has these contents
+pub fn write_sourcemap(
+    dir: &Path,
+    collected: &CollectedScripts,
+    project_name: &str,
+) -> Result<SourcemapReport, String> {
+    let layout = layout(collected, project_name)?;
+    let map = sourcemap(collected, &layout, project_name)?;
+    let mut contents = serde_json::to_string(&map)
+        .map_err(|e| format!("Failed to write {SOURCEMAP_FILE}: {e}"))?;
+    contents.push('\n');
+    let written = write_if_changed(&dir.join(SOURCEMAP_FILE), &contents)?;
+    // Scripts only resolve once their files exist, so point out any that don't
+    let missing = layout
+        .files
+        .keys()
+        .filter(|file| !dir.join(file).exists())
+        .count();
+    Ok(SourcemapReport {
+        layout,
+        written,
+        missing,
+    })
+}
+
+/// Write the layout under `root`, leaving files whose contents already match alone. Files
+/// the previous export wrote that the layout no longer contains are removed; anything
+/// else in the directory is never touched.
+pub fn write_layout(root: &Path, layout: &ScriptLayout) -> Result<ExportReport, String> {
+    let previous = read_manifest(root)?;
+    let mut report = ExportReport::default();
+    for (relative, contents) in &layout.files {
+        if write_if_changed(&root.join(relative), contents)? {
+            report.written.push(relative.clone());
+        } else {
+            report.unchanged += 1;
+        }
+    }
+
+    let current: BTreeSet<String> = layout.files.keys().map(|p| path_string(p)).collect();
+    for stale in previous.difference(&current) {
+        let relative: PathBuf = stale.split('/').collect();
+        let file = root.join(&relative);
+        match std::fs::remove_file(&file) {
+            Ok(()) => report.removed.push(relative),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to remove {}: {e}", file.display())),
+        }
+        remove_empty_parents(root, &file);
+    }
+
+    let manifest = serde_json::to_string_pretty(&json!({ "files": current }))
+        .map_err(|e| format!("Failed to write {MANIFEST_FILE}: {e}"))?;
+    write_if_changed(&root.join(MANIFEST_FILE), &(manifest + "\n"))?;
+    Ok(report)
+}
+
+/// The files the previous export into `root` wrote, empty if there was none
+fn read_manifest(root: &Path) -> Result<BTreeSet<String>, String> {
+    let file = root.join(MANIFEST_FILE);
+    let contents = match std::fs::read_to_string(&file) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(e) => return Err(format!("Failed to read {}: {e}", file.display())),
+    };
+    let manifest: Value = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse {}: {e}", file.display()))?;
+    Ok(manifest
+        .get("files")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        // Only relative paths inside the export, in case the manifest was edited
+        .filter(|path| !path.split('/').any(|part| part.is_empty() || part == ".."))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Remove the directories above a removed file that are left empty, up to `root`
+fn remove_empty_parents(root: &Path, file: &Path) {
+    let mut dir = file.parent();
+    while let Some(current) = dir {
+        if current == root || std::fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// Write a file unless it already has these contents. Returns whether it was written.
+pub fn write_if_changed(file: &Path, contents: &str) -> Result<bool, String> {
+    if std::fs::read(file).is_ok_and(|existing| existing == contents.as_bytes()) {
+        return Ok(false);
+    }
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    std::fs::write(file, contents)
+        .map_err(|e| format!("Failed to write {}: {e}", file.display()))?;
+    Ok(true)
+}
+
+/// Load a place file or Rojo place project for the subcommands, with the place named
+/// after the file or directory
+fn load_place(source: &Path, include_non_scripts: bool) -> Result<CollectedScripts, String> {
+    let dom = load_dom(source)?;
+    let is_place = dom.root().children().iter().any(|&referent| {
+        dom.get_by_ref(referent)
+            .is_some_and(|service| EXPORTED_SERVICES.contains(&service.class.as_str()))
+    });
+    if !is_place {
+        return Err(format!(
+            "{} has no services; pass a place file or a Rojo place project",
+            source.display()
+        ));
+    }
+    let file_name = source
+        .canonicalize()
+        .unwrap_or_else(|_| source.to_path_buf())
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let place_name = file_name.split('.').next().unwrap_or_default();
+    Ok(collect_dom(&dom, place_name, include_non_scripts))
+}
+
+/// Entry point of the `export-scripts` subcommand
+pub fn run_export_cli(source: &Path, output_dir: &Path, project_name: Option<String>) -> ! {
+    let result = load_place(source, false).and_then(|collected| {
+        let project_name = project_name.unwrap_or_else(|| collected.place_name.clone());
+        let layout = layout(&collected, &project_name)?;
+        let report = write_layout(output_dir, &layout)?;
+        Ok((layout, report))
+    });
+    let (layout, report) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1)
+        }
+    };
+    println!(
+        "Exported {} script(s) to {}: {} file(s) written, {} unchanged, {} removed",
+        layout.script_count,
+        output_dir.display(),
+        report.written.len(),
+        report.unchanged,
+        report.removed.len()
+    );
+    for skipped in &layout.skipped {
+        eprintln!("Skipped {skipped}");
+    }
+    std::process::exit(0)
+}
+
+/// Entry point of the `sourcemap` subcommand
+pub fn run_sourcemap_cli(
+    source: &Path,
+    output_dir: &Path,
+    project_name: Option<String>,
+    include_non_scripts: bool,
+) -> ! {
+    let result = load_place(source, include_non_scripts).and_then(|collected| {
+        let project_name = project_name.unwrap_or_else(|| collected.place_name.clone());
+        let report = write_sourcemap(output_dir, &collected, &project_name)?;
+        Ok((collected.nodes.len(), report))
+    });
+    let (instance_count, report) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1)
+        }
+    };
+    println!(
+        "{} {} with {instance_count} instance(s) and {} script(s)",
+        if report.written {
+            "Wrote"
+        } else {
+            "Unchanged:"
+        },
+        output_dir.join(SOURCEMAP_FILE).display(),
+        report.layout.script_count
+    );
+    if report.missing > 0 {
+        eprintln!(
+            "{} mapped file(s) don't exist yet. Run export-scripts with the same output directory to write them.",
+            report.missing
+        );
+    }
+    for skipped in &report.layout.skipped {
+        eprintln!("No file for {skipped}");
+    }
+    std::process::exit(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(files: &[(&str, &str)]) -> ScriptLayout {
+        ScriptLayout {
+            files: files
+                .iter()
+                .map(|(path, contents)| (path.split('/').collect(), contents.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn collect_dom_matches_the_plugin() {
+        use rbx_dom_weak::InstanceBuilder;
+        let dom = WeakDom::new(
+            InstanceBuilder::new("DataModel")
+                .with_child(
+                    InstanceBuilder::new("ServerScriptService")
+                        .with_child(
+                            InstanceBuilder::new("Script")
+                                .with_name("Main")
+                                .with_property("Source", "print(1)")
+                                .with_property("Enabled", false),
+                        )
+                        .with_child(
+                            InstanceBuilder::new("Script").with_name("MCPServerCodeRunner"),
+                        ),
+                )
+                .with_child(
+                    InstanceBuilder::new("ReplicatedStorage")
+                        .with_child(InstanceBuilder::new("Folder").with_name("Assets"))
+                        .with_child(
+                            InstanceBuilder::new("Folder")
+                                .with_name("Shared")
+                                .with_child(
+                                    InstanceBuilder::new("ModuleScript")
+                                        .with_name("Util")
+                                        .with_property("Source", "return {}"),
+                                ),
+                        ),
+                )
+                .with_child(
+                    InstanceBuilder::new("CoreGui")
+                        .with_child(InstanceBuilder::new("LocalScript").with_name("Engine")),
+                ),
+        );
+
+        let collected = collect_dom(&dom, "Place", false);
+        let names: Vec<_> = collected.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "ServerScriptService",
+                "Main",
+                "ReplicatedStorage",
+                "Shared",
+                "Util"
+            ]
+        );
+        let main = &collected.nodes[1];
+        assert_eq!(main.source.as_deref(), Some("print(1)"));
+        assert_eq!(main.run_context.as_deref(), Some("Legacy"));
+        assert!(main.disabled);
+        // The helper and Assets aren't exported
+        assert!(collected.nodes[0].partial && collected.nodes[2].partial);
+
+        let listed = collect_dom(&dom, "Place", true);
+        let assets = listed.nodes.iter().find(|n| n.name == "Assets").unwrap();
+        assert!(assets.unexported && assets.source.is_none());
+        assert!(!listed.nodes.iter().any(|n| n.name == "MCPServerCodeRunner"));
+    }
+
+    #[test]
+    fn write_layout_only_removes_files_it_wrote() {
+        let root = std::env::temp_dir().join(format!("export-{}", uuid::Uuid::new_v4()));
+        let own = root.join("src/Shared/Hand.luau");
+        std::fs::create_dir_all(own.parent().unwrap()).unwrap();
+        std::fs::write(&own, "return 1").unwrap();
+
+        let first = layout(&[
+            ("default.project.json", "{}"),
+            ("src/Shared/Old.luau", "return 2"),
+            ("src/Gone/Module.luau", "return 3"),
+        ]);
+        let report = write_layout(&root, &first).unwrap();
+        assert_eq!(report.written.len(), 3);
+        assert!(report.removed.is_empty());
+
+        let second = layout(&[
+            ("default.project.json", "{}"),
+            ("src/Shared/New.luau", "return 4"),
+        ]);
+        let report = write_layout(&root, &second).unwrap();
+        assert_eq!(report.unchanged, 1);
+        let mut removed = report.removed.clone();
+        removed.sort();
+        assert_eq!(
+            removed,
+            vec![
+                PathBuf::from("src/Gone/Module.luau"),
+                PathBuf::from("src/Shared/Old.luau")
+            ]
+        );
+        assert!(own.exists());
+        assert!(root.join("src/Shared/New.luau").exists());
+        assert!(!root.join("src/Gone").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+//! Local revision history for scripts the tools overwrite.
+//!
+//! Before write_script, edit_script, format_script or revert_script replaces a script, the
+//! source it had is saved here. Each place gets a directory with an append-only log of
+//! revisions keyed by script path, and sources stored once under their SHA-256, so
+//! history survives server restarts and repeated sources take no extra space.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const LOG_FILE: &str = "revisions.jsonl";
+const SOURCES_DIR: &str = "sources";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub path: String,
+    /// Numbered from 1 for each script path
+    pub revision: usize,
+    /// SHA-256 of the source, naming its file in the store
+    pub hash: String,
+    pub class_name: String,
+    /// The tool that replaced this source
+    pub tool: String,
+    pub saved_at: DateTime<Utc>,
+    pub lines: usize,
+}
+
+pub struct HistoryStore {
+    root: PathBuf,
+    /// Serializes log appends so concurrent writes can't number two revisions the same
+    lock: Mutex<()>,
+}
+
+impl HistoryStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The platform's per-user data directory
+    pub fn default_root() -> PathBuf {
+        let data_dir = if cfg!(target_os = "windows") {
+            std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            std::env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+                })
+        };
+        data_dir
+            .unwrap_or_else(std::env::temp_dir)
+            .join("RobloxStudioMCP")
+            .join("script-history")
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Save a script's source as its next revision. Returns `None` if it is the same as
+    /// the latest revision already saved.
+    pub fn record(
+        &self,
+        place: &str,
+        path: &str,
+        class_name: &str,
+        source: &str,
+        tool: &str,
+    ) -> Result<Option<Revision>, String> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let place_dir = self.root.join(place);
+        let hash = format!("{:x}", Sha256::digest(source.as_bytes()));
+
+        let previous = self.revisions(place, Some(path))?;
+        if previous.last().is_some_and(|latest| latest.hash == hash) {
+            return Ok(None);
+        }
+
+        let sources = place_dir.join(SOURCES_DIR);
+        std::fs::create_dir_all(&sources)
+            .map_err(|e| format!("Failed to create {}: {e}", sources.display()))?;
+        let source_file = sources.join(&hash);
+        if !source_file.is_file() {
+            // Written under a temporary name first so a crash can't leave a truncated source
+            let partial = sources.join(format!("{hash}.partial"));
+            std::fs::write(&partial, source)
+                .and_then(|()| std::fs::rename(&partial, &source_file))
+                .map_err(|e| format!("Failed to write {}: {e}", source_file.display()))?;
+        }
+
+        let revision = Revision {
+            path: path.to_string(),
+            revision: previous.len() + 1,
+            hash,
+            class_name: class_name.to_string(),
+            tool: tool.to_string(),
+            saved_at: Utc::now(),
+            lines: source.lines().count(),
+        };
+        let log = place_dir.join(LOG_FILE);
+        let mut line = serde_json::to_string(&revision)
+            .map_err(|e| format!("Failed to serialize revision: {e}"))?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {e}", log.display()))?;
+        Ok(Some(revision))
+    }
+
+    /// Revisions saved for a place, oldest first, optionally only those of one script
+    pub fn revisions(&self, place: &str, path: Option<&str>) -> Result<Vec<Revision>, String> {
+        let log = self.root.join(place).join(LOG_FILE);
+        let contents = match std::fs::read_to_string(&log) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to read {}: {e}", log.display())),
+        };
+        // A line cut short by a crash is skipped rather than losing the whole history
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Revision>(line).ok())
+            .filter(|revision| path.is_none_or(|path| revision.path == path))
+            .collect())
+    }
+
+    /// One revision of a script and its source
+    pub fn load(
+        &self,
+        place: &str,
+        path: &str,
+        revision: usize,
+    ) -> Result<(Revision, String), String> {
+        let revisions = self.revisions(place, Some(path))?;
+        let Some(found) = revisions.into_iter().find(|r| r.revision == revision) else {
+            return Err(format!(
+                "{path} has no revision {revision}. Use script_history to list its revisions."
+            ));
+        };
+        let file = self.root.join(place).join(SOURCES_DIR).join(&found.hash);
+        let source = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Failed to read {}: {e}", file.display()))?;
+        Ok((found, source))
+    }
+}
+
+/// Directory name for a place: its id once published, otherwise its name, since an
+/// unpublished place has nothing more stable
+pub fn place_key(place_id: u64, place_name: &str) -> String {
+    if place_id != 0 {
+        return format!("place-{place_id}");
+    }
+    let name: String = place_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("unpublished-{name}")
+}
@@ -0,0 +1,342 @@
+//! Patch application for the edit_script tool.
+//!
+//! Edits are applied here against the source `ReadScript` returned, never in the plugin,
+//! so a hunk that doesn't match is rejected before anything is written. Unified diff line
+//! numbers are treated as hints, like `patch` does: a hunk whose lines moved is applied
+//! where its context matches, as long as that place is unambiguous.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// Lines of context around changes in the diffs returned to the caller
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Longest line quoted back in a conflict message
+const MAX_QUOTED_LINE: usize = 120;
+
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone)]
+pub struct SearchReplace {
+    #[schemars(
+        description = "Exact text to find. It must occur exactly once in the script, so include enough surrounding lines to make it unique."
+    )]
+    pub search: String,
+    #[schemars(description = "Text to replace it with")]
+    pub replace: String,
+}
+
+/// Apply search/replace edits in order, each to the result of the previous one
+pub fn apply_replacements(source: &str, edits: &[SearchReplace]) -> Result<String, String> {
+    let mut result = source.to_string();
+    for (number, edit) in edits.iter().enumerate().map(|(i, e)| (i + 1, e)) {
+        if edit.search.is_empty() {
+            return Err(format!("Edit {number}: search text is empty"));
+        }
+        match result.matches(edit.search.as_str()).count() {
+            0 => {
+                return Err(format!(
+                    "Edit {number}: search text not found in the current source:\n{}",
+                    quote(&edit.search)
+                ))
+            }
+            1 => result = result.replacen(edit.search.as_str(), &edit.replace, 1),
+            count => {
+                return Err(format!(
+                    "Edit {number}: search text matches {count} times. Include more surrounding lines so it matches once."
+                ))
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[derive(Debug)]
+struct Hunk {
+    header: String,
+    /// Zero-based index the hunk claims its old lines start at in the original source
+    old_start: Option<usize>,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+}
+
+/// Apply a unified diff (as produced by `diff -u` or `git diff`) to a single script
+pub fn apply_unified_diff(source: &str, diff: &str) -> Result<String, String> {
+    let hunks = parse_hunks(diff)?;
+    if hunks.is_empty() {
+        return Err("The diff has no hunks (lines starting with @@)".to_string());
+    }
+
+    // Diff lines arrive without their line endings, so CRLF sources are matched as LF and
+    // get their CRLF endings back afterwards
+    let crlf = source.contains("\r\n");
+    let normalized = source.replace("\r\n", "\n");
+    let source = normalized.as_str();
+    let ends_with_newline = source.ends_with('\n');
+    let mut lines: Vec<&str> = source.split('\n').collect();
+    if ends_with_newline {
+        lines.pop();
+    }
+
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    // Hunks apply in order, so each one is searched for after the previous one
+    let mut cursor = 0;
+    for (number, hunk) in hunks.iter().enumerate().map(|(i, h)| (i + 1, h)) {
+        let position = locate(&lines, cursor, hunk)
+            .map_err(|e| format!("Hunk {number} ({}) doesn't apply: {e}", hunk.header))?;
+        output.extend(lines[cursor..position].iter().map(|l| l.to_string()));
+        output.extend(hunk.new_lines.iter().cloned());
+        cursor = position + hunk.old_lines.len();
+    }
+    output.extend(lines[cursor..].iter().map(|l| l.to_string()));
+
+    let mut result = output.join("\n");
+    if ends_with_newline && !output.is_empty() {
+        result.push('\n');
+    }
+    if crlf {
+        result = result.replace('\n', "\r\n");
+    }
+    Ok(result)
+}
+
+fn parse_hunks(diff: &str) -> Result<Vec<Hunk>, String> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    // A blank line after the last hunk is trailing whitespace, not an empty context line
+    for line in diff.trim_end_matches(['\n', '\r']).lines() {
+        if line.starts_with("@@") {
+            hunks.push(Hunk {
+                header: line.to_string(),
+                old_start: parse_old_start(line),
+                old_lines: Vec::new(),
+                new_lines: Vec::new(),
+            });
+            continue;
+        }
+        // File headers and anything else before the first hunk are ignored. Later "--- "
+        // lines are removed Luau comments, not headers.
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        match line.chars().next() {
+            Some(' ') => {
+                hunk.old_lines.push(line[1..].to_string());
+                hunk.new_lines.push(line[1..].to_string());
+            }
+            Some('-') => hunk.old_lines.push(line[1..].to_string()),
+            Some('+') => hunk.new_lines.push(line[1..].to_string()),
+            // "\ No newline at end of file": the script keeps its own final newline
+            Some('\\') => {}
+            // Some tools strip the single space from blank context lines
+            None => {
+                hunk.old_lines.push(String::new());
+                hunk.new_lines.push(String::new());
+            }
+            Some(_) => {
+                return Err(format!(
+                    "Unexpected line in hunk {}: {}",
+                    hunk.header,
+                    quote(line)
+                ))
+            }
+        }
+    }
+    Ok(hunks)
+}
+
+/// The index the old lines of a `@@ -12,5 +12,6 @@` header start at, if it has one. An
+/// old range with no lines, like `-5,0`, means the new lines go after that line.
+fn parse_old_start(header: &str) -> Option<usize> {
+    let range = header
+        .trim_start_matches('@')
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix('-'))?;
+    let (start, count) = match range.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (range.parse().ok()?, 1),
+    };
+    Some(if count == 0 {
+        start
+    } else {
+        start.saturating_sub(1)
+    })
+}
+
+/// Find where a hunk's old lines are in the source: at its stated line if they match
+/// there, otherwise at the one other place they do
+fn locate(lines: &[&str], cursor: usize, hunk: &Hunk) -> Result<usize, String> {
+    let old = &hunk.old_lines;
+    let stated = hunk.old_start;
+    if old.is_empty() {
+        return match stated {
+            Some(start) if start.max(cursor) <= lines.len() => Ok(start.max(cursor)),
+            Some(start) => Err(format!(
+                "it adds lines after line {start}, past the end of the script ({} lines)",
+                lines.len()
+            )),
+            None => Err("a hunk that only adds lines needs a line number".to_string()),
+        };
+    }
+
+    let matches_at = |position: usize| {
+        position + old.len() <= lines.len()
+            && old.iter().zip(&lines[position..]).all(|(a, b)| a == b)
+    };
+    if let Some(start) = stated {
+        if start >= cursor && matches_at(start) {
+            return Ok(start);
+        }
+    }
+
+    let candidates: Vec<usize> = (cursor..=lines.len().saturating_sub(old.len()))
+        .filter(|&position| matches_at(position))
+        .collect();
+    match (candidates.as_slice(), stated) {
+        ([], _) => Err(mismatch(lines, stated.unwrap_or(cursor), old)),
+        ([position], _) => Ok(*position),
+        (_, Some(start)) => Err(format!(
+            "its lines don't match at line {} and match {} other places (lines {}); add more context",
+            start + 1,
+            candidates.len(),
+            candidates
+                .iter()
+                .map(|position| (position + 1).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        (_, None) => Err(format!(
+            "its lines match {} places; add a line number or more context",
+            candidates.len()
+        )),
+    }
+}
+
+/// Describe the first line that differs from what the hunk expects
+fn mismatch(lines: &[&str], start: usize, old: &[String]) -> String {
+    for (offset, expected) in old.iter().enumerate() {
+        let line = start + offset;
+        match lines.get(line) {
+            Some(actual) if actual == expected => continue,
+            Some(actual) => {
+                return format!(
+                    "the script has changed. Line {} is {} but the diff expects {}",
+                    line + 1,
+                    quote(actual),
+                    quote(expected)
+                )
+            }
+            None => {
+                return format!(
+                "the script has changed. The diff expects {} past the end of the script ({} lines)",
+                quote(expected),
+                lines.len()
+            )
+            }
+        }
+    }
+    "its lines don't appear anywhere after the previous hunk".to_string()
+}
+
+fn quote(text: &str) -> String {
+    let first_line = text.lines().next().unwrap_or_default();
+    let mut quoted: String = first_line.chars().take(MAX_QUOTED_LINE).collect();
+    if quoted.len() < text.len() {
+        quoted.push_str("...");
+    }
+    format!("`{quoted}`")
+}
+
+/// A unified diff of a script's old and new source, labelled with its instance path
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header(path, path)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "local a = 1\nlocal b = 2\nlocal c = 3\nlocal d = 4\nlocal e = 5\n";
+
+    #[test]
+    fn replaces_lines_at_the_stated_position() {
+        let diff = "--- a\n+++ b\n@@ -2,3 +2,3 @@\n local b = 2\n-local c = 3\n+local c = 30\n local d = 4\n";
+        assert_eq!(
+            apply_unified_diff(SOURCE, diff).unwrap(),
+            "local a = 1\nlocal b = 2\nlocal c = 30\nlocal d = 4\nlocal e = 5\n"
+        );
+    }
+
+    #[test]
+    fn inserts_added_only_hunks_after_their_line() {
+        let after_last =
+            apply_unified_diff(SOURCE, "@@ -5,0 +6,2 @@\n+local f = 6\n+local g = 7\n");
+        assert_eq!(
+            after_last.unwrap(),
+            "local a = 1\nlocal b = 2\nlocal c = 3\nlocal d = 4\nlocal e = 5\nlocal f = 6\nlocal g = 7\n"
+        );
+        let after_second = apply_unified_diff(SOURCE, "@@ -2,0 +3 @@\n+local x = 0\n");
+        assert_eq!(
+            after_second.unwrap(),
+            "local a = 1\nlocal b = 2\nlocal x = 0\nlocal c = 3\nlocal d = 4\nlocal e = 5\n"
+        );
+        let at_start = apply_unified_diff(SOURCE, "@@ -0,0 +1 @@\n+--!strict\n");
+        assert!(at_start.unwrap().starts_with("--!strict\nlocal a = 1\n"));
+        assert!(apply_unified_diff(SOURCE, "@@ -9,0 +10 @@\n+local x = 0\n").is_err());
+    }
+
+    #[test]
+    fn applies_moved_hunks_where_their_context_matches_once() {
+        let diff = "@@ -1,2 +1,2 @@\n local c = 3\n-local d = 4\n+local d = 40\n";
+        assert_eq!(
+            apply_unified_diff(SOURCE, diff).unwrap(),
+            "local a = 1\nlocal b = 2\nlocal c = 3\nlocal d = 40\nlocal e = 5\n"
+        );
+    }
+
+    #[test]
+    fn rejects_ambiguous_and_mismatched_hunks() {
+        let source = "x()\ny()\nx()\ny()\nz()\n";
+        let ambiguous = apply_unified_diff(source, "@@ -5,2 +5,2 @@\n x()\n-y()\n+w()\n");
+        assert!(ambiguous.unwrap_err().contains("match 2 other places"));
+        let unnumbered = apply_unified_diff(source, "@@\n x()\n-y()\n+w()\n");
+        assert!(unnumbered.unwrap_err().contains("match 2 places"));
+        let mismatched = apply_unified_diff(source, "@@ -1,1 +1,1 @@\n-q()\n+w()\n");
+        assert!(mismatched.unwrap_err().contains("Line 1 is `x()`"));
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let source = "local a = 1\r\nlocal b = 2\r\n";
+        let diff = "@@ -2 +2 @@\r\n-local b = 2\r\n+local b = 20\r\n";
+        assert_eq!(
+            apply_unified_diff(source, diff).unwrap(),
+            "local a = 1\r\nlocal b = 20\r\n"
+        );
+    }
+
+    #[test]
+    fn later_hunks_are_searched_after_earlier_ones() {
+        let diff =
+            "@@ -1 +1 @@\n-local a = 1\n+local a = 10\n@@ -5 +5 @@\n-local e = 5\n+local e = 50\n";
+        assert_eq!(
+            apply_unified_diff(SOURCE, diff).unwrap(),
+            "local a = 10\nlocal b = 2\nlocal c = 3\nlocal d = 4\nlocal e = 50\n"
+        );
+    }
+
+    #[test]
+    fn replacements_must_match_once() {
+        let edit = |search: &str, replace: &str| SearchReplace {
+            search: search.to_string(),
+            replace: replace.to_string(),
+        };
+        assert_eq!(
+            apply_replacements(SOURCE, &[edit("b = 2", "b = 3")]).unwrap(),
+            SOURCE.replace("b = 2", "b = 3")
+        );
+        assert!(apply_replacements(SOURCE, &[edit("local", "")]).is_err());
+        assert!(apply_replacements(SOURCE, &[edit("missing", "")]).is_err());
+    }
+}
@@ -0,0 +1,727 @@
+//! Cross-script references for the find_references and rename_symbol tools.
+//!
+//! A symbol is either a member a ModuleScript exports (`Util.formatTime`) or an instance
+//! scripts reach by name (`ReplicatedStorage.Remotes.Damage`). Every script is parsed with
+//! full-moon and its index chains are evaluated with module_graph's resolver, so a
+//! reference is only reported where the value being indexed is known to be that module or
+//! the instance's parent. Uses of the name on values that can't be traced are listed
+//! separately and never renamed.
+
+use crate::module_graph::{Resolver, Target, Tree};
+use crate::script_export::CollectedScripts;
+use full_moon::ast::{
+    Ast, Call, Expression, Field, FunctionArgs, FunctionCall, FunctionDeclaration, Index, LastStmt,
+    LocalAssignment, Prefix, Suffix, TableConstructor, VarExpression,
+};
+use full_moon::tokenizer::{StringLiteralQuoteType, TokenReference, TokenType};
+use full_moon::visitors::Visitor;
+use full_moon::LuaVersion;
+use std::collections::BTreeMap;
+
+/// Luau keywords, which can't be used as a name after a dot
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in", "local",
+    "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Symbol {
+    Export { module: usize, name: String },
+    Instance(usize),
+}
+
+/// How a reference is written, which decides how it can be renamed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Site {
+    /// `.Name`, which becomes `["New Name"]` if the new name isn't an identifier
+    Dot { dot: usize },
+    /// `Name = ...` in a table constructor, which becomes `["New Name"] = ...` likewise
+    Key,
+    /// A method or function declaration name, which has to stay an identifier
+    Identifier,
+    /// The contents of a quoted string
+    String,
+    /// One segment of a require-by-string path
+    PathSegment,
+}
+
+/// How a name is used on the value before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Access {
+    /// `.Name`, `["Name"]`, a declaration or a table key
+    Member,
+    /// `:Name()`
+    Method,
+    /// `:WaitForChild("Name")`, `:FindFirstChild("Name")` or a string require segment
+    Child,
+}
+
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub script: String,
+    pub line: usize,
+    pub column: usize,
+    /// The line the reference is on, trimmed
+    pub context: String,
+    script_index: usize,
+    start: usize,
+    end: usize,
+    site: Site,
+}
+
+#[derive(Debug, Default)]
+pub struct References {
+    /// What was searched for, e.g. "formatTime exported by ReplicatedStorage.Util"
+    pub symbol: String,
+    /// The symbol's current name
+    pub name: String,
+    pub found: Vec<Reference>,
+    /// Uses of the name on values that couldn't be traced to the symbol, in script order
+    pub untraced: Vec<Reference>,
+    /// Scripts that don't fully parse; references after the error may be missing
+    pub syntax_errors: Vec<String>,
+    /// The instance to rename along with the references, for an instance symbol
+    pub instance: Option<String>,
+}
+
+impl References {
+    pub fn script_count(&self) -> usize {
+        let mut scripts: Vec<usize> = self.found.iter().map(|r| r.script_index).collect();
+        scripts.dedup();
+        scripts.len()
+    }
+
+    /// References grouped by script, one line each
+    pub fn listing(&self) -> String {
+        let mut output = String::new();
+        let mut current = None;
+        for reference in &self.found {
+            if current != Some(reference.script_index) {
+                current = Some(reference.script_index);
+                output.push_str(&format!("\n{}\n", reference.script));
+            }
+            output.push_str(&format!(
+                "  {}:{}  {}\n",
+                reference.line, reference.column, reference.context
+            ));
+        }
+        output
+    }
+
+    pub fn untraced_lines(&self) -> Vec<String> {
+        self.untraced
+            .iter()
+            .map(|r| format!("{}:{}:{}  {}", r.script, r.line, r.column, r.context))
+            .collect()
+    }
+}
+
+/// A script's source before and after a rename
+#[derive(Debug)]
+pub struct RenamedScript {
+    pub path: String,
+    pub class_name: String,
+    pub source: String,
+    pub renamed: String,
+}
+
+/// Find every reference to the instance at `path`, or to `export` of the ModuleScript there
+pub fn find(
+    collected: &CollectedScripts,
+    path: &str,
+    export: Option<&str>,
+) -> Result<References, String> {
+    let tree = Tree::new(collected);
+    let Some(node) = tree.find(path) else {
+        return Err(format!(
+            "No instance at {path} in the place's services. Paths look like ReplicatedStorage.Shared.Util"
+        ));
+    };
+    let symbol = match export {
+        Some(_) if collected.nodes[node].class_name != "ModuleScript" => {
+            return Err(format!(
+                "{path} is a {}; export needs a ModuleScript",
+                collected.nodes[node].class_name
+            ))
+        }
+        Some(name) => Symbol::Export {
+            module: node,
+            name: name.to_string(),
+        },
+        None => Symbol::Instance(node),
+    };
+    Ok(find_symbol(&tree, &symbol))
+}
+
+fn find_symbol(tree: &Tree, symbol: &Symbol) -> References {
+    let mut references = match symbol {
+        Symbol::Export { module, name } => References {
+            symbol: format!("{name} exported by {}", tree.paths[*module]),
+            name: name.clone(),
+            ..References::default()
+        },
+        Symbol::Instance(node) => References {
+            symbol: tree.paths[*node].clone(),
+            name: tree.collected.nodes[*node].name.clone(),
+            instance: Some(tree.paths[*node].clone()),
+            ..References::default()
+        },
+    };
+
+    for (index, node) in tree.collected.nodes.iter().enumerate() {
+        let Some(source) = &node.source else {
+            continue;
+        };
+        let result = full_moon::parse_fallible(source, LuaVersion::luau());
+        if !result.errors().is_empty() {
+            references.syntax_errors.push(tree.paths[index].clone());
+        }
+        let ast = result.ast();
+        let mut visitor = ReferenceVisitor {
+            resolver: Resolver::new(tree, index, ast),
+            symbol,
+            name: &references.name,
+            source,
+            found: Vec::new(),
+            untraced: Vec::new(),
+        };
+        visitor.visit_ast(ast);
+        visitor.returned_table(ast);
+
+        let mut found = visitor.found;
+        let mut untraced = visitor.untraced;
+        found.sort_by_key(|r| r.start);
+        found.dedup_by_key(|r| r.start);
+        untraced.sort_by_key(|r| r.start);
+        references.found.append(&mut found);
+        references.untraced.append(&mut untraced);
+    }
+    references
+}
+
+/// Check a rename is possible and work out each changed script
+pub fn rename(
+    collected: &CollectedScripts,
+    references: &References,
+    path: &str,
+    export: Option<&str>,
+    new_name: &str,
+) -> Result<Vec<RenamedScript>, String> {
+    if new_name.is_empty() {
+        return Err("new_name can't be empty".to_string());
+    }
+    if new_name == references.name {
+        return Err(format!("{} is already named {new_name}", references.symbol));
+    }
+
+    let tree = Tree::new(collected);
+    let node = tree
+        .find(path)
+        .ok_or_else(|| format!("No instance at {path}"))?;
+    match export {
+        Some(_) => {
+            // The new name is taken if the module already defines or uses it
+            let taken = find_symbol(
+                &tree,
+                &Symbol::Export {
+                    module: node,
+                    name: new_name.to_string(),
+                },
+            );
+            if let Some(existing) = taken.found.iter().find(|r| r.script_index == node) {
+                return Err(format!(
+                    "{path} already uses {new_name} at line {}: {}",
+                    existing.line, existing.context
+                ));
+            }
+        }
+        None => {
+            if collected.nodes[node].parent.is_none() {
+                return Err(format!("{path} is a service, which can't be renamed"));
+            }
+            let parent = tree.parent(Target::Node(node));
+            if tree.has_child(&parent, new_name) {
+                return Err(format!(
+                    "{} already has a child named {new_name}, so paths to it would be ambiguous",
+                    tree.paths[collected.nodes[node].parent.unwrap_or_default()]
+                ));
+            }
+        }
+    }
+
+    let identifier = is_identifier(new_name);
+    let mut by_script: BTreeMap<usize, Vec<&Reference>> = BTreeMap::new();
+    for reference in &references.found {
+        by_script
+            .entry(reference.script_index)
+            .or_default()
+            .push(reference);
+    }
+
+    let mut renamed = Vec::new();
+    for (index, mut script_references) in by_script {
+        let node = &collected.nodes[index];
+        let source = node.source.clone().unwrap_or_default();
+        let mut text = source.clone();
+        // Replaced from the end so earlier offsets stay valid
+        script_references.sort_by_key(|r| std::cmp::Reverse(r.start));
+        for reference in script_references {
+            let (start, replacement) = match reference.site {
+                Site::Dot { .. } | Site::Key | Site::Identifier if identifier => {
+                    (reference.start, new_name.to_string())
+                }
+                Site::Dot { dot } => (dot, format!("[\"{}\"]", escape(new_name))),
+                Site::Key => (reference.start, format!("[\"{}\"]", escape(new_name))),
+                Site::Identifier => return Err(format!(
+                    "{new_name} isn't a valid Luau identifier, so it can't be used at {}:{}: {}",
+                    reference.script, reference.line, reference.context
+                )),
+                Site::String => (reference.start, escape(new_name)),
+                Site::PathSegment if new_name.contains('/') || new_name.contains('\\') => {
+                    return Err(format!(
+                        "{new_name} can't be used in the require path at {}:{}: {}",
+                        reference.script, reference.line, reference.context
+                    ))
+                }
+                Site::PathSegment => (reference.start, new_name.to_string()),
+            };
+            text.replace_range(start..reference.end, &replacement);
+        }
+        renamed.push(RenamedScript {
+            path: tree.paths[index].clone(),
+            class_name: node.class_name.clone(),
+            source,
+            renamed: text,
+        });
+    }
+    Ok(renamed)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// Escape a name for a quoted string of either quote style
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' | '"' | '\'' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A plain quoted string literal and its text. Long-bracket strings and strings with
+/// escapes are left out, since their text doesn't map onto the source one to one.
+fn quoted(expression: &Expression) -> Option<(&TokenReference, String)> {
+    match expression {
+        Expression::String(token) => quoted_token(token),
+        Expression::Parentheses { expression, .. } => quoted(expression),
+        _ => None,
+    }
+}
+
+fn quoted_token(token: &TokenReference) -> Option<(&TokenReference, String)> {
+    match token.token_type() {
+        TokenType::StringLiteral {
+            literal,
+            quote_type: StringLiteralQuoteType::Double | StringLiteralQuoteType::Single,
+            ..
+        } if !literal.contains('\\') => Some((token, literal.to_string())),
+        _ => None,
+    }
+}
+
+struct ReferenceVisitor<'a, 'b> {
+    resolver: Resolver<'a, 'b>,
+    symbol: &'b Symbol,
+    name: &'b str,
+    source: &'b str,
+    found: Vec<Reference>,
+    untraced: Vec<Reference>,
+}
+
+impl Visitor for ReferenceVisitor<'_, '_> {
+    fn visit_local_assignment_end(&mut self, assignment: &LocalAssignment) {
+        // `local M = { name = ... }` defines the module's exports when it returns M
+        if let Some(table) = self.resolver.module_table.clone() {
+            let mut values = assignment.expressions().iter();
+            for name in assignment.names() {
+                let value = values.next();
+                if name.token().to_string() == table {
+                    if let Some(Expression::TableConstructor(constructor)) = value {
+                        self.table_keys(constructor);
+                    }
+                }
+            }
+        }
+        // Assigned after the values are visited, so `local x = x.Child` reads the old x
+        self.resolver.assign_locals(assignment);
+    }
+
+    fn visit_function_call(&mut self, call: &FunctionCall) {
+        self.chain(call.prefix(), call.suffixes());
+    }
+
+    fn visit_var_expression(&mut self, var: &VarExpression) {
+        self.chain(var.prefix(), var.suffixes());
+    }
+
+    fn visit_function_declaration(&mut self, declaration: &FunctionDeclaration) {
+        let name = declaration.name();
+        let mut names = name.names().pairs();
+        let Some(first) = names.next() else {
+            return;
+        };
+        let mut target = self.resolver.name(first.value());
+        for pair in names {
+            let token = pair.value();
+            let text = token.token().to_string();
+            // `function Util["New Name"]()` isn't valid, so these can't be quoted
+            self.check(&target, token, &text, Access::Member, Site::Identifier);
+            target = self.resolver.index(target, &text);
+        }
+        if let Some(method) = name.method_name() {
+            let text = method.token().to_string();
+            self.check(&target, method, &text, Access::Member, Site::Identifier);
+        }
+    }
+}
+
+impl ReferenceVisitor<'_, '_> {
+    fn chain<'s>(&mut self, prefix: &Prefix, suffixes: impl Iterator<Item = &'s Suffix>) {
+        let suffixes: Vec<&Suffix> = suffixes.collect();
+        let (mut target, taken) = self.resolver.head(prefix, &suffixes);
+        if taken == 1 {
+            if let Suffix::Call(Call::AnonymousCall(args)) = suffixes[0] {
+                self.require_path(args);
+            }
+        }
+        for suffix in &suffixes[taken..] {
+            self.suffix(&target, suffix);
+            target = self.resolver.step(target, suffix);
+        }
+    }
+
+    fn suffix(&mut self, target: &Target, suffix: &Suffix) {
+        match suffix {
+            Suffix::Index(Index::Dot { dot, name }) => {
+                let site = Site::Dot {
+                    dot: dot.token().start_position().bytes(),
+                };
+                self.check(
+                    target,
+                    name,
+                    &name.token().to_string(),
+                    Access::Member,
+                    site,
+                );
+            }
+            Suffix::Index(Index::Brackets { expression, .. }) => {
+                if let Some((token, text)) = quoted(expression) {
+                    self.check(target, token, &text, Access::Member, Site::String);
+                }
+            }
+            Suffix::Call(Call::MethodCall(method)) => {
+                let name = method.name().token().to_string();
+                self.check(
+                    target,
+                    method.name(),
+                    &name,
+                    Access::Method,
+                    Site::Identifier,
+                );
+                if name != "WaitForChild" && name != "FindFirstChild" {
+                    return;
+                }
+                let argument = match method.args() {
+                    FunctionArgs::Parentheses { arguments, .. } => {
+                        arguments.iter().next().and_then(quoted)
+                    }
+                    FunctionArgs::String(token) => quoted_token(token),
+                    _ => None,
+                };
+                if let Some((token, text)) = argument {
+                    self.check(target, token, &text, Access::Child, Site::String);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Segments of a require-by-string path that name the instance
+    fn require_path(&mut self, args: &FunctionArgs) {
+        if !matches!(self.symbol, Symbol::Instance(_)) {
+            return;
+        }
+        let argument = match args {
+            FunctionArgs::Parentheses { arguments, .. } => arguments.iter().next().and_then(quoted),
+            FunctionArgs::String(token) => quoted_token(token),
+            _ => None,
+        };
+        let Some((token, path)) = argument else {
+            return;
+        };
+        let start = token.token().start_position().bytes() + 1;
+        let mut offset = 0;
+        for (i, segment) in path.split('/').enumerate() {
+            if i > 0 && !matches!(segment, "" | "." | "..") {
+                let parent = self.resolver.string_require(&path[..offset - 1]);
+                if segment == self.name {
+                    let reference = self.reference(token, start + offset, segment.len());
+                    self.record(&parent, Access::Child, reference, Site::PathSegment);
+                }
+            }
+            offset += segment.len() + 1;
+        }
+    }
+
+    /// Keys of the table a ModuleScript returns directly: `return { name = ... }`
+    fn returned_table(&mut self, ast: &Ast) {
+        if let Some(LastStmt::Return(ret)) = ast.nodes().last_stmt() {
+            let mut returns = ret.returns().iter();
+            if let (Some(Expression::TableConstructor(constructor)), None) =
+                (returns.next(), returns.next())
+            {
+                if self.resolver.tree.collected.nodes[self.resolver.script].class_name
+                    == "ModuleScript"
+                {
+                    self.table_keys(constructor);
+                }
+            }
+        }
+    }
+
+    fn table_keys(&mut self, constructor: &TableConstructor) {
+        let module = Target::Module(self.resolver.script);
+        for field in constructor.fields() {
+            match field {
+                Field::NameKey { key, .. } => {
+                    self.check(
+                        &module,
+                        key,
+                        &key.token().to_string(),
+                        Access::Member,
+                        Site::Key,
+                    );
+                }
+                Field::ExpressionKey { key, .. } => {
+                    if let Some((token, text)) = quoted(key) {
+                        self.check(&module, token, &text, Access::Member, Site::String);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Record `name`, used on `target`, if it is the symbol or might be
+    fn check(
+        &mut self,
+        target: &Target,
+        token: &TokenReference,
+        name: &str,
+        access: Access,
+        site: Site,
+    ) {
+        if name != self.name {
+            return;
+        }
+        let reference = match site {
+            // Inside the quotes
+            Site::String => self.reference(
+                token,
+                token.token().start_position().bytes() + 1,
+                name.len(),
+            ),
+            _ => self.reference(token, token.token().start_position().bytes(), name.len()),
+        };
+        self.record(target, access, reference, site);
+    }
+
+    fn record(&mut self, target: &Target, access: Access, mut reference: Reference, site: Site) {
+        reference.site = site;
+        // A module's members aren't children, and an instance's children aren't methods
+        let owner = match self.symbol {
+            Symbol::Export { module, .. } if access != Access::Child => Target::Module(*module),
+            Symbol::Instance(node) if access != Access::Method => {
+                self.resolver.tree.parent(Target::Node(*node))
+            }
+            _ => return,
+        };
+        if let Target::Unknown(_) = target {
+            self.untraced.push(reference);
+        } else if *target == owner {
+            self.found.push(reference);
+        }
+    }
+
+    fn reference(&self, token: &TokenReference, start: usize, len: usize) -> Reference {
+        let position = token.token().start_position();
+        let line = position.line();
+        let context = self
+            .source
+            .lines()
+            .nth(line - 1)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        Reference {
+            script: self.resolver.tree.paths[self.resolver.script].clone(),
+            line,
+            column: position.character() + start - position.bytes(),
+            context,
+            script_index: self.resolver.script,
+            start,
+            end: start + len,
+            site: Site::Identifier,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script_export::ScriptNode;
+
+    fn node(
+        name: &str,
+        class_name: &str,
+        parent: Option<usize>,
+        source: Option<&str>,
+    ) -> ScriptNode {
+        ScriptNode {
+            name: name.to_string(),
+            class_name: class_name.to_string(),
+            parent,
+            source: source.map(str::to_string),
+            run_context: None,
+            disabled: false,
+            partial: false,
+            unexported: false,
+        }
+    }
+
+    fn place(util: &str, main: &str) -> CollectedScripts {
+        CollectedScripts {
+            place_name: "Place".to_string(),
+            nodes: vec![
+                node("ReplicatedStorage", "ReplicatedStorage", None, None),
+                node("Util", "ModuleScript", Some(0), Some(util)),
+                node("Remotes", "Folder", Some(0), None),
+                node("Damage", "RemoteEvent", Some(2), None),
+                node("Heal", "RemoteEvent", Some(2), None),
+                node("ServerScriptService", "ServerScriptService", None, None),
+                node("Main", "Script", Some(5), Some(main)),
+            ],
+        }
+    }
+
+    fn rename_in(
+        collected: &CollectedScripts,
+        path: &str,
+        export: Option<&str>,
+        new_name: &str,
+    ) -> Result<Vec<(String, String)>, String> {
+        let references = find(collected, path, export)?;
+        Ok(rename(collected, &references, path, export, new_name)?
+            .into_iter()
+            .map(|script| (script.path, script.renamed))
+            .collect())
+    }
+
+    #[test]
+    fn renames_an_export_where_it_is_defined_and_used() {
+        let collected = place(
+            "local Util = {}\nfunction Util.formatTime(t)\n\treturn t\nend\nreturn Util\n",
+            "local Util = require(game:GetService(\"ReplicatedStorage\").Util)\nprint(Util.formatTime(1), formatTime)\n",
+        );
+        let renamed = rename_in(
+            &collected,
+            "ReplicatedStorage.Util",
+            Some("formatTime"),
+            "format",
+        )
+        .unwrap();
+        assert_eq!(
+            renamed,
+            vec![
+                (
+                    "ReplicatedStorage.Util".to_string(),
+                    "local Util = {}\nfunction Util.format(t)\n\treturn t\nend\nreturn Util\n".to_string()
+                ),
+                (
+                    "ServerScriptService.Main".to_string(),
+                    "local Util = require(game:GetService(\"ReplicatedStorage\").Util)\nprint(Util.format(1), formatTime)\n".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_names_that_are_not_identifiers() {
+        let collected = place(
+            "return {\n\tformatTime = function(t)\n\t\treturn t\n\tend,\n}\n",
+            "local Util = require(game.ReplicatedStorage.Util)\nprint(Util.formatTime(1))\n",
+        );
+        let renamed = rename_in(
+            &collected,
+            "ReplicatedStorage.Util",
+            Some("formatTime"),
+            "format time",
+        )
+        .unwrap();
+        assert_eq!(
+            renamed[0].1,
+            "return {\n\t[\"format time\"] = function(t)\n\t\treturn t\n\tend,\n}\n"
+        );
+        assert_eq!(
+            renamed[1].1,
+            "local Util = require(game.ReplicatedStorage.Util)\nprint(Util[\"format time\"](1))\n"
+        );
+
+        // A function declaration name has to stay an identifier
+        let collected = place(
+            "local Util = {}\nfunction Util.formatTime(t)\n\treturn t\nend\nreturn Util\n",
+            "",
+        );
+        let error = rename_in(
+            &collected,
+            "ReplicatedStorage.Util",
+            Some("formatTime"),
+            "format time",
+        )
+        .unwrap_err();
+        assert!(error.contains("isn't a valid Luau identifier"), "{error}");
+    }
+
+    #[test]
+    fn renames_an_instance_in_paths_and_child_lookups() {
+        let main = "local Remotes = game:GetService(\"ReplicatedStorage\"):WaitForChild(\"Remotes\")\nRemotes.Damage:FireAllClients()\nRemotes:WaitForChild('Damage'):FireAllClients()\n";
+        let collected = place("return {}\n", main);
+        let renamed =
+            rename_in(&collected, "ReplicatedStorage.Remotes.Damage", None, "Hit").unwrap();
+        assert_eq!(
+            renamed,
+            vec![(
+                "ServerScriptService.Main".to_string(),
+                main.replace("Damage", "Hit")
+            )]
+        );
+
+        let error =
+            rename_in(&collected, "ReplicatedStorage.Remotes.Damage", None, "Heal").unwrap_err();
+        assert!(error.contains("already has a child named Heal"), "{error}");
+    }
+}
@@ -0,0 +1,154 @@
+//! Text search across script sources for the search_scripts tool.
+//!
+//! Sources are fetched from the plugin in one `CollectScripts` call and matched here with
+//! the regex crate, line by line, so callers get standard regex syntax rather than Luau
+//! patterns. Output follows grep's conventions: `line:` for matches, `line-` for context.
+
+use crate::script_export::CollectedScripts;
+use regex::{Regex, RegexBuilder};
+
+pub const DEFAULT_CONTEXT_LINES: usize = 2;
+pub const MAX_CONTEXT_LINES: usize = 10;
+pub const DEFAULT_MAX_RESULTS: usize = 100;
+pub const MAX_RESULTS: usize = 1000;
+
+/// Lines longer than this (usually generated data) are cut in the output
+const MAX_LINE_LENGTH: usize = 300;
+
+/// Script classes search_scripts can filter on
+pub const SCRIPT_CLASSES: [&str; 3] = ["Script", "LocalScript", "ModuleScript"];
+
+pub struct ScriptSearch {
+    matcher: Regex,
+    context_lines: usize,
+    max_results: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchResults {
+    pub scripts_searched: usize,
+    pub scripts_matched: usize,
+    pub matches: usize,
+    /// Whether the search stopped at max_results
+    pub truncated: bool,
+    pub output: String,
+}
+
+impl ScriptSearch {
+    pub fn new(
+        pattern: &str,
+        regex: bool,
+        case_sensitive: bool,
+        context_lines: usize,
+        max_results: usize,
+    ) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("pattern is empty".to_string());
+        }
+        if context_lines > MAX_CONTEXT_LINES {
+            return Err(format!("context_lines must be at most {MAX_CONTEXT_LINES}"));
+        }
+        if max_results == 0 || max_results > MAX_RESULTS {
+            return Err(format!("max_results must be between 1 and {MAX_RESULTS}"));
+        }
+        let source = if regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let matcher = RegexBuilder::new(&source)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| format!("Invalid regex: {e}"))?;
+        Ok(Self {
+            matcher,
+            context_lines,
+            max_results,
+        })
+    }
+
+    /// Search every collected script whose class passes the filter, in tree order
+    pub fn run(&self, collected: &CollectedScripts, classes: &[String]) -> SearchResults {
+        let mut results = SearchResults::default();
+        let mut paths: Vec<String> = Vec::with_capacity(collected.nodes.len());
+        for node in &collected.nodes {
+            let path = match node.parent {
+                Some(parent) => format!("{}.{}", paths[parent], node.name),
+                None => node.name.clone(),
+            };
+            paths.push(path);
+
+            let Some(source) = &node.source else {
+                continue;
+            };
+            if !classes.is_empty() && !classes.contains(&node.class_name) {
+                continue;
+            }
+            results.scripts_searched += 1;
+            if results.truncated {
+                continue;
+            }
+            self.search_script(
+                &format!("{} ({})", paths.last().unwrap(), node.class_name),
+                source,
+                &mut results,
+            );
+        }
+        results
+    }
+
+    fn search_script(&self, heading: &str, source: &str, results: &mut SearchResults) {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut matched = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if self.matcher.is_match(line) {
+                if results.matches == self.max_results {
+                    results.truncated = true;
+                    break;
+                }
+                results.matches += 1;
+                matched.push(index);
+            }
+        }
+        if matched.is_empty() {
+            return;
+        }
+        results.scripts_matched += 1;
+        results.output.push_str(&format!("\n{heading}\n"));
+
+        // Print each match with its context, merging windows that touch and marking gaps
+        let mut printed_until = 0;
+        for (position, &index) in matched.iter().enumerate() {
+            let start = index.saturating_sub(self.context_lines);
+            let end = (index + self.context_lines + 1).min(lines.len());
+            if position > 0 && start > printed_until {
+                results.output.push_str("  --\n");
+            }
+            let first = start.max(printed_until);
+            for (line, text) in lines[first..end]
+                .iter()
+                .enumerate()
+                .map(|(i, t)| (first + i, t))
+            {
+                let separator = if matched.binary_search(&line).is_ok() {
+                    ':'
+                } else {
+                    '-'
+                };
+                results
+                    .output
+                    .push_str(&format!("  {}{separator} {}\n", line + 1, shorten(text)));
+            }
+            printed_until = end;
+        }
+    }
+}
+
+fn shorten(line: &str) -> String {
+    if line.chars().count() <= MAX_LINE_LENGTH {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(MAX_LINE_LENGTH).collect();
+    short.push_str("...");
+    short
+}
@@ -0,0 +1,152 @@
+//! Shared types for the terrain tools.
+//!
+//! Terrain is stored in 4-stud voxels, and the region APIs (`ReadVoxels`, `ReplaceMaterial`,
+//! `FillRegion`) work on grid-aligned regions. Regions are aligned and size-checked here
+//! so an oversized request fails fast instead of stalling Studio.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+/// Size of one terrain voxel in studs
+pub const VOXEL_SIZE: f64 = 4.0;
+
+// Roblox rejects ReadVoxels/WriteVoxels regions larger than this many voxels
+pub const MAX_REGION_VOXELS: u64 = 4_194_304;
+
+// Terrain spans at most 32k voxels in each direction
+const MAX_COORDINATE: f64 = 131_072.0;
+
+/// Materials that Terrain accepts (a subset of Enum.Material)
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone, Copy, PartialEq, Eq)]
+pub enum TerrainMaterial {
+    Air,
+    Asphalt,
+    Basalt,
+    Brick,
+    Cobblestone,
+    Concrete,
+    CrackedLava,
+    Glacier,
+    Grass,
+    Ground,
+    Ice,
+    LeafyGrass,
+    Limestone,
+    Mud,
+    Pavement,
+    Rock,
+    Salt,
+    Sand,
+    Sandstone,
+    Slate,
+    Snow,
+    Water,
+    WoodPlanks,
+}
+
+impl TerrainMaterial {
+    /// Default Terrain.MaterialColors, used for material map images
+    pub fn map_color(self) -> [u8; 3] {
+        match self {
+            Self::Air => [0, 0, 0],
+            Self::Asphalt => [115, 123, 107],
+            Self::Basalt => [30, 30, 37],
+            Self::Brick => [138, 86, 62],
+            Self::Cobblestone => [132, 123, 90],
+            Self::Concrete => [127, 102, 63],
+            Self::CrackedLava => [232, 156, 74],
+            Self::Glacier => [101, 176, 234],
+            Self::Grass => [106, 127, 63],
+            Self::Ground => [102, 92, 59],
+            Self::Ice => [129, 194, 224],
+            Self::LeafyGrass => [115, 132, 74],
+            Self::Limestone => [206, 173, 148],
+            Self::Mud => [58, 46, 36],
+            Self::Pavement => [148, 148, 140],
+            Self::Rock => [102, 108, 111],
+            Self::Salt => [198, 189, 181],
+            Self::Sand => [143, 126, 95],
+            Self::Sandstone => [137, 90, 71],
+            Self::Slate => [63, 127, 107],
+            Self::Snow => [195, 199, 218],
+            Self::Water => [12, 84, 92],
+            Self::WoodPlanks => [139, 109, 79],
+        }
+    }
+}
+
+/// Shapes supported by fill_terrain, matching Terrain:FillBlock/FillBall/FillCylinder/FillWedge
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TerrainShape {
+    Block,
+    Ball,
+    Cylinder,
+    Wedge,
+}
+
+/// An axis-aligned box in world space, given by two opposite corners
+#[derive(Debug, Deserialize, Serialize, schemars::JsonSchema, Clone, Copy)]
+pub struct TerrainRegion {
+    /// Minimum corner [x, y, z] in studs
+    pub min: [f64; 3],
+    /// Maximum corner [x, y, z] in studs
+    pub max: [f64; 3],
+}
+
+impl TerrainRegion {
+    pub fn from_center_size(center: [f64; 3], size: [f64; 3]) -> Self {
+        Self {
+            min: std::array::from_fn(|i| center[i] - size[i] / 2.0),
+            max: std::array::from_fn(|i| center[i] + size[i] / 2.0),
+        }
+    }
+
+    /// Expand outward to the voxel grid, the same way Region3:ExpandToGrid does
+    pub fn aligned(&self) -> Self {
+        Self {
+            min: self.min.map(|c| (c / VOXEL_SIZE).floor() * VOXEL_SIZE),
+            max: self.max.map(|c| (c / VOXEL_SIZE).ceil() * VOXEL_SIZE),
+        }
+    }
+
+    /// Number of voxels covered once aligned to the grid
+    pub fn voxel_count(&self) -> u64 {
+        let aligned = self.aligned();
+        (0..3)
+            .map(|i| ((aligned.max[i] - aligned.min[i]) / VOXEL_SIZE) as u64)
+            .product()
+    }
+
+    /// Check the corners without the voxel limit, for tools that split the region into chunks
+    pub fn validate_bounds(&self) -> Result<(), String> {
+        if self.min.iter().chain(&self.max).any(|c| !c.is_finite()) {
+            return Err("Region corners must be finite numbers".to_string());
+        }
+        if self
+            .min
+            .iter()
+            .chain(&self.max)
+            .any(|c| c.abs() > MAX_COORDINATE)
+        {
+            return Err(format!(
+                "Region corners must be within ±{MAX_COORDINATE} studs"
+            ));
+        }
+        if (0..3).any(|i| self.min[i] >= self.max[i]) {
+            return Err("Region min must be less than max on every axis".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.validate_bounds()?;
+        let voxels = self.voxel_count();
+        if voxels > MAX_REGION_VOXELS {
+            return Err(format!(
+                "Region covers {voxels} voxels; the limit is {MAX_REGION_VOXELS} (e.g. 1024x256x1024 studs). Split it into smaller regions."
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+//! Results of a run_tests call and their JUnit XML form.
+//!
+//! The plugin's TestRunner reports TestEZ and Jest-Lua runs in one shape: every test case
+//! with its spec, status and failure message, plus errors that belong to no single test,
+//! like a spec that fails to load or a failing beforeAll.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The plugin's TestRunner, for runs that don't go through the plugin
+pub const TEST_RUNNER_SOURCE: &str = include_str!("../plugin/src/TestRunner.luau");
+
+/// Longest failure message quoted in a tool result; the JUnit report keeps the whole text
+const MAX_MESSAGE_LENGTH: usize = 4000;
+
+#[derive(Debug, Deserialize)]
+pub struct TestCase {
+    /// Describe blocks and test title, space separated
+    pub name: String,
+    pub spec: String,
+    /// "passed", "failed" or "skipped"
+    pub status: String,
+    /// Failure messages with their stack traces
+    pub message: Option<String>,
+    /// Seconds, where the framework reports it
+    pub duration: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TestReport {
+    /// "TestEZ" or "Jest"
+    pub framework: String,
+    pub framework_path: String,
+    /// TestEZ spec modules, or Jest project roots
+    pub specs: Vec<String>,
+    pub tests: Vec<TestCase>,
+    pub errors: Vec<String>,
+}
+
+impl TestReport {
+    pub fn count(&self, status: &str) -> usize {
+        self.tests
+            .iter()
+            .filter(|test| test.status == status)
+            .count()
+    }
+
+    /// A summary line, then each error and failure with its message
+    pub fn summary(&self, context: &str) -> String {
+        let mut text = format!(
+            "[SUCCESS] {} passed, {} failed, {} skipped in {} {} ({} at {}, {context} mode)",
+            self.count("passed"),
+            self.count("failed"),
+            self.count("skipped"),
+            self.specs.len(),
+            if self.framework == "Jest" {
+                "project(s)"
+            } else {
+                "spec(s)"
+            },
+            self.framework,
+            self.framework_path
+        );
+        if !self.errors.is_empty() {
+            text.push_str(&format!(
+                "\n\nErrors outside tests ({}):",
+                self.errors.len()
+            ));
+            for error in &self.errors {
+                text.push_str(&format!("\n{}", indent(&shorten(error), "  ")));
+            }
+        }
+        let failures: Vec<&TestCase> = self.tests.iter().filter(|t| t.status == "failed").collect();
+        if !failures.is_empty() {
+            text.push_str(&format!("\n\nFailures ({}):", failures.len()));
+            for test in failures {
+                text.push_str(&format!("\n  {} ({})", test.name, test.spec));
+                if let Some(message) = &test.message {
+                    text.push_str(&format!("\n{}", indent(&shorten(message), "    ")));
+                }
+            }
+        }
+        text
+    }
+
+    /// One testsuite per spec, with errors outside tests as a suite of their own
+    pub fn to_junit(&self) -> String {
+        let mut suites: BTreeMap<&str, Vec<&TestCase>> = BTreeMap::new();
+        for test in &self.tests {
+            suites.entry(&test.spec).or_default().push(test);
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" errors=\"{}\">\n",
+            escape(&self.framework),
+            self.tests.len(),
+            self.count("failed"),
+            self.count("skipped"),
+            self.errors.len()
+        ));
+        for (spec, tests) in suites {
+            let failures = tests.iter().filter(|t| t.status == "failed").count();
+            let skipped = tests.iter().filter(|t| t.status == "skipped").count();
+            let time = tests
+                .iter()
+                .filter_map(|t| t.duration)
+                .fold(0.0, |total, duration| total + duration);
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" skipped=\"{skipped}\" errors=\"0\" time=\"{time:.3}\">\n",
+                escape(spec),
+                tests.len()
+            ));
+            for test in tests {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\"",
+                    escape(spec),
+                    escape(&test.name)
+                ));
+                if let Some(duration) = test.duration {
+                    xml.push_str(&format!(" time=\"{duration:.3}\""));
+                }
+                match test.status.as_str() {
+                    "failed" => {
+                        let message = test.message.as_deref().unwrap_or_default();
+                        xml.push_str(&format!(
+                            ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                            escape(message.lines().next().unwrap_or_default()),
+                            escape(message)
+                        ));
+                    }
+                    "skipped" => xml.push_str(">\n      <skipped/>\n    </testcase>\n"),
+                    _ => xml.push_str("/>\n"),
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        if !self.errors.is_empty() {
+            xml.push_str(&format!(
+                "  <testsuite name=\"errors\" tests=\"{0}\" failures=\"0\" skipped=\"0\" errors=\"{0}\">\n",
+                self.errors.len()
+            ));
+            for (index, error) in self.errors.iter().enumerate() {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"errors\" name=\"error {}\">\n      <error message=\"{}\">{}</error>\n    </testcase>\n",
+                    index + 1,
+                    escape(error.lines().next().unwrap_or_default()),
+                    escape(error)
+                ));
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    /// Write the JUnit report, creating the file's directory if needed
+    pub fn write_junit(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        std::fs::write(path, self.to_junit())
+            .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+    }
+}
+
+fn indent(text: &str, prefix: &str) -> String {
+    text.lines()
+        .map(|line| format!("{prefix}{line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= MAX_MESSAGE_LENGTH {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(MAX_MESSAGE_LENGTH).collect();
+    short.push_str("\n... (truncated)");
+    short
+}
+
+/// Escape text for XML, dropping control characters XML 1.0 can't hold. Attributes only
+/// ever get a single line.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\t' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}